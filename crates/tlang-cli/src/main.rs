@@ -1,11 +1,102 @@
-use std::{io, io::Write};
+use std::{
+    env, fs, io,
+    io::Write,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
 
-fn run(input: &str, env: &mut tlang::Env) -> Result<Option<tlang::Val>, String> {
-    let parse = tlang::parse(input).map_err(|msg| format!("Parse error: {}", msg))?;
+use tlang::{
+    limits::Limits,
+    lint::{Level, LintConfig},
+    optimize::Passes,
+    typeck::TypeEnv,
+};
 
-    let evaluated = parse
-        .eval(env)
-        .map_err(|msg| format!("Evaluation error: {}", msg))?;
+/// Bindings kept around between the inputs of a REPL session.
+#[derive(Default)]
+struct Session {
+    env: tlang::Env,
+    type_env: TypeEnv,
+    lints: LintConfig,
+    passes: Passes,
+    backend: tlang::Backend,
+    /// Whether an input is being evaluated, which is what Ctrl-C interrupts.
+    evaluating: Arc<AtomicBool>,
+}
+
+fn run(input: &str, session: &mut Session) -> Result<Option<tlang::Val>, String> {
+    let mut parse = tlang::parse(input);
+
+    if !parse.errors().is_empty() {
+        let msgs: Vec<_> = parse
+            .errors()
+            .iter()
+            .map(|error| format!("Parse error: {}", error))
+            .collect();
+
+        return Err(msgs.join("\n"));
+    }
+
+    let (denied, warnings): (Vec<_>, Vec<_>) = parse
+        .lint(&session.lints)
+        .into_iter()
+        .partition(tlang::lint::Warning::is_denied);
+
+    for warning in warnings {
+        eprintln!("Lint: {}", warning);
+    }
+
+    if !denied.is_empty() {
+        let msgs: Vec<_> = denied
+            .iter()
+            .map(|warning| format!("Lint: {}", warning))
+            .collect();
+
+        return Err(msgs.join("\n"));
+    }
+
+    if let Err(errors) = parse.resolve(&session.env) {
+        let msgs: Vec<_> = errors
+            .iter()
+            .map(|error| format!("Resolve error: {}", error))
+            .collect();
+
+        return Err(msgs.join("\n"));
+    }
+
+    // What the input defines is only kept if it is evaluated successfully too: the types of its
+    // bindings are only added then, and its values are removed again otherwise.
+    let mut type_env = session.type_env.clone();
+
+    if let Err(errors) = parse.typeck(&mut type_env) {
+        let msgs: Vec<_> = errors
+            .iter()
+            .map(|error| format!("Type error: {}", error))
+            .collect();
+
+        return Err(msgs.join("\n"));
+    }
+
+    parse.optimize(&session.passes);
+
+    let checkpoint = session.env.checkpoint();
+
+    // A Ctrl-C that came in as the previous input finished isn't meant for this one.
+    session.env.interrupt_handle().reset();
+    session.evaluating.store(true, Ordering::Relaxed);
+    let evaluated = parse.eval_with(&mut session.env, session.backend);
+    session.evaluating.store(false, Ordering::Relaxed);
+
+    let evaluated = evaluated.map_err(|error| {
+        session.env.restore(checkpoint);
+        format_eval_error(&error, input)
+    })?;
+
+    session.type_env = type_env;
 
     if evaluated == tlang::Val::Unit {
         Ok(None)
@@ -14,13 +105,170 @@ fn run(input: &str, env: &mut tlang::Env) -> Result<Option<tlang::Val>, String>
     }
 }
 
+/// The message of `error`, followed by one line per frame of its trace, innermost first. Runs of
+/// the same frame, as left by deep recursion, are shown once.
+fn format_eval_error(error: &tlang::EvalError, input: &str) -> String {
+    let mut msg = format!("Evaluation error: {}", error);
+
+    let mut frames = error
+        .trace
+        .iter()
+        .map(|frame| {
+            let function = frame.function.as_deref().unwrap_or("<top level>");
+
+            // Frames in functions defined by earlier inputs don't refer to this one.
+            match line_col(input, frame.span.start) {
+                Some((line, column)) => format!("{} ({}:{})", function, line, column),
+                None => function.to_string(),
+            }
+        })
+        .peekable();
+
+    while let Some(frame) = frames.next() {
+        msg.push_str(&format!("\n    at {}", frame));
+
+        let mut repeated = 0;
+        while frames.next_if_eq(&frame).is_some() {
+            repeated += 1;
+        }
+
+        if repeated > 0 {
+            msg.push_str(&format!("\n    ... repeated {} more time(s)", repeated));
+        }
+    }
+
+    msg
+}
+
+/// The 1-based line and column of the byte `offset` in `input`, if it is within it.
+fn line_col(input: &str, offset: usize) -> Option<(usize, usize)> {
+    let before = input.get(..offset)?;
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+
+    Some((
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    ))
+}
+
+/// Sets the level of the lints given with `-A <lint>`, `-W <lint>` or `-D <lint>` (allow, warn or
+/// deny), disables the optimization passes given with `--no-opt <pass>`, and picks the VM backend
+/// if `--vm` is given. Returns the remaining arguments.
+fn parse_args(
+    args: impl IntoIterator<Item = String>,
+    session: &mut Session,
+) -> Result<Vec<String>, String> {
+    let mut args = args.into_iter();
+    let mut rest = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let level = match arg.as_str() {
+            "-A" => Level::Allow,
+            "-W" => Level::Warn,
+            "-D" => Level::Deny,
+            "--no-opt" => {
+                let pass = args.next().ok_or_else(|| {
+                    format!("expected the name of an optimization pass after '{}'", arg)
+                })?;
+
+                session.passes.set(pass.parse()?, false);
+                continue;
+            }
+            "--vm" => {
+                session.backend = tlang::Backend::Vm;
+                continue;
+            }
+            _ => {
+                rest.push(arg);
+                continue;
+            }
+        };
+
+        let lint = args
+            .next()
+            .ok_or_else(|| format!("expected the name of a lint after '{}'", arg))?;
+
+        session.lints.set(lint.parse()?, level);
+    }
+
+    Ok(rest)
+}
+
+/// Makes Ctrl-C interrupt the input being evaluated, which undoes what the input defined like any
+/// other error, or exit if there is none.
+fn handle_ctrl_c(session: &Session) {
+    let interrupt = session.env.interrupt_handle();
+    let evaluating = Arc::clone(&session.evaluating);
+
+    let result = ctrlc::set_handler(move || {
+        if evaluating.load(Ordering::Relaxed) {
+            interrupt.interrupt();
+        } else {
+            process::exit(130);
+        }
+    });
+
+    if let Err(error) = result {
+        eprintln!("Cannot handle Ctrl-C: {}", error);
+    }
+}
+
+fn run_file(path: &str, mut session: Session) -> io::Result<()> {
+    let input = fs::read_to_string(path)?;
+
+    match run(&input, &mut session) {
+        Ok(Some(val)) => println!("{}", val),
+        Ok(None) => {}
+        Err(msg) => {
+            eprintln!("{}", msg);
+            process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// The stack inputs are evaluated on, which is far larger than that of the main thread so that
+/// deeply recursive functions can be run.
+const STACK_SIZE: usize = 64 << 20;
+
 fn main() -> io::Result<()> {
+    let cli = thread::Builder::new().stack_size(STACK_SIZE).spawn(cli)?;
+
+    match cli.join() {
+        Ok(result) => result,
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+fn cli() -> io::Result<()> {
+    let mut session = Session::default();
+
+    // Half of the stack is left for parsing and checking the input, and for the host itself.
+    session.env.set_limits(Limits {
+        stack: STACK_SIZE / 2,
+        ..Limits::default()
+    });
+
+    let args = match parse_args(env::args().skip(1), &mut session) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            process::exit(2);
+        }
+    };
+
+    handle_ctrl_c(&session);
+
+    if let Some(path) = args.first() {
+        return run_file(path, session);
+    }
+
     let stdin = io::stdin();
     let mut stdout = io::stdout();
     let mut stderr = io::stderr();
 
     let mut input = String::new();
-    let mut env = tlang::Env::default();
 
     loop {
         write!(stdout, "→ ")?;
@@ -28,7 +276,7 @@ fn main() -> io::Result<()> {
 
         stdin.read_line(&mut input)?;
 
-        match run(input.trim(), &mut env) {
+        match run(input.trim(), &mut session) {
             Ok(Some(val)) => writeln!(stdout, "{}", val)?,
             Err(msg) => writeln!(stderr, "{}", msg)?,
             _ => {}
@@ -0,0 +1,68 @@
+//! The typed abstract syntax tree, lowered from the lossless [`syntax`](crate::syntax) tree of an
+//! error-free [`Parse`](crate::Parse).
+//!
+//! Unlike the syntax tree, the AST doesn't keep whitespace, comments or punctuation around, which
+//! makes it the more convenient representation for analyses and transformations. Both can be
+//! written by implementing [`Visitor`] or [`VisitorMut`] and only overriding the methods for the
+//! nodes of interest; the default implementations walk into every child node.
+//!
+//! Expressions and statements are stored in the [`Arena`] of their [`SourceFile`], and refer to
+//! their children by [`ExprId`] and [`StmtId`]. Those IDs stay the same for as long as the AST is
+//! around, so anything computed about a node can be kept in a side table keyed by its ID.
+//!
+//! ```
+//! use tlang::{
+//!     ast::{BindingUsage, Visitor},
+//!     Symbol,
+//! };
+//!
+//! #[derive(Default)]
+//! struct UsedNames(Vec<Symbol>);
+//!
+//! impl Visitor for UsedNames {
+//!     fn visit_binding_usage(&mut self, binding_usage: &BindingUsage) {
+//!         self.0.push(binding_usage.name.clone());
+//!     }
+//! }
+//!
+//! let parse = tlang::parse("let a = 1\n{ let b = a; b }");
+//!
+//! let mut used_names = UsedNames::default();
+//! used_names.visit_source_file(parse.source_file().unwrap());
+//!
+//! assert_eq!(used_names.0, ["a", "b"]);
+//! ```
+
+pub use arena::{Arena, ExprId, StmtId};
+pub use visit::{
+    walk_binding_def, walk_block, walk_call, walk_catch, walk_expr, walk_field_def,
+    walk_field_init, walk_fn_def, walk_fn_sig, walk_if, walk_impl_def, walk_method_call,
+    walk_param, walk_source_file, walk_stmt, walk_struct_def, walk_struct_lit, walk_trait_def,
+    walk_try_catch, walk_type_expr, Visitor,
+};
+pub use visit_mut::{
+    walk_binding_def_mut, walk_block_mut, walk_call_mut, walk_catch_mut, walk_expr_mut,
+    walk_field_def_mut, walk_field_init_mut, walk_fn_def_mut, walk_fn_sig_mut, walk_if_mut,
+    walk_impl_def_mut, walk_method_call_mut, walk_param_mut, walk_source_file_mut, walk_stmt_mut,
+    walk_struct_def_mut, walk_struct_lit_mut, walk_trait_def_mut, walk_try_catch_mut,
+    walk_type_expr_mut, VisitorMut,
+};
+
+pub use crate::{
+    binding_def::BindingDef,
+    expr::{
+        BindingUsage, Block, Call, Catch, Expr, ExprKind, FieldInit, If, MethodCall, Number, Op,
+        Slot, StructLit, TryCatch, UnaryOp,
+    },
+    fn_def::{FnDef, Param},
+    impl_def::ImplDef,
+    source_file::SourceFile,
+    stmt::Stmt,
+    struct_def::{FieldDef, StructDef},
+    trait_def::{FnSig, TraitDef},
+    type_expr::{TypeExpr, TypeExprKind, TypeParam},
+};
+
+mod arena;
+mod visit;
+mod visit_mut;
@@ -0,0 +1,145 @@
+use std::{
+    mem,
+    ops::{Index, IndexMut},
+};
+
+use super::{Expr, ExprKind, Stmt};
+
+/// Where the expressions and statements of an AST are stored. Nodes refer to their children by
+/// ID rather than owning them, so that the whole tree takes a couple of allocations, and so that
+/// each node has an identity that information about it can be attached to in a side table.
+///
+/// IDs are handed out in the order nodes are allocated, which is children before their parents
+/// when lowering. Replacing a node keeps its ID, while nodes that are no longer referred to stay
+/// in the arena until it is dropped.
+///
+/// ```
+/// use tlang::ast::{Arena, ExprKind, Number, Op};
+///
+/// let mut arena = Arena::default();
+/// let lhs = arena.alloc_expr(ExprKind::Number(Number(1)));
+/// let rhs = arena.alloc_expr(ExprKind::Number(Number(2)));
+/// let sum = arena.alloc_expr(ExprKind::Operation { lhs, rhs, op: Op::Add });
+///
+/// assert!(matches!(arena[sum].kind, ExprKind::Operation { op: Op::Add, .. }));
+/// assert_eq!(arena.exprs().map(|(id, _)| id).collect::<Vec<_>>(), [lhs, rhs, sum]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Arena {
+    exprs: Vec<Expr>,
+    stmts: Vec<Stmt>,
+}
+
+/// The identity of an expression in an [`Arena`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExprId(u32);
+
+/// The identity of a statement in an [`Arena`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StmtId(u32);
+
+impl ExprId {
+    /// The position of the expression in its arena, which side tables can be indexed by.
+    #[inline]
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl StmtId {
+    #[inline]
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Arena {
+    pub fn alloc_expr(&mut self, expr: impl Into<Expr>) -> ExprId {
+        self.exprs.push(expr.into());
+        ExprId(id(self.exprs.len() - 1))
+    }
+
+    pub fn alloc_stmt(&mut self, stmt: Stmt) -> StmtId {
+        self.stmts.push(stmt);
+        StmtId(id(self.stmts.len() - 1))
+    }
+
+    /// Takes the expression out of the arena while `f` changes it, so that `f` can change its
+    /// children in the arena too. In the meantime, the expression's place holds a dummy.
+    pub(crate) fn with_expr<T>(
+        &mut self,
+        id: ExprId,
+        f: impl FnOnce(&mut Self, &mut Expr) -> T,
+    ) -> T {
+        let mut expr = mem::replace(&mut self[id], ExprKind::Bool(false).into());
+        let result = f(self, &mut expr);
+        self[id] = expr;
+
+        result
+    }
+
+    /// Like [`with_expr`](Self::with_expr), but for a statement.
+    pub(crate) fn with_stmt<T>(
+        &mut self,
+        id: StmtId,
+        f: impl FnOnce(&mut Self, &mut Stmt) -> T,
+    ) -> T {
+        let mut stmt = mem::replace(&mut self[id], Stmt::Expr(ExprId(u32::MAX)));
+        let result = f(self, &mut stmt);
+        self[id] = stmt;
+
+        result
+    }
+
+    /// Every expression in the arena, in the order they were allocated.
+    pub fn exprs(&self) -> impl Iterator<Item = (ExprId, &Expr)> {
+        self.exprs
+            .iter()
+            .enumerate()
+            .map(|(idx, expr)| (ExprId(id(idx)), expr))
+    }
+
+    /// Every statement in the arena, in the order they were allocated.
+    pub fn stmts(&self) -> impl Iterator<Item = (StmtId, &Stmt)> {
+        self.stmts
+            .iter()
+            .enumerate()
+            .map(|(idx, stmt)| (StmtId(id(idx)), stmt))
+    }
+}
+
+fn id(idx: usize) -> u32 {
+    u32::try_from(idx).expect("too many AST nodes")
+}
+
+impl Index<ExprId> for Arena {
+    type Output = Expr;
+
+    #[inline]
+    fn index(&self, id: ExprId) -> &Expr {
+        &self.exprs[id.index()]
+    }
+}
+
+impl IndexMut<ExprId> for Arena {
+    #[inline]
+    fn index_mut(&mut self, id: ExprId) -> &mut Expr {
+        &mut self.exprs[id.index()]
+    }
+}
+
+impl Index<StmtId> for Arena {
+    type Output = Stmt;
+
+    #[inline]
+    fn index(&self, id: StmtId) -> &Stmt {
+        &self.stmts[id.index()]
+    }
+}
+
+impl IndexMut<StmtId> for Arena {
+    #[inline]
+    fn index_mut(&mut self, id: StmtId) -> &mut Stmt {
+        &mut self.stmts[id.index()]
+    }
+}
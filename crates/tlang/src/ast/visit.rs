@@ -0,0 +1,521 @@
+use super::{
+    Arena, BindingDef, BindingUsage, Block, Call, Catch, ExprId, ExprKind, FieldDef, FieldInit,
+    FnDef, FnSig, If, ImplDef, MethodCall, Number, Op, Param, SourceFile, Stmt, StmtId, StructDef,
+    StructLit, TraitDef, TryCatch, TypeExpr, TypeExprKind, TypeParam, UnaryOp,
+};
+
+/// Read-only traversal of the AST.
+///
+/// Every method defaults to visiting the children of its node (through the matching `walk_*`
+/// function), so an implementation that overrides a method and still wants to reach the nodes
+/// below has to call the `walk_*` function itself.
+///
+/// Statements and expressions are visited by their ID, and every method for a node that can
+/// contain them is passed the [`Arena`] they are in.
+pub trait Visitor {
+    fn visit_source_file(&mut self, source_file: &SourceFile) {
+        walk_source_file(self, source_file);
+    }
+
+    fn visit_stmt(&mut self, arena: &Arena, stmt: StmtId) {
+        walk_stmt(self, arena, stmt);
+    }
+
+    fn visit_binding_def(&mut self, arena: &Arena, binding_def: &BindingDef) {
+        walk_binding_def(self, arena, binding_def);
+    }
+
+    fn visit_fn_def(&mut self, arena: &Arena, fn_def: &FnDef) {
+        walk_fn_def(self, arena, fn_def);
+    }
+
+    fn visit_type_param(&mut self, _type_param: &TypeParam) {}
+
+    fn visit_param(&mut self, param: &Param) {
+        walk_param(self, param);
+    }
+
+    fn visit_struct_def(&mut self, struct_def: &StructDef) {
+        walk_struct_def(self, struct_def);
+    }
+
+    fn visit_field_def(&mut self, field_def: &FieldDef) {
+        walk_field_def(self, field_def);
+    }
+
+    fn visit_trait_def(&mut self, trait_def: &TraitDef) {
+        walk_trait_def(self, trait_def);
+    }
+
+    fn visit_fn_sig(&mut self, fn_sig: &FnSig) {
+        walk_fn_sig(self, fn_sig);
+    }
+
+    fn visit_impl_def(&mut self, arena: &Arena, impl_def: &ImplDef) {
+        walk_impl_def(self, arena, impl_def);
+    }
+
+    fn visit_expr(&mut self, arena: &Arena, expr: ExprId) {
+        walk_expr(self, arena, expr);
+    }
+
+    fn visit_number(&mut self, _number: &Number) {}
+
+    fn visit_op(&mut self, _op: &Op) {}
+
+    fn visit_unary_op(&mut self, _op: &UnaryOp) {}
+
+    fn visit_binding_usage(&mut self, _binding_usage: &BindingUsage) {}
+
+    fn visit_type_expr(&mut self, ty: &TypeExpr) {
+        walk_type_expr(self, ty);
+    }
+
+    fn visit_block(&mut self, arena: &Arena, block: &Block) {
+        walk_block(self, arena, block);
+    }
+
+    fn visit_if(&mut self, arena: &Arena, if_expr: &If) {
+        walk_if(self, arena, if_expr);
+    }
+
+    fn visit_call(&mut self, arena: &Arena, call: &Call) {
+        walk_call(self, arena, call);
+    }
+
+    fn visit_struct_lit(&mut self, arena: &Arena, struct_lit: &StructLit) {
+        walk_struct_lit(self, arena, struct_lit);
+    }
+
+    fn visit_method_call(&mut self, arena: &Arena, method_call: &MethodCall) {
+        walk_method_call(self, arena, method_call);
+    }
+
+    fn visit_field_init(&mut self, arena: &Arena, field_init: &FieldInit) {
+        walk_field_init(self, arena, field_init);
+    }
+
+    fn visit_try_catch(&mut self, arena: &Arena, try_catch: &TryCatch) {
+        walk_try_catch(self, arena, try_catch);
+    }
+
+    fn visit_catch(&mut self, arena: &Arena, catch: &Catch) {
+        walk_catch(self, arena, catch);
+    }
+}
+
+pub fn walk_source_file<V: Visitor + ?Sized>(visitor: &mut V, source_file: &SourceFile) {
+    for &stmt in &source_file.stmts {
+        visitor.visit_stmt(source_file.arena(), stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, arena: &Arena, stmt: StmtId) {
+    match &arena[stmt] {
+        Stmt::BindingDef(binding_def) => visitor.visit_binding_def(arena, binding_def),
+        Stmt::FnDef(fn_def) => visitor.visit_fn_def(arena, fn_def),
+        Stmt::StructDef(struct_def) => visitor.visit_struct_def(struct_def),
+        Stmt::TraitDef(trait_def) => visitor.visit_trait_def(trait_def),
+        Stmt::ImplDef(impl_def) => visitor.visit_impl_def(arena, impl_def),
+        Stmt::Expr(expr) | Stmt::Semi(expr) => visitor.visit_expr(arena, *expr),
+    }
+}
+
+pub fn walk_binding_def<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena,
+    binding_def: &BindingDef,
+) {
+    if let Some(ty) = &binding_def.ty {
+        visitor.visit_type_expr(ty);
+    }
+
+    visitor.visit_expr(arena, binding_def.val);
+}
+
+pub fn walk_fn_def<V: Visitor + ?Sized>(visitor: &mut V, arena: &Arena, fn_def: &FnDef) {
+    for type_param in &fn_def.type_params {
+        visitor.visit_type_param(type_param);
+    }
+
+    for param in &fn_def.params {
+        visitor.visit_param(param);
+    }
+
+    if let Some(ret_ty) = &fn_def.ret_ty {
+        visitor.visit_type_expr(ret_ty);
+    }
+
+    visitor.visit_block(arena, &fn_def.body);
+}
+
+pub fn walk_param<V: Visitor + ?Sized>(visitor: &mut V, param: &Param) {
+    if let Some(ty) = &param.ty {
+        visitor.visit_type_expr(ty);
+    }
+}
+
+pub fn walk_struct_def<V: Visitor + ?Sized>(visitor: &mut V, struct_def: &StructDef) {
+    for type_param in &struct_def.type_params {
+        visitor.visit_type_param(type_param);
+    }
+
+    for field_def in &struct_def.fields {
+        visitor.visit_field_def(field_def);
+    }
+}
+
+pub fn walk_field_def<V: Visitor + ?Sized>(visitor: &mut V, field_def: &FieldDef) {
+    visitor.visit_type_expr(&field_def.ty);
+}
+
+pub fn walk_trait_def<V: Visitor + ?Sized>(visitor: &mut V, trait_def: &TraitDef) {
+    for method in &trait_def.methods {
+        visitor.visit_fn_sig(method);
+    }
+}
+
+pub fn walk_fn_sig<V: Visitor + ?Sized>(visitor: &mut V, fn_sig: &FnSig) {
+    for param in &fn_sig.params {
+        visitor.visit_param(param);
+    }
+
+    if let Some(ret_ty) = &fn_sig.ret_ty {
+        visitor.visit_type_expr(ret_ty);
+    }
+}
+
+pub fn walk_impl_def<V: Visitor + ?Sized>(visitor: &mut V, arena: &Arena, impl_def: &ImplDef) {
+    for type_param in &impl_def.type_params {
+        visitor.visit_type_param(type_param);
+    }
+
+    visitor.visit_type_expr(&impl_def.target);
+
+    for method in &impl_def.methods {
+        visitor.visit_fn_def(arena, method);
+    }
+}
+
+pub fn walk_type_expr<V: Visitor + ?Sized>(visitor: &mut V, ty: &TypeExpr) {
+    match &ty.kind {
+        TypeExprKind::Named { args, .. } => {
+            for arg in args {
+                visitor.visit_type_expr(arg);
+            }
+        }
+        TypeExprKind::Fn { params, ret } => {
+            for param in params {
+                visitor.visit_type_expr(param);
+            }
+
+            visitor.visit_type_expr(ret);
+        }
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, arena: &Arena, expr: ExprId) {
+    match &arena[expr].kind {
+        ExprKind::Number(number) => visitor.visit_number(number),
+        ExprKind::Bool(_) | ExprKind::Str(_) => {}
+        ExprKind::Operation { lhs, rhs, op } => {
+            visitor.visit_expr(arena, *lhs);
+            visitor.visit_op(op);
+            visitor.visit_expr(arena, *rhs);
+        }
+        ExprKind::Unary { op, expr } => {
+            visitor.visit_unary_op(op);
+            visitor.visit_expr(arena, *expr);
+        }
+        ExprKind::BindingUsage(binding_usage) => visitor.visit_binding_usage(binding_usage),
+        ExprKind::Block(block) => visitor.visit_block(arena, block),
+        ExprKind::If(if_expr) => visitor.visit_if(arena, if_expr),
+        ExprKind::Call(call) => visitor.visit_call(arena, call),
+        ExprKind::List(elements) => {
+            for &element in elements {
+                visitor.visit_expr(arena, element);
+            }
+        }
+        ExprKind::Index { base, index } => {
+            visitor.visit_expr(arena, *base);
+            visitor.visit_expr(arena, *index);
+        }
+        ExprKind::Field { base, .. } => visitor.visit_expr(arena, *base),
+        ExprKind::StructLit(struct_lit) => visitor.visit_struct_lit(arena, struct_lit),
+        ExprKind::MethodCall(method_call) => visitor.visit_method_call(arena, method_call),
+        ExprKind::Try(expr) | ExprKind::Throw(expr) => visitor.visit_expr(arena, *expr),
+        ExprKind::TryCatch(try_catch) => visitor.visit_try_catch(arena, try_catch),
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, arena: &Arena, block: &Block) {
+    for &stmt in &block.stmts {
+        visitor.visit_stmt(arena, stmt);
+    }
+}
+
+pub fn walk_if<V: Visitor + ?Sized>(visitor: &mut V, arena: &Arena, if_expr: &If) {
+    visitor.visit_expr(arena, if_expr.condition);
+    visitor.visit_block(arena, &if_expr.then_branch);
+
+    if let Some(else_branch) = if_expr.else_branch {
+        visitor.visit_expr(arena, else_branch);
+    }
+}
+
+pub fn walk_call<V: Visitor + ?Sized>(visitor: &mut V, arena: &Arena, call: &Call) {
+    visitor.visit_expr(arena, call.callee);
+
+    for &arg in &call.args {
+        visitor.visit_expr(arena, arg);
+    }
+}
+
+pub fn walk_struct_lit<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena,
+    struct_lit: &StructLit,
+) {
+    for field_init in &struct_lit.fields {
+        visitor.visit_field_init(arena, field_init);
+    }
+}
+
+pub fn walk_field_init<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena,
+    field_init: &FieldInit,
+) {
+    visitor.visit_expr(arena, field_init.val);
+}
+
+pub fn walk_method_call<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    arena: &Arena,
+    method_call: &MethodCall,
+) {
+    visitor.visit_expr(arena, method_call.receiver);
+
+    for &arg in &method_call.args {
+        visitor.visit_expr(arena, arg);
+    }
+}
+
+pub fn walk_try_catch<V: Visitor + ?Sized>(visitor: &mut V, arena: &Arena, try_catch: &TryCatch) {
+    visitor.visit_block(arena, &try_catch.body);
+
+    if let Some(catch) = &try_catch.catch {
+        visitor.visit_catch(arena, catch);
+    }
+
+    if let Some(finally) = &try_catch.finally {
+        visitor.visit_block(arena, finally);
+    }
+}
+
+pub fn walk_catch<V: Visitor + ?Sized>(visitor: &mut V, arena: &Arena, catch: &Catch) {
+    visitor.visit_block(arena, &catch.body);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Symbol;
+
+    #[derive(Default)]
+    struct Counter {
+        numbers: Vec<i32>,
+        ops: Vec<Op>,
+        binding_defs: Vec<Symbol>,
+        binding_usages: Vec<Symbol>,
+        blocks: usize,
+    }
+
+    impl Visitor for Counter {
+        fn visit_binding_def(&mut self, arena: &Arena, binding_def: &BindingDef) {
+            self.binding_defs.push(binding_def.name.clone());
+            walk_binding_def(self, arena, binding_def);
+        }
+
+        fn visit_number(&mut self, number: &Number) {
+            self.numbers.push(number.0);
+        }
+
+        fn visit_op(&mut self, op: &Op) {
+            self.ops.push(*op);
+        }
+
+        fn visit_binding_usage(&mut self, binding_usage: &BindingUsage) {
+            self.binding_usages.push(binding_usage.name.clone());
+        }
+
+        fn visit_type_expr(&mut self, ty: &TypeExpr) {
+            walk_type_expr(self, ty);
+        }
+
+        fn visit_block(&mut self, arena: &Arena, block: &Block) {
+            self.blocks += 1;
+            walk_block(self, arena, block);
+        }
+    }
+
+    #[test]
+    fn visit_every_node() {
+        let parse = crate::parse("let a = 1 + 2\n{ let b = { a }; b; 3 }");
+
+        let mut counter = Counter::default();
+        counter.visit_source_file(parse.source_file().unwrap());
+
+        assert_eq!(counter.numbers, [1, 2, 3]);
+        assert_eq!(counter.ops, [Op::Add]);
+        assert_eq!(counter.binding_defs, ["a", "b"]);
+        assert_eq!(counter.binding_usages, ["a", "b"]);
+        assert_eq!(counter.blocks, 2);
+    }
+
+    #[test]
+    fn overriding_without_walking_skips_children() {
+        struct SkipBlocks(usize);
+
+        impl Visitor for SkipBlocks {
+            fn visit_type_expr(&mut self, ty: &TypeExpr) {
+                walk_type_expr(self, ty);
+            }
+
+            fn visit_block(&mut self, _arena: &Arena, _block: &Block) {}
+
+            fn visit_number(&mut self, _number: &Number) {
+                self.0 += 1;
+            }
+        }
+
+        let parse = crate::parse("1\n{ 2 }");
+
+        let mut visitor = SkipBlocks(0);
+        visitor.visit_source_file(parse.source_file().unwrap());
+
+        assert_eq!(visitor.0, 1);
+    }
+
+    #[test]
+    fn visit_fns_ifs_and_calls() {
+        #[derive(Default)]
+        struct Names(Vec<String>);
+
+        impl Visitor for Names {
+            fn visit_fn_def(&mut self, arena: &Arena, fn_def: &FnDef) {
+                self.0.push(format!("fn {}", fn_def.name));
+                walk_fn_def(self, arena, fn_def);
+            }
+
+            fn visit_param(&mut self, param: &Param) {
+                self.0.push(format!("param {}", param.name));
+            }
+
+            fn visit_binding_usage(&mut self, binding_usage: &BindingUsage) {
+                self.0.push(binding_usage.name.to_string());
+            }
+        }
+
+        let parse = crate::parse("fn f(a, b) { if !a { b } else { f(b, a) } }");
+
+        let mut names = Names::default();
+        names.visit_source_file(parse.source_file().unwrap());
+
+        assert_eq!(
+            names.0,
+            ["fn f", "param a", "param b", "a", "b", "f", "b", "a"]
+        );
+    }
+
+    #[test]
+    fn visit_type_annotations() {
+        #[derive(Default)]
+        struct Types(Vec<String>);
+
+        impl Visitor for Types {
+            fn visit_type_expr(&mut self, ty: &TypeExpr) {
+                self.0.push(ty.to_string());
+                walk_type_expr(self, ty);
+            }
+        }
+
+        let parse = crate::parse("let a: Int = 1\nfn f(g: fn(Bool) -> Str) -> Unit {}");
+
+        let mut types = Types::default();
+        types.visit_source_file(parse.source_file().unwrap());
+
+        assert_eq!(types.0, ["Int", "fn(Bool) -> Str", "Bool", "Str", "Unit"]);
+    }
+
+    #[test]
+    fn visit_structs_traits_and_methods() {
+        #[derive(Default)]
+        struct Names(Vec<String>);
+
+        impl Visitor for Names {
+            fn visit_struct_def(&mut self, struct_def: &StructDef) {
+                self.0.push(format!("struct {}", struct_def.name));
+                walk_struct_def(self, struct_def);
+            }
+
+            fn visit_field_def(&mut self, field_def: &FieldDef) {
+                self.0.push(format!("field {}", field_def.name));
+            }
+
+            fn visit_fn_sig(&mut self, fn_sig: &FnSig) {
+                self.0.push(format!("sig {}", fn_sig.name));
+            }
+
+            fn visit_impl_def(&mut self, arena: &Arena, impl_def: &ImplDef) {
+                self.0.push(format!(
+                    "impl {} for {}",
+                    impl_def.trait_name, impl_def.target
+                ));
+                walk_impl_def(self, arena, impl_def);
+            }
+
+            fn visit_fn_def(&mut self, arena: &Arena, fn_def: &FnDef) {
+                self.0.push(format!("fn {}", fn_def.name));
+                walk_fn_def(self, arena, fn_def);
+            }
+
+            fn visit_method_call(&mut self, arena: &Arena, method_call: &MethodCall) {
+                self.0.push(format!("call {}", method_call.name));
+                walk_method_call(self, arena, method_call);
+            }
+
+            fn visit_field_init(&mut self, arena: &Arena, field_init: &FieldInit) {
+                self.0.push(format!("init {}", field_init.name));
+                walk_field_init(self, arena, field_init);
+            }
+
+            fn visit_binding_usage(&mut self, binding_usage: &BindingUsage) {
+                self.0.push(binding_usage.name.to_string());
+            }
+        }
+
+        let parse = crate::parse(
+            "struct P { x: Int }\ntrait T { fn t(self) }\nimpl T for P { fn t(self) { self.x } }\nP { x: [a][0] }.t()",
+        );
+
+        let mut names = Names::default();
+        names.visit_source_file(parse.source_file().unwrap());
+
+        assert_eq!(
+            names.0,
+            [
+                "struct P",
+                "field x",
+                "sig t",
+                "impl T for P",
+                "fn t",
+                "self",
+                "call t",
+                "init x",
+                "a"
+            ]
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use super::{
+    Arena, BindingDef, BindingUsage, Block, Call, Catch, ExprId, ExprKind, FieldDef, FieldInit,
+    FnDef, FnSig, If, ImplDef, MethodCall, Number, Op, Param, SourceFile, Stmt, StmtId, StructDef,
+    StructLit, TraitDef, TryCatch, TypeExpr, TypeExprKind, TypeParam, UnaryOp,
+};
+
+/// Traversal of the AST that can modify nodes in place.
+///
+/// Mirrors [`Visitor`](super::Visitor): every method defaults to visiting the children of its node
+/// through the matching `walk_*_mut` function. A statement or expression can be replaced as a
+/// whole through the arena in [`visit_stmt_mut`](Self::visit_stmt_mut) and
+/// [`visit_expr_mut`](Self::visit_expr_mut); while its children are being walked, it is taken out
+/// of the arena and passed to the methods for its parts instead.
+pub trait VisitorMut {
+    fn visit_source_file_mut(&mut self, source_file: &mut SourceFile) {
+        walk_source_file_mut(self, source_file);
+    }
+
+    fn visit_stmt_mut(&mut self, arena: &mut Arena, stmt: StmtId) {
+        walk_stmt_mut(self, arena, stmt);
+    }
+
+    fn visit_binding_def_mut(&mut self, arena: &mut Arena, binding_def: &mut BindingDef) {
+        walk_binding_def_mut(self, arena, binding_def);
+    }
+
+    fn visit_fn_def_mut(&mut self, arena: &mut Arena, fn_def: &mut FnDef) {
+        walk_fn_def_mut(self, arena, fn_def);
+    }
+
+    fn visit_type_param_mut(&mut self, _type_param: &mut TypeParam) {}
+
+    fn visit_param_mut(&mut self, param: &mut Param) {
+        walk_param_mut(self, param);
+    }
+
+    fn visit_struct_def_mut(&mut self, struct_def: &mut StructDef) {
+        walk_struct_def_mut(self, struct_def);
+    }
+
+    fn visit_field_def_mut(&mut self, field_def: &mut FieldDef) {
+        walk_field_def_mut(self, field_def);
+    }
+
+    fn visit_trait_def_mut(&mut self, trait_def: &mut TraitDef) {
+        walk_trait_def_mut(self, trait_def);
+    }
+
+    fn visit_fn_sig_mut(&mut self, fn_sig: &mut FnSig) {
+        walk_fn_sig_mut(self, fn_sig);
+    }
+
+    fn visit_impl_def_mut(&mut self, arena: &mut Arena, impl_def: &mut ImplDef) {
+        walk_impl_def_mut(self, arena, impl_def);
+    }
+
+    fn visit_expr_mut(&mut self, arena: &mut Arena, expr: ExprId) {
+        walk_expr_mut(self, arena, expr);
+    }
+
+    fn visit_number_mut(&mut self, _number: &mut Number) {}
+
+    fn visit_op_mut(&mut self, _op: &mut Op) {}
+
+    fn visit_unary_op_mut(&mut self, _op: &mut UnaryOp) {}
+
+    fn visit_binding_usage_mut(&mut self, _binding_usage: &mut BindingUsage) {}
+
+    fn visit_type_expr_mut(&mut self, ty: &mut TypeExpr) {
+        walk_type_expr_mut(self, ty);
+    }
+
+    fn visit_block_mut(&mut self, arena: &mut Arena, block: &mut Block) {
+        walk_block_mut(self, arena, block);
+    }
+
+    fn visit_if_mut(&mut self, arena: &mut Arena, if_expr: &mut If) {
+        walk_if_mut(self, arena, if_expr);
+    }
+
+    fn visit_call_mut(&mut self, arena: &mut Arena, call: &mut Call) {
+        walk_call_mut(self, arena, call);
+    }
+
+    fn visit_struct_lit_mut(&mut self, arena: &mut Arena, struct_lit: &mut StructLit) {
+        walk_struct_lit_mut(self, arena, struct_lit);
+    }
+
+    fn visit_method_call_mut(&mut self, arena: &mut Arena, method_call: &mut MethodCall) {
+        walk_method_call_mut(self, arena, method_call);
+    }
+
+    fn visit_field_init_mut(&mut self, arena: &mut Arena, field_init: &mut FieldInit) {
+        walk_field_init_mut(self, arena, field_init);
+    }
+
+    fn visit_try_catch_mut(&mut self, arena: &mut Arena, try_catch: &mut TryCatch) {
+        walk_try_catch_mut(self, arena, try_catch);
+    }
+
+    fn visit_catch_mut(&mut self, arena: &mut Arena, catch: &mut Catch) {
+        walk_catch_mut(self, arena, catch);
+    }
+}
+
+pub fn walk_source_file_mut<V: VisitorMut + ?Sized>(visitor: &mut V, source_file: &mut SourceFile) {
+    let (stmts, arena) = source_file.parts_mut();
+
+    for &mut stmt in stmts {
+        visitor.visit_stmt_mut(arena, stmt);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, arena: &mut Arena, stmt: StmtId) {
+    arena.with_stmt(stmt, |arena, stmt| match stmt {
+        Stmt::BindingDef(binding_def) => visitor.visit_binding_def_mut(arena, binding_def),
+        Stmt::FnDef(fn_def) => visitor.visit_fn_def_mut(arena, fn_def),
+        Stmt::StructDef(struct_def) => visitor.visit_struct_def_mut(struct_def),
+        Stmt::TraitDef(trait_def) => visitor.visit_trait_def_mut(trait_def),
+        Stmt::ImplDef(impl_def) => visitor.visit_impl_def_mut(arena, impl_def),
+        Stmt::Expr(expr) | Stmt::Semi(expr) => visitor.visit_expr_mut(arena, *expr),
+    });
+}
+
+pub fn walk_binding_def_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena,
+    binding_def: &mut BindingDef,
+) {
+    if let Some(ty) = &mut binding_def.ty {
+        visitor.visit_type_expr_mut(ty);
+    }
+
+    visitor.visit_expr_mut(arena, binding_def.val);
+}
+
+pub fn walk_fn_def_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena,
+    fn_def: &mut FnDef,
+) {
+    for type_param in &mut fn_def.type_params {
+        visitor.visit_type_param_mut(type_param);
+    }
+
+    for param in &mut fn_def.params {
+        visitor.visit_param_mut(param);
+    }
+
+    if let Some(ret_ty) = &mut fn_def.ret_ty {
+        visitor.visit_type_expr_mut(ret_ty);
+    }
+
+    visitor.visit_block_mut(arena, &mut fn_def.body);
+}
+
+pub fn walk_param_mut<V: VisitorMut + ?Sized>(visitor: &mut V, param: &mut Param) {
+    if let Some(ty) = &mut param.ty {
+        visitor.visit_type_expr_mut(ty);
+    }
+}
+
+pub fn walk_struct_def_mut<V: VisitorMut + ?Sized>(visitor: &mut V, struct_def: &mut StructDef) {
+    for type_param in &mut struct_def.type_params {
+        visitor.visit_type_param_mut(type_param);
+    }
+
+    for field_def in &mut struct_def.fields {
+        visitor.visit_field_def_mut(field_def);
+    }
+}
+
+pub fn walk_field_def_mut<V: VisitorMut + ?Sized>(visitor: &mut V, field_def: &mut FieldDef) {
+    visitor.visit_type_expr_mut(&mut field_def.ty);
+}
+
+pub fn walk_trait_def_mut<V: VisitorMut + ?Sized>(visitor: &mut V, trait_def: &mut TraitDef) {
+    for method in &mut trait_def.methods {
+        visitor.visit_fn_sig_mut(method);
+    }
+}
+
+pub fn walk_fn_sig_mut<V: VisitorMut + ?Sized>(visitor: &mut V, fn_sig: &mut FnSig) {
+    for param in &mut fn_sig.params {
+        visitor.visit_param_mut(param);
+    }
+
+    if let Some(ret_ty) = &mut fn_sig.ret_ty {
+        visitor.visit_type_expr_mut(ret_ty);
+    }
+}
+
+pub fn walk_impl_def_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena,
+    impl_def: &mut ImplDef,
+) {
+    for type_param in &mut impl_def.type_params {
+        visitor.visit_type_param_mut(type_param);
+    }
+
+    visitor.visit_type_expr_mut(&mut impl_def.target);
+
+    for method in &mut impl_def.methods {
+        visitor.visit_fn_def_mut(arena, method);
+    }
+}
+
+pub fn walk_type_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, ty: &mut TypeExpr) {
+    match &mut ty.kind {
+        TypeExprKind::Named { args, .. } => {
+            for arg in args {
+                visitor.visit_type_expr_mut(arg);
+            }
+        }
+        TypeExprKind::Fn { params, ret } => {
+            for param in params {
+                visitor.visit_type_expr_mut(param);
+            }
+
+            visitor.visit_type_expr_mut(ret);
+        }
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, arena: &mut Arena, expr: ExprId) {
+    arena.with_expr(expr, |arena, expr| match &mut expr.kind {
+        ExprKind::Number(number) => visitor.visit_number_mut(number),
+        ExprKind::Bool(_) | ExprKind::Str(_) => {}
+        ExprKind::Operation { lhs, rhs, op } => {
+            visitor.visit_expr_mut(arena, *lhs);
+            visitor.visit_op_mut(op);
+            visitor.visit_expr_mut(arena, *rhs);
+        }
+        ExprKind::Unary { op, expr } => {
+            visitor.visit_unary_op_mut(op);
+            visitor.visit_expr_mut(arena, *expr);
+        }
+        ExprKind::BindingUsage(binding_usage) => visitor.visit_binding_usage_mut(binding_usage),
+        ExprKind::Block(block) => visitor.visit_block_mut(arena, block),
+        ExprKind::If(if_expr) => visitor.visit_if_mut(arena, if_expr),
+        ExprKind::Call(call) => visitor.visit_call_mut(arena, call),
+        ExprKind::List(elements) => {
+            for &mut element in elements {
+                visitor.visit_expr_mut(arena, element);
+            }
+        }
+        ExprKind::Index { base, index } => {
+            visitor.visit_expr_mut(arena, *base);
+            visitor.visit_expr_mut(arena, *index);
+        }
+        ExprKind::Field { base, .. } => visitor.visit_expr_mut(arena, *base),
+        ExprKind::StructLit(struct_lit) => visitor.visit_struct_lit_mut(arena, struct_lit),
+        ExprKind::MethodCall(method_call) => visitor.visit_method_call_mut(arena, method_call),
+        ExprKind::Try(expr) | ExprKind::Throw(expr) => visitor.visit_expr_mut(arena, *expr),
+        ExprKind::TryCatch(try_catch) => visitor.visit_try_catch_mut(arena, try_catch),
+    });
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena,
+    block: &mut Block,
+) {
+    for &mut stmt in &mut block.stmts {
+        visitor.visit_stmt_mut(arena, stmt);
+    }
+}
+
+pub fn walk_if_mut<V: VisitorMut + ?Sized>(visitor: &mut V, arena: &mut Arena, if_expr: &mut If) {
+    visitor.visit_expr_mut(arena, if_expr.condition);
+    visitor.visit_block_mut(arena, &mut if_expr.then_branch);
+
+    if let Some(else_branch) = if_expr.else_branch {
+        visitor.visit_expr_mut(arena, else_branch);
+    }
+}
+
+pub fn walk_call_mut<V: VisitorMut + ?Sized>(visitor: &mut V, arena: &mut Arena, call: &mut Call) {
+    visitor.visit_expr_mut(arena, call.callee);
+
+    for &mut arg in &mut call.args {
+        visitor.visit_expr_mut(arena, arg);
+    }
+}
+
+pub fn walk_struct_lit_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena,
+    struct_lit: &mut StructLit,
+) {
+    for field_init in &mut struct_lit.fields {
+        visitor.visit_field_init_mut(arena, field_init);
+    }
+}
+
+pub fn walk_field_init_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena,
+    field_init: &mut FieldInit,
+) {
+    visitor.visit_expr_mut(arena, field_init.val);
+}
+
+pub fn walk_method_call_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena,
+    method_call: &mut MethodCall,
+) {
+    visitor.visit_expr_mut(arena, method_call.receiver);
+
+    for &mut arg in &mut method_call.args {
+        visitor.visit_expr_mut(arena, arg);
+    }
+}
+
+pub fn walk_try_catch_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena,
+    try_catch: &mut TryCatch,
+) {
+    visitor.visit_block_mut(arena, &mut try_catch.body);
+
+    if let Some(catch) = &mut try_catch.catch {
+        visitor.visit_catch_mut(arena, catch);
+    }
+
+    if let Some(finally) = &mut try_catch.finally {
+        visitor.visit_block_mut(arena, finally);
+    }
+}
+
+pub fn walk_catch_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    arena: &mut Arena,
+    catch: &mut Catch,
+) {
+    visitor.visit_block_mut(arena, &mut catch.body);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{env::Env, val::Val};
+
+    struct Rename<'a> {
+        from: &'a str,
+        to: &'a str,
+    }
+
+    impl VisitorMut for Rename<'_> {
+        fn visit_binding_def_mut(&mut self, arena: &mut Arena, binding_def: &mut BindingDef) {
+            if binding_def.name == self.from {
+                binding_def.name = self.to.into();
+            }
+
+            walk_binding_def_mut(self, arena, binding_def);
+        }
+
+        fn visit_binding_usage_mut(&mut self, binding_usage: &mut BindingUsage) {
+            if binding_usage.name == self.from {
+                binding_usage.name = self.to.into();
+            }
+        }
+    }
+
+    struct Double;
+
+    impl VisitorMut for Double {
+        fn visit_number_mut(&mut self, number: &mut Number) {
+            number.0 *= 2;
+        }
+    }
+
+    #[test]
+    fn rename_bindings() {
+        let mut parse = crate::parse("let a = 1\n{ let b = a; b }");
+
+        Rename { from: "a", to: "z" }.visit_source_file_mut(parse.source_file_mut().unwrap());
+
+        let mut env = Env::default();
+        assert_eq!(parse.eval(&mut env), Ok(Val::Number(1)));
+        assert_eq!(env.get_binding_value("z".into()), Ok(Val::Number(1)));
+        assert!(env.get_binding_value("a".into()).is_err());
+    }
+
+    #[test]
+    fn replace_numbers() {
+        let mut parse = crate::parse("{ let a = 3 * 4; a }");
+
+        Double.visit_source_file_mut(parse.source_file_mut().unwrap());
+
+        assert_eq!(parse.eval(&mut Env::default()), Ok(Val::Number(48)));
+    }
+}
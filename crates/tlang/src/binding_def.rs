@@ -1,36 +1,52 @@
-use crate::{env::Env, expr::Expr, util};
+use std::rc::Rc;
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) struct BindingDef {
-    pub(crate) name: String,
-    pub(crate) val: Expr,
+use crate::{
+    ast::{Arena, ExprId},
+    env::Env,
+    expr::Expr,
+    symbol::Symbol,
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    type_expr::TypeExpr,
+    unwind::Unwind,
+};
+
+/// `let name: ty = val`, where the type annotation is optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingDef {
+    pub name: Symbol,
+    pub ty: Option<TypeExpr>,
+    pub val: ExprId,
+    pub span: TextRange,
 }
 
 impl BindingDef {
-    pub(crate) fn new(s: &str) -> Result<(&str, Self), String> {
-        let s = util::tag("let", s)?;
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
 
-        let (s, _) = util::extract_whitespace1(s)?;
+        let ty = TypeExpr::lower_annotation(node)?;
 
-        let (s, name) = util::extract_ident(s)?;
-        let (s, _) = util::extract_whitespace(s);
+        let val = node.children().find(|child| !child.kind().is_type())?;
+        let val = Expr::lower(arena, &val)?;
 
-        let s = util::tag("=", s)?;
-        let (s, _) = util::extract_whitespace(s);
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            ty,
+            val,
+            span: node.text_range(),
+        })
+    }
 
-        let (s, val) = Expr::new(s)?;
+    pub(crate) fn eval(&self, arena: &Rc<Arena>, env: &mut Env) -> Result<(), Unwind> {
+        let val = arena[self.val].eval(arena, env)?;
 
-        Ok((
-            s,
-            Self {
-                name: name.to_string(),
-                val,
-            },
-        ))
-    }
+        if let Some(ty) = &self.ty {
+            ty.check(&val, env)
+                .map_err(|msg| format!("in binding '{}': {}", self.name, msg))?;
+        }
 
-    pub(crate) fn eval(&self, env: &mut Env) -> Result<(), String> {
-        env.store_binding(&self.name, self.val.eval(env)?);
+        env.store_binding(self.name.clone(), val);
 
         Ok(())
     }
@@ -40,31 +56,48 @@ impl BindingDef {
 mod tests {
     use super::*;
 
-    use crate::expr::{Number, Op};
+    use crate::{
+        expr::{ExprKind, Number, Op},
+        stmt::Stmt,
+    };
 
     #[test]
     fn parse_binding_def() {
-        assert_eq!(
-            BindingDef::new("let a = 10 / 2"),
-            Ok((
-                "",
-                BindingDef {
-                    name: "a".to_string(),
-                    val: Expr::Operation {
-                        lhs: Number(10),
-                        rhs: Number(2),
-                        op: Op::Div
-                    }
-                }
-            ))
-        );
+        let mut arena = Arena::default();
+        let lhs = arena.alloc_expr(Expr::new(
+            ExprKind::Number(Number(10)),
+            TextRange::new(8, 10),
+        ));
+        let rhs = arena.alloc_expr(Expr::new(
+            ExprKind::Number(Number(2)),
+            TextRange::new(13, 14),
+        ));
+        let val = arena.alloc_expr(Expr::new(
+            ExprKind::Operation {
+                lhs,
+                rhs,
+                op: Op::Div,
+            },
+            TextRange::new(8, 14),
+        ));
+        let stmt = arena.alloc_stmt(Stmt::BindingDef(BindingDef {
+            name: "a".into(),
+            ty: None,
+            val,
+            span: TextRange::new(0, 14),
+        }));
+
+        assert_eq!(crate::parse_stmt("let a = 10 / 2"), Ok((arena, stmt)));
     }
 
     #[test]
     fn cannot_parse_binding_def_without_space_after_let() {
         assert_eq!(
-            BindingDef::new("letaaa=1+2"),
-            Err("expected whitespace".to_string()),
+            crate::parse_stmt("letaaa=1+2"),
+            Err(vec![
+                "error at 6..7: expected ';' or line break after statement, but found '='"
+                    .to_string()
+            ]),
         );
     }
 }
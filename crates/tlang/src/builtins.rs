@@ -0,0 +1,148 @@
+//! Bindings that exist without being defined, such as the constructors of `Option` and `Result`.
+//! Like any other binding, they can be shadowed.
+
+use std::rc::Rc;
+
+use crate::{
+    gc, limits,
+    symbol::Symbol,
+    typeck::{Scheme, Type, TypeVar},
+    val::{Builtin, Function, Val},
+};
+
+/// The names of all built-in bindings.
+pub(crate) const NAMES: [&str; 7] = ["None", "Some", "Ok", "Err", "unwrap_or", "ok", "gc"];
+
+/// The value of the built-in binding `name`, or an error if there is none.
+pub(crate) fn lookup(name: Symbol) -> Result<Val, String> {
+    value(&name).ok_or_else(|| format!("binding with name '{}' does not exist", name))
+}
+
+/// The value of the built-in binding `name`, if there is one.
+pub(crate) fn value(name: &str) -> Option<Val> {
+    let builtin = match name {
+        "None" => return Some(Val::Option(None)),
+        "Some" => Builtin {
+            name: "Some",
+            arity: 1,
+            call: |mut args| limits::allocated(Val::Option(Some(Rc::new(args.remove(0))))),
+        },
+        "Ok" => Builtin {
+            name: "Ok",
+            arity: 1,
+            call: |mut args| limits::allocated(Val::Result(Ok(Rc::new(args.remove(0))))),
+        },
+        "Err" => Builtin {
+            name: "Err",
+            arity: 1,
+            call: |mut args| limits::allocated(Val::Result(Err(Rc::new(args.remove(0))))),
+        },
+        "unwrap_or" => Builtin {
+            name: "unwrap_or",
+            arity: 2,
+            call: |mut args| {
+                let default = args.pop().unwrap();
+
+                match args.pop().unwrap() {
+                    Val::Option(Some(val)) => Ok((*val).clone()),
+                    Val::Option(None) => Ok(default),
+                    val => Err(format!(
+                        "expected Option as first argument of 'unwrap_or', but found {}",
+                        val.type_name()
+                    )
+                    .into()),
+                }
+            },
+        },
+        "ok" => Builtin {
+            name: "ok",
+            arity: 1,
+            call: |mut args| match args.remove(0) {
+                Val::Result(Ok(val)) => limits::allocated(Val::Option(Some(val))),
+                Val::Result(Err(_)) => Ok(Val::Option(None)),
+                val => Err(format!(
+                    "expected Result as argument of 'ok', but found {}",
+                    val.type_name()
+                )
+                .into()),
+            },
+        },
+        "gc" => Builtin {
+            name: "gc",
+            arity: 0,
+            call: |_| Ok(Val::Number(gc::collect().try_into().unwrap_or(i32::MAX))),
+        },
+        _ => return None,
+    };
+
+    Some(Val::Fn(Function::builtin(builtin)))
+}
+
+/// The type of the built-in binding `name`, if there is one.
+pub(crate) fn scheme(name: &str) -> Option<Scheme> {
+    let a = Type::Var(TypeVar(0));
+    let b = Type::Var(TypeVar(1));
+
+    let (vars, ty) = match name {
+        "None" => (1, Type::option(a)),
+        "Some" => (1, Type::func([a.clone()], Type::option(a))),
+        "Ok" => (2, Type::func([a.clone()], Type::result(a, b))),
+        "Err" => (2, Type::func([b.clone()], Type::result(a, b))),
+        "unwrap_or" => (1, Type::func([Type::option(a.clone()), a.clone()], a)),
+        "ok" => (2, Type::func([Type::result(a.clone(), b)], Type::option(a))),
+        "gc" => (0, Type::func([], Type::Int)),
+        _ => return None,
+    };
+
+    Some(Scheme {
+        vars: (0..vars).map(TypeVar).collect(),
+        ty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_value_has_a_type() {
+        for name in NAMES {
+            assert!(value(name).is_some());
+            assert!(scheme(name).is_some());
+        }
+
+        assert_eq!(value("nope"), None);
+        assert_eq!(scheme("nope"), None);
+    }
+
+    #[test]
+    fn eval_builtins() {
+        let eval = |s: &str| crate::parse(s).eval(&mut crate::Env::default());
+
+        assert_eq!(eval("unwrap_or(Some(1), 2)"), Ok(Val::Number(1)));
+        assert_eq!(eval("unwrap_or(ok(Err(1)), 2)"), Ok(Val::Number(2)));
+        assert_eq!(
+            eval("unwrap_or(1, 2)"),
+            Err("expected Option as first argument of 'unwrap_or', but found Int".to_string())
+        );
+        assert_eq!(eval("let Some = 1\nSome"), Ok(Val::Number(1)));
+    }
+
+    #[test]
+    fn arity_matches_type() {
+        for name in ["Some", "Ok", "Err", "unwrap_or", "ok", "gc"] {
+            let Some(Val::Fn(function)) = value(name) else {
+                panic!("'{}' is not a function", name)
+            };
+            let Some(Scheme {
+                ty: Type::Fn { params, .. },
+                ..
+            }) = scheme(name)
+            else {
+                panic!("'{}' doesn't have a function type", name)
+            };
+
+            assert_eq!(function.arity(), params.len());
+        }
+    }
+}
@@ -1,35 +1,318 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    mem,
+    rc::{Rc, Weak},
+};
 
-use crate::val::Val;
+use crate::{
+    gc::Obj,
+    interrupt::InterruptHandle,
+    limits::Limits,
+    struct_def::StructDef,
+    symbol::Symbol,
+    trait_def::TraitDef,
+    val::{Function, Val},
+};
 
-#[derive(Debug, Default, PartialEq, Clone)]
-pub struct Env<'parent> {
-    bindings: HashMap<String, Val>,
-    parent: Option<&'parent Self>,
+/// A scope of bindings, which can be shared: functions keep the environment they were defined in
+/// around, so that they can use its bindings whenever they get called.
+#[derive(Debug, Default, Clone)]
+pub struct Env(Rc<RefCell<Scope>>);
+
+#[derive(Debug, Default, PartialEq)]
+struct Scope {
+    /// The slot of the latest binding of each name in `slots`.
+    bindings: HashMap<Symbol, usize>,
+    /// The name and value of each binding, in the order they were stored in the scope. A binding
+    /// that shadows an earlier one of the same name gets a slot of its own, so that functions
+    /// using the earlier one keep doing so.
+    slots: Vec<(Symbol, Val)>,
+    types: HashMap<Symbol, TypeBinding>,
+    traits: HashMap<Symbol, Rc<TraitDef>>,
+    /// The methods of impls, by the name of the type they are for and the name of the method,
+    /// along with the name of the trait they belong to.
+    methods: HashMap<(Symbol, Symbol), (Symbol, Function)>,
+    /// The limits evaluation in the scope is subject to, and the handle that interrupts it, which
+    /// only matter for the scope inputs are evaluated in.
+    limits: Limits,
+    interrupt: Option<InterruptHandle>,
+    parent: Option<Env>,
+}
+
+/// The bindings and declarations of an environment at some point, which it can be
+/// [restored](Env::restore) to, e.g. to undo what an input defined before it failed.
+#[derive(Debug, Clone)]
+pub struct EnvCheckpoint {
+    bindings: HashMap<Symbol, usize>,
+    slots: usize,
+    types: HashMap<Symbol, TypeBinding>,
+    traits: HashMap<Symbol, Rc<TraitDef>>,
+    methods: HashMap<(Symbol, Symbol), (Symbol, Function)>,
 }
 
-impl<'parent> Env<'parent> {
-    pub(crate) fn create_child(&'parent self) -> Self {
-        Self {
+/// What a type name refers to, which is needed to check type annotations at runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TypeBinding {
+    Struct(Rc<StructDef>),
+    /// A type parameter of the function being called, which stands for any type.
+    Param,
+}
+
+impl Env {
+    /// Sets the limits every input evaluated in this environment is subject to from now on.
+    #[inline]
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.0.borrow_mut().limits = limits;
+    }
+
+    #[inline]
+    pub fn limits(&self) -> Limits {
+        self.0.borrow().limits
+    }
+
+    /// A handle that interrupts the evaluation of inputs in this environment, which can be sent to
+    /// other threads.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.0
+            .borrow_mut()
+            .interrupt
+            .get_or_insert_with(InterruptHandle::default)
+            .clone()
+    }
+
+    #[inline]
+    pub(crate) fn interrupt(&self) -> Option<InterruptHandle> {
+        self.0.borrow().interrupt.clone()
+    }
+
+    /// Everything defined in this environment so far.
+    pub fn checkpoint(&self) -> EnvCheckpoint {
+        let scope = self.0.borrow();
+
+        EnvCheckpoint {
+            bindings: scope.bindings.clone(),
+            slots: scope.slots.len(),
+            types: scope.types.clone(),
+            traits: scope.traits.clone(),
+            methods: scope.methods.clone(),
+        }
+    }
+
+    /// Removes everything defined in this environment since `checkpoint` was taken of it. As
+    /// bindings are never replaced, only shadowed, the earlier ones are still around.
+    pub fn restore(&mut self, checkpoint: EnvCheckpoint) {
+        let mut scope = self.0.borrow_mut();
+
+        scope.bindings = checkpoint.bindings;
+        scope.slots.truncate(checkpoint.slots);
+        scope.types = checkpoint.types;
+        scope.traits = checkpoint.traits;
+        scope.methods = checkpoint.methods;
+    }
+
+    pub(crate) fn create_child(&self) -> Self {
+        Self(Rc::new(RefCell::new(Scope {
             bindings: HashMap::new(),
-            parent: Some(self),
+            slots: Vec::new(),
+            types: HashMap::new(),
+            traits: HashMap::new(),
+            methods: HashMap::new(),
+            limits: Limits::default(),
+            interrupt: None,
+            parent: Some(self.clone()),
+        })))
+    }
+
+    /// Binds `name` in the next slot of this scope, shadowing earlier bindings of the same name.
+    pub(crate) fn store_binding(&mut self, name: Symbol, val: Val) {
+        let mut scope = self.0.borrow_mut();
+
+        let slot = scope.slots.len();
+        scope.bindings.insert(name.clone(), slot);
+        scope.slots.push((name, val));
+    }
+
+    /// The value in `slot` of the scope `depth` levels up from this one.
+    pub(crate) fn get_slot(&self, depth: usize, slot: usize) -> Val {
+        let scope = self.0.borrow();
+
+        match depth {
+            0 => scope.slots[slot].1.clone(),
+            _ => scope.parent.as_ref().unwrap().get_slot(depth - 1, slot),
         }
     }
 
+    /// The names of the bindings in this scope and its ancestors by slot, outermost scope first,
+    /// which is where the [resolver](crate::resolve) starts from.
+    pub(crate) fn slot_names(&self) -> Vec<Vec<Symbol>> {
+        let scope = self.0.borrow();
+
+        let mut names = match &scope.parent {
+            Some(parent) => parent.slot_names(),
+            None => Vec::new(),
+        };
+
+        names.push(scope.slots.iter().map(|(name, _)| name.clone()).collect());
+
+        names
+    }
+
+    /// The value of the latest binding of `name` in this scope or its ancestors, which tests use
+    /// to check where bindings end up.
+    #[cfg(test)]
+    pub(crate) fn get_binding_value(&self, name: Symbol) -> Result<Val, String> {
+        let scope = self.0.borrow();
+
+        match scope.bindings.get(&name) {
+            Some(&slot) => Ok(scope.slots[slot].1.clone()),
+            None => match &scope.parent {
+                Some(parent) => parent.get_binding_value(name),
+                None => Err(format!("binding with name '{}' does not exist", name)),
+            },
+        }
+    }
+}
+
+impl Env {
+    #[inline]
+    pub(crate) fn store_struct(&mut self, def: Rc<StructDef>) {
+        self.0
+            .borrow_mut()
+            .types
+            .insert(def.name.clone(), TypeBinding::Struct(def));
+    }
+
+    #[inline]
+    pub(crate) fn store_type_param(&mut self, name: Symbol) {
+        self.0.borrow_mut().types.insert(name, TypeBinding::Param);
+    }
+
+    pub(crate) fn get_type(&self, name: Symbol) -> Option<TypeBinding> {
+        let scope = self.0.borrow();
+
+        scope.types.get(&name).cloned().or_else(|| {
+            scope
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.get_type(name))
+        })
+    }
+}
+
+impl Env {
+    #[inline]
+    pub(crate) fn store_trait(&mut self, def: Rc<TraitDef>) {
+        self.0.borrow_mut().traits.insert(def.name.clone(), def);
+    }
+
+    pub(crate) fn get_trait(&self, name: Symbol) -> Option<Rc<TraitDef>> {
+        let scope = self.0.borrow();
+
+        scope.traits.get(&name).cloned().or_else(|| {
+            scope
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.get_trait(name))
+        })
+    }
+
     #[inline]
-    pub(crate) fn store_binding(&mut self, name: &str, val: Val) {
-        self.bindings.insert(name.to_string(), val);
+    pub(crate) fn store_method(
+        &mut self,
+        type_name: Symbol,
+        trait_name: Symbol,
+        name: Symbol,
+        method: Function,
+    ) {
+        self.0
+            .borrow_mut()
+            .methods
+            .insert((type_name, name), (trait_name, method));
     }
 
-    pub(crate) fn get_binding_value(&self, name: &str) -> Result<Val, String> {
-        self.get_binding_value_without_error_msg(name)
-            .ok_or_else(|| format!("binding with name '{}' does not exist", name))
+    #[inline]
+    pub(crate) fn get_method(&self, type_name: Symbol, name: Symbol) -> Option<Function> {
+        self.lookup_method(&(type_name, name))
+            .map(|(_, method)| method)
     }
 
-    fn get_binding_value_without_error_msg(&self, name: &str) -> Option<Val> {
-        self.bindings.get(name).cloned().or_else(|| {
-            self.parent
-                .and_then(|parent| parent.get_binding_value_without_error_msg(name))
+    /// The trait that the method `name` of the type `type_name` belongs to, if it has one.
+    #[inline]
+    pub(crate) fn get_method_trait(&self, type_name: Symbol, name: Symbol) -> Option<Symbol> {
+        self.lookup_method(&(type_name, name))
+            .map(|(trait_name, _)| trait_name)
+    }
+
+    fn lookup_method(&self, key: &(Symbol, Symbol)) -> Option<(Symbol, Function)> {
+        let scope = self.0.borrow();
+
+        scope.methods.get(key).cloned().or_else(|| {
+            scope
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.lookup_method(key))
         })
     }
 }
+
+/// An environment that is only looked at by the [garbage collector](crate::gc) if something
+/// else keeps it around.
+pub(crate) struct WeakEnv(Weak<RefCell<Scope>>);
+
+impl WeakEnv {
+    #[inline]
+    pub(crate) fn upgrade(&self) -> Option<Env> {
+        self.0.upgrade().map(Env)
+    }
+}
+
+/// What the garbage collector needs to know about environments.
+impl Env {
+    #[inline]
+    pub(crate) fn downgrade(&self) -> WeakEnv {
+        WeakEnv(Rc::downgrade(&self.0))
+    }
+
+    /// The address of the scope, which tells environments sharing it apart from others.
+    #[inline]
+    pub(crate) fn addr(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+
+    #[inline]
+    pub(crate) fn strong_count(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+
+    /// Adds the values and environments the scope holds on to to `refs`, or returns `false` if
+    /// the scope is being changed right now.
+    pub(crate) fn trace(&self, refs: &mut Vec<Obj>) -> bool {
+        let Ok(scope) = self.0.try_borrow() else {
+            return false;
+        };
+
+        refs.extend(scope.slots.iter().filter_map(|(_, val)| Obj::of(val)));
+        refs.extend(
+            scope
+                .methods
+                .values()
+                .map(|(_, method)| Obj::Fn(method.clone())),
+        );
+        refs.extend(scope.parent.clone().map(Obj::Scope));
+
+        true
+    }
+
+    /// Drops everything the scope holds on to, which breaks the cycles it is part of.
+    pub(crate) fn clear(&self) {
+        let scope = mem::take(&mut *self.0.borrow_mut());
+        drop(scope);
+    }
+}
+
+impl PartialEq for Env {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0) || *self.0.borrow() == *other.0.borrow()
+    }
+}
@@ -1,95 +1,511 @@
-use binding_usage::BindingUsage;
-use block::Block;
+pub use binding_usage::{BindingUsage, Slot};
+pub use block::Block;
+pub use call::Call;
+pub use if_expr::If;
+pub use method_call::MethodCall;
+pub use struct_lit::{FieldInit, StructLit};
+pub use try_catch::{Catch, TryCatch};
 
-use crate::{env::Env, util, val::Val};
+use std::{
+    fmt::{self, Display, Formatter},
+    rc::Rc,
+};
+
+use crate::{
+    ast::{Arena, ExprId},
+    env::Env,
+    lexer, limits,
+    symbol::{self, Symbol},
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    unwind::Unwind,
+    val::{Function, Val},
+};
 
 mod binding_usage;
 mod block;
+mod call;
+mod if_expr;
+mod method_call;
+mod struct_lit;
+mod try_catch;
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) struct Number(pub(crate) i32);
-
-impl Number {
-    fn new(s: &str) -> Result<(&str, Self), String> {
-        let (s, number) = util::extract_digits(s)?;
-
-        Ok((s, Number(number.parse().unwrap())))
-    }
-}
+/// An integer literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Number(pub i32);
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) enum Op {
+/// A binary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Op {
     Add,
     Sub,
     Mul,
     Div,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
 }
 
 impl Op {
-    fn new(s: &str) -> Result<(&str, Self), String> {
-        util::tag("+", s)
-            .map(|s| (s, Self::Add))
-            .or_else(|_| util::tag("-", s).map(|s| (s, Self::Sub)))
-            .or_else(|_| util::tag("*", s).map(|s| (s, Self::Mul)))
-            .or_else(|_| util::tag("/", s).map(|s| (s, Self::Div)))
+    fn from_kind(kind: SyntaxKind) -> Option<Self> {
+        match kind {
+            SyntaxKind::Plus => Some(Self::Add),
+            SyntaxKind::Minus => Some(Self::Sub),
+            SyntaxKind::Star => Some(Self::Mul),
+            SyntaxKind::Slash => Some(Self::Div),
+            SyntaxKind::EqualsEquals => Some(Self::Eq),
+            SyntaxKind::BangEquals => Some(Self::NotEq),
+            SyntaxKind::Less => Some(Self::Lt),
+            SyntaxKind::LessEquals => Some(Self::LtEq),
+            SyntaxKind::Greater => Some(Self::Gt),
+            SyntaxKind::GreaterEquals => Some(Self::GtEq),
+            _ => None,
+        }
     }
+
+    /// The name of the method that implements the operator for values other than integers: `add`,
+    /// `sub`, `mul` and `div` for arithmetic, `eq` for both `==` and `!=`, and `cmp` for
+    /// comparisons, which has to return a negative number, zero or a positive number if the
+    /// receiver is less than, equal to or greater than the argument.
+    pub fn method_name(self) -> Symbol {
+        match self {
+            Op::Add => symbol::ADD.clone(),
+            Op::Sub => symbol::SUB.clone(),
+            Op::Mul => symbol::MUL.clone(),
+            Op::Div => symbol::DIV.clone(),
+            Op::Eq | Op::NotEq => symbol::EQ.clone(),
+            Op::Lt | Op::LtEq | Op::Gt | Op::GtEq => symbol::CMP.clone(),
+        }
+    }
+
+    /// Values without a method for the operator can still be compared for equality, which
+    /// compares them structurally.
+    pub(crate) fn eval(
+        self,
+        lhs: Val,
+        rhs: Val,
+        env: &Env,
+        span: TextRange,
+    ) -> Result<Val, Unwind> {
+        match (self, lhs, rhs) {
+            (op, Val::Number(lhs), Val::Number(rhs)) => Ok(op.eval_numbers(lhs, rhs)?),
+            (op, lhs, rhs) => match env.get_method(lhs.type_name(), op.method_name()) {
+                Some(method) => op.eval_method(&method, lhs, rhs, span),
+                None if op == Op::Eq => Ok(Val::Bool(lhs == rhs)),
+                None if op == Op::NotEq => Ok(Val::Bool(lhs != rhs)),
+                None => Err(format!(
+                    "cannot apply '{}' to {} and {}",
+                    op,
+                    lhs.type_name(),
+                    rhs.type_name()
+                )
+                .into()),
+            },
+        }
+    }
+
+    /// Integers are the only values the operators don't look up a method for, so this is all
+    /// there is to evaluating them on integers.
+    pub(crate) fn eval_numbers(self, lhs: i32, rhs: i32) -> Result<Val, String> {
+        let overflow = || "integer overflow".to_string();
+
+        match self {
+            Op::Add => lhs.checked_add(rhs).map(Val::Number).ok_or_else(overflow),
+            Op::Sub => lhs.checked_sub(rhs).map(Val::Number).ok_or_else(overflow),
+            Op::Mul => lhs.checked_mul(rhs).map(Val::Number).ok_or_else(overflow),
+            Op::Div if rhs == 0 => Err("division by zero".to_string()),
+            Op::Div => lhs.checked_div(rhs).map(Val::Number).ok_or_else(overflow),
+            Op::Lt => Ok(Val::Bool(lhs < rhs)),
+            Op::LtEq => Ok(Val::Bool(lhs <= rhs)),
+            Op::Gt => Ok(Val::Bool(lhs > rhs)),
+            Op::GtEq => Ok(Val::Bool(lhs >= rhs)),
+            Op::Eq => Ok(Val::Bool(lhs == rhs)),
+            Op::NotEq => Ok(Val::Bool(lhs != rhs)),
+        }
+    }
+
+    fn eval_method(
+        self,
+        method: &Function,
+        lhs: Val,
+        rhs: Val,
+        span: TextRange,
+    ) -> Result<Val, Unwind> {
+        let result = method.call(vec![lhs, rhs], span)?;
+
+        match (self, result) {
+            (Op::Add | Op::Sub | Op::Mul | Op::Div, result) => Ok(result),
+            (Op::Eq, Val::Bool(eq)) => Ok(Val::Bool(eq)),
+            (Op::NotEq, Val::Bool(eq)) => Ok(Val::Bool(!eq)),
+            (Op::Lt, Val::Number(ord)) => Ok(Val::Bool(ord < 0)),
+            (Op::LtEq, Val::Number(ord)) => Ok(Val::Bool(ord <= 0)),
+            (Op::Gt, Val::Number(ord)) => Ok(Val::Bool(ord > 0)),
+            (Op::GtEq, Val::Number(ord)) => Ok(Val::Bool(ord >= 0)),
+            (op, result) => Err(format!(
+                "method '{}' must return {}, but returned {}",
+                op.method_name(),
+                if matches!(op, Op::Eq | Op::NotEq) {
+                    "Bool"
+                } else {
+                    "Int"
+                },
+                result.type_name()
+            )
+            .into()),
+        }
+    }
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::Eq => "==",
+            Op::NotEq => "!=",
+            Op::Lt => "<",
+            Op::LtEq => "<=",
+            Op::Gt => ">",
+            Op::GtEq => ">=",
+        };
+
+        write!(f, "{}", op)
+    }
+}
+
+/// A prefix operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnaryOp {
+    /// `-`
+    Neg,
+    /// `!`
+    Not,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) enum Expr {
+impl UnaryOp {
+    fn from_kind(kind: SyntaxKind) -> Option<Self> {
+        match kind {
+            SyntaxKind::Minus => Some(Self::Neg),
+            SyntaxKind::Bang => Some(Self::Not),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn eval(self, val: Val) -> Result<Val, String> {
+        match (self, val) {
+            (UnaryOp::Neg, Val::Number(n)) => n
+                .checked_neg()
+                .map(Val::Number)
+                .ok_or_else(|| "integer overflow".to_string()),
+            (UnaryOp::Not, Val::Bool(b)) => Ok(Val::Bool(!b)),
+            (op, val) => Err(format!("cannot apply '{}' to {}", op, val.type_name())),
+        }
+    }
+}
+
+impl Display for UnaryOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UnaryOp::Neg => write!(f, "-"),
+            UnaryOp::Not => write!(f, "!"),
+        }
+    }
+}
+
+/// An expression together with the range of the input it was lowered from.
+///
+/// Expressions built by hand (e.g. by an AST transformation) can use the [`From<ExprKind>`]
+/// implementation, which gives them an empty span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: TextRange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExprKind {
     Number(Number),
-    Operation { lhs: Number, rhs: Number, op: Op },
+    Bool(bool),
+    Str(String),
+    /// `lhs op rhs`
+    Operation {
+        lhs: ExprId,
+        rhs: ExprId,
+        op: Op,
+    },
+    /// `op expr`
+    Unary {
+        op: UnaryOp,
+        expr: ExprId,
+    },
     BindingUsage(BindingUsage),
     Block(Block),
+    If(If),
+    Call(Call),
+    /// `[elements]`
+    List(Vec<ExprId>),
+    /// `base[index]`
+    Index {
+        base: ExprId,
+        index: ExprId,
+    },
+    /// `base.name`
+    Field {
+        base: ExprId,
+        name: Symbol,
+    },
+    StructLit(StructLit),
+    MethodCall(MethodCall),
+    /// `expr?`
+    Try(ExprId),
+    /// `throw expr`
+    Throw(ExprId),
+    TryCatch(TryCatch),
+}
+
+/// The outcome of evaluating an expression in tail position, the last thing the body of a function
+/// does: either its value, or the call it ends with, which is left to be made by
+/// [`Function::call`] once the body's evaluation is done with.
+pub(crate) enum Tail {
+    Val(Val),
+    Call {
+        function: Function,
+        args: Vec<Val>,
+        call_site: TextRange,
+    },
+}
+
+impl Tail {
+    /// Makes the call, if there is one, pinning errors to the call site like the call
+    /// expression would.
+    pub(crate) fn finish(self) -> Result<Val, Unwind> {
+        match self {
+            Tail::Val(val) => Ok(val),
+            Tail::Call {
+                function,
+                args,
+                call_site,
+            } => function
+                .call(args, call_site)
+                .map_err(|unwind| unwind.located(call_site)),
+        }
+    }
+}
+
+impl From<ExprKind> for Expr {
+    #[inline]
+    fn from(kind: ExprKind) -> Self {
+        Self::new(kind, TextRange::default())
+    }
 }
 
 impl Expr {
     #[inline]
-    pub(crate) fn new(s: &str) -> Result<(&str, Self), String> {
-        Self::new_operation(s)
-            .or_else(|_| Self::new_number(s))
-            .or_else(|_| {
-                BindingUsage::new(s)
-                    .map(|(s, binding_usage)| (s, Self::BindingUsage(binding_usage)))
-            })
-            .or_else(|_| Block::new(s).map(|(s, block)| (s, Self::Block(block))))
+    pub fn new(kind: ExprKind, span: TextRange) -> Self {
+        Self { kind, span }
     }
 
-    fn new_operation(s: &str) -> Result<(&str, Self), String> {
-        let (s, lhs) = Number::new(s)?;
-        let (s, _) = util::extract_whitespace(s);
+    /// Lowers `node` and its children into `arena`, returning the ID of the expression.
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<ExprId> {
+        let kind = match node.kind() {
+            SyntaxKind::Literal => Self::lower_literal(node)?,
+            SyntaxKind::BinaryExpr => Self::lower_operation(arena, node)?,
+            SyntaxKind::PrefixExpr => Self::lower_unary(arena, node)?,
+            // Parentheses only affect how the input is parsed, so they don't show up in the AST.
+            SyntaxKind::ParenExpr => return Self::lower(arena, &node.children().next()?),
+            SyntaxKind::BindingUsage => ExprKind::BindingUsage(BindingUsage::lower(node)?),
+            SyntaxKind::Block => ExprKind::Block(Block::lower(arena, node)?),
+            SyntaxKind::IfExpr => ExprKind::If(If::lower(arena, node)?),
+            SyntaxKind::CallExpr => ExprKind::Call(Call::lower(arena, node)?),
+            SyntaxKind::ListExpr => ExprKind::List(
+                node.children()
+                    .map(|element| Self::lower(arena, &element))
+                    .collect::<Option<_>>()?,
+            ),
+            SyntaxKind::IndexExpr => {
+                let mut children = node.children();
 
-        let (s, op) = Op::new(s)?;
-        let (s, _) = util::extract_whitespace(s);
+                ExprKind::Index {
+                    base: Self::lower(arena, &children.next()?)?,
+                    index: Self::lower(arena, &children.next()?)?,
+                }
+            }
+            SyntaxKind::FieldExpr => {
+                let name = node
+                    .tokens()
+                    .find(|token| token.kind() == SyntaxKind::Ident)?;
 
-        let (s, rhs) = Number::new(s)?;
+                ExprKind::Field {
+                    base: Self::lower(arena, &node.children().next()?)?,
+                    name: Symbol::intern(name.text()),
+                }
+            }
+            SyntaxKind::StructLit => ExprKind::StructLit(StructLit::lower(arena, node)?),
+            SyntaxKind::MethodCallExpr => ExprKind::MethodCall(MethodCall::lower(arena, node)?),
+            SyntaxKind::TryExpr => ExprKind::Try(Self::lower(arena, &node.children().next()?)?),
+            SyntaxKind::ThrowExpr => ExprKind::Throw(Self::lower(arena, &node.children().next()?)?),
+            SyntaxKind::TryCatchExpr => ExprKind::TryCatch(TryCatch::lower(arena, node)?),
+            _ => return None,
+        };
 
-        Ok((s, Self::Operation { lhs, rhs, op }))
+        Some(arena.alloc_expr(Self::new(kind, node.text_range())))
     }
 
-    #[inline]
-    fn new_number(s: &str) -> Result<(&str, Self), String> {
-        Number::new(s).map(|(s, number)| (s, Self::Number(number)))
+    fn lower_literal(node: &SyntaxNode) -> Option<ExprKind> {
+        let token = node.tokens().find(|token| !token.kind().is_trivia())?;
+
+        match token.kind() {
+            SyntaxKind::Number => token
+                .text()
+                .parse()
+                .ok()
+                .map(|n| ExprKind::Number(Number(n))),
+            SyntaxKind::TrueKw => Some(ExprKind::Bool(true)),
+            SyntaxKind::FalseKw => Some(ExprKind::Bool(false)),
+            SyntaxKind::String => lexer::unescape_string(token.text()).map(ExprKind::Str),
+            _ => None,
+        }
     }
 
-    pub(crate) fn eval(&self, env: &Env) -> Result<Val, String> {
-        match self {
-            Expr::Number(Number(n)) => Ok(Val::Number(*n)),
-            Expr::Operation { lhs, rhs, op } => {
-                let Number(lhs) = lhs;
-                let Number(rhs) = rhs;
-
-                let result = match op {
-                    Op::Add => lhs + rhs,
-                    Op::Sub => lhs - rhs,
-                    Op::Mul => lhs * rhs,
-                    Op::Div => lhs / rhs,
-                };
+    fn lower_operation(arena: &mut Arena, node: &SyntaxNode) -> Option<ExprKind> {
+        let mut operands = node.children();
+
+        let lhs = Self::lower(arena, &operands.next()?)?;
+        let rhs = Self::lower(arena, &operands.next()?)?;
 
-                Ok(Val::Number(result))
+        let op = node
+            .tokens()
+            .find_map(|token| Op::from_kind(token.kind()))?;
+
+        Some(ExprKind::Operation { lhs, rhs, op })
+    }
+
+    fn lower_unary(arena: &mut Arena, node: &SyntaxNode) -> Option<ExprKind> {
+        let op = node
+            .tokens()
+            .find_map(|token| UnaryOp::from_kind(token.kind()))?;
+
+        let expr = Self::lower(arena, &node.children().next()?)?;
+
+        Some(ExprKind::Unary { op, expr })
+    }
+
+    /// Errors that haven't been pinned to a part of the input yet are pinned to this expression.
+    ///
+    /// The children of the expression are looked up in `arena`, which functions defined in it keep
+    /// around so that their bodies can be evaluated when they are called.
+    pub(crate) fn eval(&self, arena: &Rc<Arena>, env: &Env) -> Result<Val, Unwind> {
+        limits::step().map_err(|unwind| unwind.located(self.span))?;
+
+        self.eval_kind(arena, env)
+            .map_err(|unwind| unwind.located(self.span))
+    }
+
+    /// Evaluates the expression in tail position, where calls in the branches it ends with are
+    /// left to the caller.
+    pub(crate) fn eval_tail(&self, arena: &Rc<Arena>, env: &Env) -> Result<Tail, Unwind> {
+        let tail = limits::step().and_then(|()| match &self.kind {
+            ExprKind::Block(block) => block.eval_tail(arena, env),
+            ExprKind::If(if_expr) => if_expr.eval_tail(arena, env),
+            ExprKind::Call(call) => call.eval_tail(arena, env, self.span),
+            ExprKind::MethodCall(method_call) => method_call.eval_tail(arena, env, self.span),
+            _ => self.eval_kind(arena, env).map(Tail::Val),
+        });
+
+        tail.map_err(|unwind| unwind.located(self.span))
+    }
+
+    fn eval_kind(&self, arena: &Rc<Arena>, env: &Env) -> Result<Val, Unwind> {
+        let eval = |expr: ExprId| arena[expr].eval(arena, env);
+
+        match &self.kind {
+            ExprKind::Number(Number(n)) => Ok(Val::Number(*n)),
+            ExprKind::Bool(b) => Ok(Val::Bool(*b)),
+            ExprKind::Str(s) => limits::allocated(Val::Str(s.clone())),
+            ExprKind::Operation { lhs, rhs, op } => {
+                op.eval(eval(*lhs)?, eval(*rhs)?, env, self.span)
+            }
+            ExprKind::Unary { op, expr } => Ok(op.eval(eval(*expr)?)?),
+            ExprKind::BindingUsage(binding_usage) => binding_usage.eval(env),
+            ExprKind::Block(block) => block.eval(arena, env),
+            ExprKind::If(if_expr) => if_expr.eval(arena, env),
+            ExprKind::Call(call) => call.eval(arena, env, self.span),
+            ExprKind::List(elements) => limits::allocated(Val::List(
+                elements
+                    .iter()
+                    .map(|&element| eval(element))
+                    .collect::<Result<_, _>>()?,
+            )),
+            ExprKind::Index { base, index } => Ok(Self::eval_index(eval(*base)?, eval(*index)?)?),
+            ExprKind::Field { base, name } => Ok(Self::eval_field(eval(*base)?, name.clone())?),
+            ExprKind::StructLit(struct_lit) => struct_lit.eval(arena, env),
+            ExprKind::MethodCall(method_call) => method_call.eval(arena, env, self.span),
+            ExprKind::Try(expr) => Self::eval_try(eval(*expr)?),
+            ExprKind::Throw(expr) => Err(Unwind::throw(eval(*expr)?)),
+            ExprKind::TryCatch(try_catch) => try_catch.eval(arena, env),
+        }
+    }
+
+    /// Unwraps `Some` and `Ok`, while `None` and `Err` are returned from the enclosing function.
+    pub(crate) fn eval_try(val: Val) -> Result<Val, Unwind> {
+        match val {
+            Val::Option(Some(val)) | Val::Result(Ok(val)) => Ok((*val).clone()),
+            val @ (Val::Option(None) | Val::Result(Err(_))) => Err(Unwind::Return(val)),
+            val => Err(format!("cannot apply '?' to a value of type {}", val.type_name()).into()),
+        }
+    }
+
+    pub(crate) fn eval_index(base: Val, index: Val) -> Result<Val, String> {
+        let elements = match base {
+            Val::List(elements) => elements,
+            val => {
+                return Err(format!(
+                    "cannot index into a value of type {}",
+                    val.type_name()
+                ))
+            }
+        };
+
+        let index = match index {
+            Val::Number(index) => index,
+            val => {
+                return Err(format!(
+                    "expected Int as index, but found {}",
+                    val.type_name()
+                ))
             }
-            Expr::BindingUsage(binding_usage) => binding_usage.eval(env),
-            Expr::Block(block) => block.eval(env),
+        };
+
+        usize::try_from(index)
+            .ok()
+            .and_then(|idx| elements.get(idx).cloned())
+            .ok_or_else(|| {
+                format!(
+                    "index {} is out of bounds for a list of length {}",
+                    index,
+                    elements.len()
+                )
+            })
+    }
+
+    pub(crate) fn eval_field(base: Val, name: Symbol) -> Result<Val, String> {
+        match base {
+            Val::Struct(instance) => instance
+                .field(name.clone())
+                .cloned()
+                .ok_or_else(|| format!("struct '{}' has no field '{}'", instance.name, name)),
+            val => Err(format!(
+                "cannot access field '{}' on a value of type {}",
+                name,
+                val.type_name()
+            )),
         }
     }
 }
@@ -98,12 +514,38 @@ impl Expr {
 mod tests {
     use super::*;
 
+    use crate::{ast::StmtId, stmt::Stmt};
+
+    fn number(n: i32, start: usize, end: usize) -> Expr {
+        Expr::new(ExprKind::Number(Number(n)), TextRange::new(start, end))
+    }
+
+    /// What parsing an expression statement whose expression is `expr` results in.
+    fn expr_stmt(mut arena: Arena, expr: ExprId) -> (Arena, StmtId) {
+        let stmt = arena.alloc_stmt(Stmt::Expr(expr));
+        (arena, stmt)
+    }
+
     mod number {
         use super::*;
 
         #[test]
         fn parse_number() {
-            assert_eq!(Number::new("132"), Ok(("", Number(132))));
+            let mut arena = Arena::default();
+            let expr = arena.alloc_expr(number(132, 0, 3));
+
+            assert_eq!(crate::parse_stmt("132"), Ok(expr_stmt(arena, expr)));
+        }
+
+        #[test]
+        fn cannot_parse_number_out_of_range() {
+            assert_eq!(
+                crate::parse_stmt("99999999999"),
+                Err(vec![
+                    "error at 0..11: expected 32-bit integer, but found number '99999999999'"
+                        .to_string()
+                ])
+            );
         }
     }
 
@@ -112,112 +554,300 @@ mod tests {
 
         #[test]
         fn parse_add_op() {
-            assert_eq!(Op::new("+"), Ok(("", Op::Add)));
+            assert_eq!(Op::from_kind(SyntaxKind::Plus), Some(Op::Add));
         }
 
         #[test]
         fn parse_sub_op() {
-            assert_eq!(Op::new("-"), Ok(("", Op::Sub)));
+            assert_eq!(Op::from_kind(SyntaxKind::Minus), Some(Op::Sub));
         }
 
         #[test]
         fn parse_mul_op() {
-            assert_eq!(Op::new("*"), Ok(("", Op::Mul)));
+            assert_eq!(Op::from_kind(SyntaxKind::Star), Some(Op::Mul));
         }
 
         #[test]
         fn parse_div_op() {
-            assert_eq!(Op::new("/"), Ok(("", Op::Div)));
+            assert_eq!(Op::from_kind(SyntaxKind::Slash), Some(Op::Div));
+        }
+
+        #[test]
+        fn parse_comparison_ops() {
+            assert_eq!(Op::from_kind(SyntaxKind::EqualsEquals), Some(Op::Eq));
+            assert_eq!(Op::from_kind(SyntaxKind::BangEquals), Some(Op::NotEq));
+            assert_eq!(Op::from_kind(SyntaxKind::Less), Some(Op::Lt));
+            assert_eq!(Op::from_kind(SyntaxKind::LessEquals), Some(Op::LtEq));
+            assert_eq!(Op::from_kind(SyntaxKind::Greater), Some(Op::Gt));
+            assert_eq!(Op::from_kind(SyntaxKind::GreaterEquals), Some(Op::GtEq));
         }
     }
 
     mod expr {
         use super::*;
 
-        use binding_usage::BindingUsage;
-        use block::Block;
-
-        use crate::stmt::Stmt;
-
         mod parse {
             use super::*;
 
             #[test]
             fn parse_one_plus_two() {
-                assert_eq!(
-                    Expr::new("1+2"),
-                    Ok((
-                        "",
-                        Expr::Operation {
-                            lhs: Number(1),
-                            rhs: Number(2),
-                            op: Op::Add
-                        }
-                    ))
-                )
+                let mut arena = Arena::default();
+                let lhs = arena.alloc_expr(number(1, 0, 1));
+                let rhs = arena.alloc_expr(number(2, 2, 3));
+                let expr = arena.alloc_expr(Expr::new(
+                    ExprKind::Operation {
+                        lhs,
+                        rhs,
+                        op: Op::Add,
+                    },
+                    TextRange::new(0, 3),
+                ));
+
+                assert_eq!(crate::parse_stmt("1+2"), Ok(expr_stmt(arena, expr)))
             }
 
             #[test]
             fn parse_expr_with_whitespace() {
+                let mut arena = Arena::default();
+                let lhs = arena.alloc_expr(number(2, 0, 1));
+                let rhs = arena.alloc_expr(number(2, 4, 5));
+                let expr = arena.alloc_expr(Expr::new(
+                    ExprKind::Operation {
+                        lhs,
+                        rhs,
+                        op: Op::Mul,
+                    },
+                    TextRange::new(0, 5),
+                ));
+
+                assert_eq!(crate::parse_stmt("2 * 2"), Ok(expr_stmt(arena, expr)));
+            }
+
+            #[test]
+            fn parse_nested_operations_by_precedence() {
+                let mut arena = Arena::default();
+                let one = arena.alloc_expr(number(1, 0, 1));
+                let two = arena.alloc_expr(number(2, 4, 5));
+                let three = arena.alloc_expr(number(3, 8, 9));
+                let mul = arena.alloc_expr(Expr::new(
+                    ExprKind::Operation {
+                        lhs: two,
+                        rhs: three,
+                        op: Op::Mul,
+                    },
+                    TextRange::new(4, 9),
+                ));
+                let add = arena.alloc_expr(Expr::new(
+                    ExprKind::Operation {
+                        lhs: one,
+                        rhs: mul,
+                        op: Op::Add,
+                    },
+                    TextRange::new(0, 9),
+                ));
+                let seven = arena.alloc_expr(number(7, 13, 14));
+                let eq = arena.alloc_expr(Expr::new(
+                    ExprKind::Operation {
+                        lhs: add,
+                        rhs: seven,
+                        op: Op::Eq,
+                    },
+                    TextRange::new(0, 14),
+                ));
+
                 assert_eq!(
-                    Expr::new("2 * 2"),
-                    Ok((
-                        "",
-                        Expr::Operation {
-                            lhs: Number(2),
-                            rhs: Number(2),
-                            op: Op::Mul
-                        }
-                    ))
+                    crate::parse_stmt("1 + 2 * 3 == 7"),
+                    Ok(expr_stmt(arena, eq))
                 );
             }
 
+            #[test]
+            fn parentheses_are_not_kept() {
+                let mut arena = Arena::default();
+                let expr = arena.alloc_expr(number(5, 2, 3));
+
+                assert_eq!(crate::parse_stmt("((5))"), Ok(expr_stmt(arena, expr)));
+            }
+
             #[test]
             fn parse_number_as_expr() {
-                assert_eq!(Expr::new("456"), Ok(("", Expr::Number(Number(456)))))
+                let mut arena = Arena::default();
+                let expr = arena.alloc_expr(number(456, 0, 3));
+
+                assert_eq!(crate::parse_stmt("456"), Ok(expr_stmt(arena, expr)))
             }
 
             #[test]
-            fn parse_binding_usage() {
+            fn parse_bools() {
+                for (s, b) in [("true", true), ("false", false)] {
+                    let mut arena = Arena::default();
+                    let expr =
+                        arena.alloc_expr(Expr::new(ExprKind::Bool(b), TextRange::new(0, s.len())));
+
+                    assert_eq!(crate::parse_stmt(s), Ok(expr_stmt(arena, expr)));
+                }
+            }
+
+            #[test]
+            fn parse_string_with_escapes() {
+                let mut arena = Arena::default();
+                let expr = arena.alloc_expr(Expr::new(
+                    ExprKind::Str("a\n\"b\"".to_string()),
+                    TextRange::new(0, 10),
+                ));
+
                 assert_eq!(
-                    Expr::new("bar"),
-                    Ok((
-                        "",
-                        Expr::BindingUsage(BindingUsage {
-                            name: "bar".to_string(),
-                        }),
-                    )),
+                    crate::parse_stmt(r#""a\n\"b\"""#),
+                    Ok(expr_stmt(arena, expr))
                 );
             }
 
             #[test]
-            fn parse_block() {
-                assert_eq!(
-                    Expr::new("{ 200 }"),
-                    Ok((
-                        "",
-                        Expr::Block(Block {
-                            stmts: vec![Stmt::Expr(Expr::Number(Number(200)))],
+            fn parse_unary() {
+                let mut arena = Arena::default();
+                let operand = arena.alloc_expr(number(5, 1, 2));
+                let expr = arena.alloc_expr(Expr::new(
+                    ExprKind::Unary {
+                        op: UnaryOp::Neg,
+                        expr: operand,
+                    },
+                    TextRange::new(0, 2),
+                ));
+
+                assert_eq!(crate::parse_stmt("-5"), Ok(expr_stmt(arena, expr)));
+            }
+
+            #[test]
+            fn parse_index_and_field() {
+                let binding_usage = |name: &str, start, end| {
+                    Expr::new(
+                        ExprKind::BindingUsage(BindingUsage {
+                            name: name.into(),
+                            slot: None,
                         }),
-                    )),
-                );
+                        TextRange::new(start, end),
+                    )
+                };
+
+                let mut arena = Arena::default();
+                let a = arena.alloc_expr(binding_usage("a", 0, 1));
+                let field = arena.alloc_expr(Expr::new(
+                    ExprKind::Field {
+                        base: a,
+                        name: "b".into(),
+                    },
+                    TextRange::new(0, 3),
+                ));
+                let i = arena.alloc_expr(binding_usage("i", 4, 5));
+                let index = arena.alloc_expr(Expr::new(
+                    ExprKind::Index {
+                        base: field,
+                        index: i,
+                    },
+                    TextRange::new(0, 6),
+                ));
+
+                assert_eq!(crate::parse_stmt("a.b[i]"), Ok(expr_stmt(arena, index)));
+            }
+
+            #[test]
+            fn parse_list() {
+                let mut arena = Arena::default();
+                let elements = vec![
+                    arena.alloc_expr(number(1, 1, 2)),
+                    arena.alloc_expr(number(2, 4, 5)),
+                ];
+                let list =
+                    arena.alloc_expr(Expr::new(ExprKind::List(elements), TextRange::new(0, 7)));
+
+                assert_eq!(crate::parse_stmt("[1, 2,]"), Ok(expr_stmt(arena, list)));
+            }
+
+            #[test]
+            fn parse_binding_usage() {
+                let mut arena = Arena::default();
+                let expr = arena.alloc_expr(Expr::new(
+                    ExprKind::BindingUsage(BindingUsage {
+                        name: "bar".into(),
+                        slot: None,
+                    }),
+                    TextRange::new(0, 3),
+                ));
+
+                assert_eq!(crate::parse_stmt("bar"), Ok(expr_stmt(arena, expr)));
+            }
+
+            #[test]
+            fn parse_block() {
+                let mut arena = Arena::default();
+                let number = arena.alloc_expr(number(200, 2, 5));
+                let stmt = arena.alloc_stmt(Stmt::Expr(number));
+                let block = arena.alloc_expr(Expr::new(
+                    ExprKind::Block(Block { stmts: vec![stmt] }),
+                    TextRange::new(0, 7),
+                ));
+
+                assert_eq!(crate::parse_stmt("{ 200 }"), Ok(expr_stmt(arena, block)));
+            }
+
+            /// Each expression has an ID of its own, so that information about it can be kept
+            /// outside of the AST.
+            #[test]
+            fn side_table_keyed_by_expr_id() {
+                let parse = crate::parse("1 + 2 + 3");
+                let source_file = parse.source_file().unwrap();
+                let arena = source_file.arena();
+
+                let mut numbers = std::collections::HashMap::new();
+                for (id, expr) in arena.exprs() {
+                    if let ExprKind::Number(Number(n)) = expr.kind {
+                        numbers.insert(id, n);
+                    }
+                }
+
+                let Stmt::Expr(sum) = arena[source_file.stmts[0]] else {
+                    panic!("expected an expression statement")
+                };
+                let ExprKind::Operation { lhs, rhs, .. } = arena[sum].kind else {
+                    panic!("expected an operation")
+                };
+
+                assert_eq!(numbers.len(), 3);
+                assert_eq!(numbers.get(&lhs), None);
+                assert_eq!(numbers.get(&rhs), Some(&3));
             }
         }
 
         mod eval {
             use super::*;
 
+            use std::rc::Rc;
+
             use crate::env::Env;
 
+            fn operation(lhs: Val, rhs: Val, op: Op) -> Result<Val, String> {
+                op.eval(lhs, rhs, &Env::default(), TextRange::default())
+                    .map_err(|unwind| unwind.into_error().message)
+            }
+
+            fn eval(s: &str) -> Result<Val, String> {
+                crate::parse(s).eval(&mut Env::default())
+            }
+
             #[test]
             fn eval_add() {
+                let mut arena = Arena::default();
+                let lhs = arena.alloc_expr(ExprKind::Number(Number(10)));
+                let rhs = arena.alloc_expr(ExprKind::Number(Number(20)));
+                let expr = arena.alloc_expr(ExprKind::Operation {
+                    lhs,
+                    rhs,
+                    op: Op::Add,
+                });
+                let arena = Rc::new(arena);
+
                 assert_eq!(
-                    Expr::Operation {
-                        lhs: Number(10),
-                        rhs: Number(20),
-                        op: Op::Add
-                    }
-                    .eval(&Env::default()),
+                    arena[expr].eval(&arena, &Env::default()),
                     Ok(Val::Number(30))
                 );
             }
@@ -225,12 +855,7 @@ mod tests {
             #[test]
             fn eval_sub() {
                 assert_eq!(
-                    Expr::Operation {
-                        lhs: Number(1),
-                        rhs: Number(5),
-                        op: Op::Sub
-                    }
-                    .eval(&Env::default()),
+                    operation(Val::Number(1), Val::Number(5), Op::Sub),
                     Ok(Val::Number(-4))
                 );
             }
@@ -238,12 +863,7 @@ mod tests {
             #[test]
             fn eval_mul() {
                 assert_eq!(
-                    Expr::Operation {
-                        lhs: Number(5),
-                        rhs: Number(6),
-                        op: Op::Mul
-                    }
-                    .eval(&Env::default()),
+                    operation(Val::Number(5), Val::Number(6), Op::Mul),
                     Ok(Val::Number(30))
                 );
             }
@@ -251,37 +871,198 @@ mod tests {
             #[test]
             fn eval_div() {
                 assert_eq!(
-                    Expr::Operation {
-                        lhs: Number(200),
-                        rhs: Number(20),
-                        op: Op::Div
-                    }
-                    .eval(&Env::default()),
+                    operation(Val::Number(200), Val::Number(20), Op::Div),
                     Ok(Val::Number(10))
                 );
             }
 
             #[test]
-            fn eval_binding_usage() {
+            fn eval_nested_operations() {
+                assert_eq!(eval("(1 + 2) * -3"), Ok(Val::Number(-9)));
+            }
+
+            #[test]
+            fn eval_comparisons() {
+                assert_eq!(eval("1 < 2"), Ok(Val::Bool(true)));
+                assert_eq!(eval("2 <= 1"), Ok(Val::Bool(false)));
+                assert_eq!(eval("\"a\" == \"a\""), Ok(Val::Bool(true)));
+                assert_eq!(eval("!(1 != 2)"), Ok(Val::Bool(false)));
+            }
+
+            #[test]
+            fn division_by_zero() {
+                assert_eq!(
+                    operation(Val::Number(1), Val::Number(0), Op::Div),
+                    Err("division by zero".to_string())
+                );
+            }
+
+            #[test]
+            fn overflow() {
+                assert_eq!(
+                    operation(Val::Number(i32::MAX), Val::Number(1), Op::Add),
+                    Err("integer overflow".to_string())
+                );
+            }
+
+            #[test]
+            fn cannot_add_bools() {
+                assert_eq!(
+                    operation(Val::Bool(true), Val::Number(1), Op::Add),
+                    Err("cannot apply '+' to Bool and Int".to_string())
+                );
+            }
+
+            #[test]
+            fn overloaded_operators() {
+                let vec2 = "struct V { x: Int, y: Int }
+trait Add { fn add(self, other: V) -> V }
+trait Eq { fn eq(self, other: V) -> Bool }
+trait Ord { fn cmp(self, other: V) -> Int }
+impl Add for V { fn add(self, other: V) -> V { V { x: self.x + other.x, y: self.y + other.y } } }
+impl Eq for V { fn eq(self, other: V) -> Bool { self.x == other.x } }
+impl Ord for V { fn cmp(self, other: V) -> Int { self.x - other.x } }
+";
+
                 let mut env = Env::default();
-                env.store_binding("ten", Val::Number(10));
+                crate::parse(vec2).eval(&mut env).unwrap();
+
+                let eval = |s: &str| crate::parse(s).eval(&mut env.clone());
 
                 assert_eq!(
-                    Expr::BindingUsage(BindingUsage {
-                        name: "ten".to_string(),
-                    })
-                    .eval(&env),
-                    Ok(Val::Number(10)),
+                    eval("(V { x: 1, y: 2 } + V { x: 3, y: 4 }).y"),
+                    Ok(Val::Number(6))
+                );
+                assert_eq!(
+                    eval("V { x: 1, y: 2 } == V { x: 1, y: 3 }"),
+                    Ok(Val::Bool(true))
                 );
+                assert_eq!(
+                    eval("V { x: 1, y: 2 } != V { x: 1, y: 3 }"),
+                    Ok(Val::Bool(false))
+                );
+                assert_eq!(
+                    eval("[V { x: 1, y: 0 } < V { x: 2, y: 0 }, V { x: 1, y: 0 } >= V { x: 2, y: 0 }]"),
+                    Ok(Val::List(vec![Val::Bool(true), Val::Bool(false)].into()))
+                );
+            }
+
+            #[test]
+            fn structs_without_eq_are_compared_structurally() {
+                assert_eq!(
+                    eval("struct P { x: Int }\nP { x: 1 } == P { x: 1 }"),
+                    Ok(Val::Bool(true))
+                );
+            }
+
+            #[test]
+            fn cmp_must_return_int() {
+                assert_eq!(
+                    eval("trait Ord { fn cmp(self, other: Str) -> Bool }\nimpl Ord for Str { fn cmp(self, other) -> Bool { true } }\n\"a\" < \"b\""),
+                    Err("method 'cmp' must return Int, but returned Bool".to_string())
+                );
+            }
+
+            #[test]
+            fn try_unwraps_or_returns_from_the_function() {
+                let half = "fn half(n) { if n / 2 * 2 == n { Some(n / 2) } else { None } }
+fn quarter(n) { Some(half(half(n)?)?) }
+fn check(n) { if n < 0 { Err(\"negative\") } else { Ok(n) } }
+fn double(n) { Ok({ check(n)? } * 2) }
+";
+
+                let mut env = Env::default();
+                crate::parse(half).eval(&mut env).unwrap();
+
+                let eval = |s: &str| crate::parse(s).eval(&mut env.clone());
+
+                assert_eq!(
+                    eval("quarter(8)"),
+                    Ok(Val::Option(Some(Rc::new(Val::Number(2)))))
+                );
+                assert_eq!(eval("quarter(6)"), Ok(Val::Option(None)));
+                assert_eq!(
+                    eval("double(2)").map(|val| val.to_string()),
+                    Ok("Ok(4)".to_string())
+                );
+                assert_eq!(
+                    eval("double(-1)").map(|val| val.to_string()),
+                    Ok("Err(\"negative\")".to_string())
+                );
+            }
+
+            #[test]
+            fn try_outside_of_function() {
+                assert_eq!(eval("Ok(1)?"), Ok(Val::Number(1)));
+                assert_eq!(
+                    eval("None?"),
+                    Err("the '?' operator can only be used inside a function".to_string())
+                );
+                assert_eq!(
+                    eval("fn f() { 1? }\nf()"),
+                    Err("cannot apply '?' to a value of type Int".to_string())
+                );
+            }
+
+            #[test]
+            fn eval_list_and_index() {
+                assert_eq!(eval("[1, 2 + 3][1]"), Ok(Val::Number(5)));
+                assert_eq!(
+                    eval("[1, 2]"),
+                    Ok(Val::List(vec![Val::Number(1), Val::Number(2)].into()))
+                );
+            }
+
+            #[test]
+            fn index_out_of_bounds() {
+                assert_eq!(
+                    eval("[1, 2][-1]"),
+                    Err("index -1 is out of bounds for a list of length 2".to_string())
+                );
+            }
+
+            #[test]
+            fn cannot_index_into_number() {
+                assert_eq!(
+                    eval("1[0]"),
+                    Err("cannot index into a value of type Int".to_string())
+                );
+            }
+
+            #[test]
+            fn cannot_access_field_of_number() {
+                assert_eq!(
+                    eval("let a = 1\na.x"),
+                    Err("cannot access field 'x' on a value of type Int".to_string())
+                );
+            }
+
+            #[test]
+            fn eval_binding_usage() {
+                let mut env = Env::default();
+                env.store_binding("ten".into(), Val::Number(10));
+
+                let mut arena = Arena::default();
+                let expr = arena.alloc_expr(ExprKind::BindingUsage(BindingUsage {
+                    name: "ten".into(),
+                    slot: Some(Slot { depth: 0, index: 0 }),
+                }));
+                let arena = Rc::new(arena);
+
+                assert_eq!(arena[expr].eval(&arena, &env), Ok(Val::Number(10)));
             }
+
             #[test]
             fn eval_block() {
+                let mut arena = Arena::default();
+                let number = arena.alloc_expr(ExprKind::Number(Number(10)));
+                let stmt = arena.alloc_stmt(Stmt::Expr(number));
+                let expr = arena.alloc_expr(ExprKind::Block(Block { stmts: vec![stmt] }));
+                let arena = Rc::new(arena);
+
                 assert_eq!(
-                    Expr::Block(Block {
-                        stmts: vec![Stmt::Expr(Expr::Number(Number(10)))],
-                    })
-                    .eval(&Env::default()),
-                    Ok(Val::Number(10)),
+                    arena[expr].eval(&arena, &Env::default()),
+                    Ok(Val::Number(10))
                 );
             }
         }
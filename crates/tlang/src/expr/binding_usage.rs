@@ -1,25 +1,46 @@
-use crate::{env::Env, util, val::Val};
+use crate::{
+    builtins,
+    env::Env,
+    symbol::Symbol,
+    syntax::{SyntaxKind, SyntaxNode},
+    unwind::Unwind,
+    val::Val,
+};
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) struct BindingUsage {
-    pub(crate) name: String,
+/// A reference to a binding by its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingUsage {
+    pub name: Symbol,
+    /// Where the binding's value is kept, as found by the [resolver](crate::resolve). It is
+    /// `None` before resolving, and for built-in bindings.
+    pub slot: Option<Slot>,
+}
+
+/// The slot at `index` of the environment `depth` levels up from the one a binding is used in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slot {
+    pub depth: usize,
+    pub index: usize,
 }
 
 impl BindingUsage {
-    pub(crate) fn new(s: &str) -> Result<(&str, Self), String> {
-        let (s, name) = util::extract_ident(s)?;
-
-        Ok((
-            s,
-            Self {
-                name: name.to_string(),
-            },
-        ))
+    pub(crate) fn lower(node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            slot: None,
+        })
     }
 
-    #[inline]
-    pub(crate) fn eval(&self, env: &Env) -> Result<Val, String> {
-        env.get_binding_value(&self.name)
+    /// Usages the resolver found no slot for can only refer to built-in bindings.
+    pub(crate) fn eval(&self, env: &Env) -> Result<Val, Unwind> {
+        match self.slot {
+            Some(Slot { depth, index }) => Ok(env.get_slot(depth, index)),
+            None => Ok(builtins::lookup(self.name.clone())?),
+        }
     }
 }
 
@@ -27,45 +48,93 @@ impl BindingUsage {
 mod tests {
     use super::*;
 
-    use crate::{env::Env, val::Val};
+    use crate::{
+        ast::Arena,
+        env::Env,
+        expr::{Expr, ExprKind},
+        stmt::Stmt,
+        syntax::TextRange,
+        val::Val,
+    };
 
     #[test]
     fn parse_binding_usage() {
-        assert_eq!(
-            BindingUsage::new("abc"),
-            Ok((
-                "",
-                BindingUsage {
-                    name: "abc".to_string()
-                }
-            ))
-        )
+        let mut arena = Arena::default();
+        let expr = arena.alloc_expr(Expr::new(
+            ExprKind::BindingUsage(BindingUsage {
+                name: "abc".into(),
+                slot: None,
+            }),
+            TextRange::new(0, 3),
+        ));
+        let stmt = arena.alloc_stmt(Stmt::Expr(expr));
+
+        assert_eq!(crate::parse_stmt("abc"), Ok((arena, stmt)))
     }
 
     #[test]
     fn eval_existing_binding_usage() {
         let mut env = Env::default();
-        env.store_binding("foo", Val::Number(10));
+        env.store_binding("foo".into(), Val::Number(10));
 
         assert_eq!(
             BindingUsage {
-                name: "foo".to_string()
+                name: "foo".into(),
+                slot: Some(Slot { depth: 0, index: 0 }),
             }
             .eval(&env),
             Ok(Val::Number(10))
         )
     }
 
+    #[test]
+    fn eval_binding_usage_from_slot() {
+        let mut outer = Env::default();
+        outer.store_binding("foo".into(), Val::Number(1));
+        outer.store_binding("bar".into(), Val::Number(2));
+
+        let mut inner = outer.create_child();
+        inner.store_binding("bar".into(), Val::Number(3));
+
+        // The slot is used as is, without looking at the names in the scopes it skips.
+        assert_eq!(
+            BindingUsage {
+                name: "bar".into(),
+                slot: Some(Slot { depth: 1, index: 1 }),
+            }
+            .eval(&inner),
+            Ok(Val::Number(2))
+        );
+    }
+
+    #[test]
+    fn eval_builtin_binding_usage() {
+        assert_eq!(
+            BindingUsage {
+                name: "None".into(),
+                slot: None,
+            }
+            .eval(&Env::default()),
+            Ok(Val::Option(None))
+        );
+    }
+
+    /// Usages without a slot don't look for bindings of the environment by name, which the
+    /// resolver didn't find them in either.
     #[test]
     fn eval_non_existent_binding_usage() {
-        let empty_env = Env::default();
+        let mut env = Env::default();
+        env.store_binding("i_dont_exist".into(), Val::Number(10));
 
         assert_eq!(
             BindingUsage {
-                name: "i_dont_exist".to_string()
+                name: "i_dont_exist".into(),
+                slot: None,
             }
-            .eval(&empty_env),
-            Err("binding with name 'i_dont_exist' does not exist".to_string())
+            .eval(&env),
+            Err(Unwind::from(
+                "binding with name 'i_dont_exist' does not exist".to_string()
+            ))
         );
     }
 }
@@ -1,45 +1,48 @@
-use crate::{env::Env, stmt::Stmt, util, val::Val};
+use std::rc::Rc;
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) struct Block {
-    pub(crate) stmts: Vec<Stmt>,
+use super::Tail;
+use crate::{
+    ast::{Arena, StmtId},
+    env::Env,
+    stmt::Stmt,
+    syntax::SyntaxNode,
+    unwind::Unwind,
+    val::Val,
+};
+
+/// `{ stmts }`, evaluated in a child environment of the one it appears in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub stmts: Vec<StmtId>,
 }
 
 impl Block {
-    pub(crate) fn new(s: &str) -> Result<(&str, Self), String> {
-        let s = util::tag("{", s)?;
-        let (s, _) = util::extract_whitespace(s);
-
-        let mut s = s;
-        let mut stmts = Vec::new();
-
-        while let Ok((new_s, stmt)) = Stmt::new(s) {
-            s = new_s;
-            stmts.push(stmt);
-
-            let (new_s, _) = util::extract_whitespace(s);
-            s = new_s;
-        }
-
-        let (s, _) = util::extract_whitespace(s);
-        let s = util::tag("}", s)?;
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        Stmt::lower_all(arena, node).map(|stmts| Self { stmts })
+    }
 
-        Ok((s, Block { stmts }))
+    pub(crate) fn eval(&self, arena: &Rc<Arena>, env: &Env) -> Result<Val, Unwind> {
+        self.eval_tail(arena, env)?.finish()
     }
 
-    pub(crate) fn eval(&self, env: &Env) -> Result<Val, String> {
+    /// Evaluates the block in tail position, where the last statement is too if it is an
+    /// expression.
+    pub(crate) fn eval_tail(&self, arena: &Rc<Arena>, env: &Env) -> Result<Tail, Unwind> {
         if self.stmts.is_empty() {
-            Ok(Val::Unit)
+            Ok(Tail::Val(Val::Unit))
         } else {
             let mut child_env = env.create_child();
 
             let smtms_except_last = &self.stmts[..self.stmts.len() - 1];
 
-            for stmt in smtms_except_last {
-                stmt.eval(&mut child_env)?;
+            for &stmt in smtms_except_last {
+                arena[stmt].eval(arena, &mut child_env)?;
             }
 
-            self.stmts.last().unwrap().eval(&mut child_env)
+            match &arena[*self.stmts.last().unwrap()] {
+                Stmt::Expr(expr) => arena[*expr].eval_tail(arena, &child_env),
+                stmt => stmt.eval(arena, &mut child_env).map(Tail::Val),
+            }
         }
     }
 }
@@ -47,68 +50,157 @@ impl Block {
 #[cfg(test)]
 mod tests {
     use super::{
-        super::{binding_usage::BindingUsage, Expr, Number},
+        super::{binding_usage::BindingUsage, Expr, ExprKind, Number, Slot},
         *,
     };
 
-    use crate::binding_def::BindingDef;
+    use crate::{ast::ExprId, binding_def::BindingDef, syntax::TextRange};
+
+    fn number(arena: &mut Arena, n: i32) -> ExprId {
+        arena.alloc_expr(ExprKind::Number(Number(n)))
+    }
+
+    fn binding_usage(arena: &mut Arena, name: &str, depth: usize, index: usize) -> ExprId {
+        arena.alloc_expr(ExprKind::BindingUsage(BindingUsage {
+            name: name.into(),
+            slot: Some(Slot { depth, index }),
+        }))
+    }
+
+    fn binding_def(arena: &mut Arena, name: &str, val: ExprId) -> StmtId {
+        arena.alloc_stmt(Stmt::BindingDef(BindingDef {
+            name: name.into(),
+            ty: None,
+            val,
+            span: TextRange::default(),
+        }))
+    }
 
     mod parse {
         use super::*;
 
+        /// What parsing a block with `stmts` as its statements results in.
+        fn block_stmt(mut arena: Arena, stmts: Vec<StmtId>, span: TextRange) -> (Arena, StmtId) {
+            let block = arena.alloc_expr(Expr::new(ExprKind::Block(Block { stmts }), span));
+            let stmt = arena.alloc_stmt(Stmt::Expr(block));
+
+            (arena, stmt)
+        }
+
+        fn usage_at(arena: &mut Arena, name: &str, start: usize, end: usize) -> ExprId {
+            arena.alloc_expr(Expr::new(
+                ExprKind::BindingUsage(BindingUsage {
+                    name: name.into(),
+                    slot: None,
+                }),
+                TextRange::new(start, end),
+            ))
+        }
+
         #[test]
         fn parse_empty_block() {
-            assert_eq!(Block::new("{}"), Ok(("", Block { stmts: Vec::new() })));
+            assert_eq!(
+                crate::parse_stmt("{}"),
+                Ok(block_stmt(
+                    Arena::default(),
+                    Vec::new(),
+                    TextRange::new(0, 2)
+                ))
+            );
         }
 
         #[test]
         fn parse_empty_block_with_whitespace() {
-            assert_eq!(Block::new("{   }"), Ok(("", Block { stmts: Vec::new() })));
+            assert_eq!(
+                crate::parse_stmt("{   }"),
+                Ok(block_stmt(
+                    Arena::default(),
+                    Vec::new(),
+                    TextRange::new(0, 5)
+                ))
+            );
         }
 
         #[test]
         fn parse_block_with_one_stmt() {
+            let mut arena = Arena::default();
+            let five =
+                arena.alloc_expr(Expr::new(ExprKind::Number(Number(5)), TextRange::new(2, 3)));
+            let stmt = arena.alloc_stmt(Stmt::Expr(five));
+
             assert_eq!(
-                Block::new("{ 5 }"),
-                Ok((
-                    "",
-                    Block {
-                        stmts: vec![Stmt::Expr(Expr::Number(Number(5)))],
-                    },
-                )),
+                crate::parse_stmt("{ 5 }"),
+                Ok(block_stmt(arena, vec![stmt], TextRange::new(0, 5))),
             );
         }
 
         #[test]
         fn parse_block_with_multiple_stmts() {
+            let mut arena = Arena::default();
+            let ten = arena.alloc_expr(Expr::new(
+                ExprKind::Number(Number(10)),
+                TextRange::new(14, 16),
+            ));
+            let a = arena.alloc_stmt(Stmt::BindingDef(BindingDef {
+                name: "a".into(),
+                ty: None,
+                val: ten,
+                span: TextRange::new(6, 16),
+            }));
+            let usage = usage_at(&mut arena, "a", 29, 30);
+            let b = arena.alloc_stmt(Stmt::BindingDef(BindingDef {
+                name: "b".into(),
+                ty: None,
+                val: usage,
+                span: TextRange::new(21, 30),
+            }));
+            let usage = usage_at(&mut arena, "b", 35, 36);
+            let last = arena.alloc_stmt(Stmt::Expr(usage));
+
             assert_eq!(
-                Block::new(
+                crate::parse_stmt(
                     "{
     let a = 10
     let b = a
     b
 }",
                 ),
-                Ok((
-                    "",
-                    Block {
-                        stmts: vec![
-                            Stmt::BindingDef(BindingDef {
-                                name: "a".to_string(),
-                                val: Expr::Number(Number(10)),
-                            }),
-                            Stmt::BindingDef(BindingDef {
-                                name: "b".to_string(),
-                                val: Expr::BindingUsage(BindingUsage {
-                                    name: "a".to_string(),
-                                }),
-                            }),
-                            Stmt::Expr(Expr::BindingUsage(BindingUsage {
-                                name: "b".to_string(),
-                            })),
-                        ],
-                    },
-                )),
+                Ok(block_stmt(arena, vec![a, b, last], TextRange::new(0, 38))),
+            );
+        }
+
+        #[test]
+        fn parse_block_with_semicolons() {
+            let mut arena = Arena::default();
+            let ten = arena.alloc_expr(Expr::new(
+                ExprKind::Number(Number(10)),
+                TextRange::new(10, 12),
+            ));
+            let a = arena.alloc_stmt(Stmt::BindingDef(BindingDef {
+                name: "a".into(),
+                ty: None,
+                val: ten,
+                span: TextRange::new(2, 12),
+            }));
+            let usage = usage_at(&mut arena, "a", 14, 15);
+            let semi = arena.alloc_stmt(Stmt::Semi(usage));
+
+            assert_eq!(
+                crate::parse_stmt("{ let a = 10; a; }"),
+                Ok(block_stmt(arena, vec![a, semi], TextRange::new(0, 18))),
+            );
+        }
+
+        #[test]
+        fn cannot_parse_block_with_missing_separators() {
+            assert_eq!(
+                crate::parse_stmt("{ let a = 1 let b = 2 a }"),
+                Err(vec![
+                    "error at 12..15: expected ';' or line break after statement, but found 'let'"
+                        .to_string(),
+                    "error at 22..23: expected ';' or line break after statement, but found identifier 'a'"
+                        .to_string(),
+                ]),
             );
         }
     }
@@ -118,109 +210,114 @@ mod tests {
 
         use crate::{env::Env, expr::Op, val::Val};
 
+        fn eval(arena: Arena, stmts: Vec<StmtId>, env: &Env) -> Result<Val, Unwind> {
+            Block { stmts }.eval(&Rc::new(arena), env)
+        }
+
         #[test]
         fn eval_empty_block() {
             assert_eq!(
-                Block { stmts: Vec::new() }.eval(&Env::default()),
+                eval(Arena::default(), Vec::new(), &Env::default()),
                 Ok(Val::Unit),
             );
         }
 
         #[test]
         fn eval_block_with_one_expr() {
+            let mut arena = Arena::default();
+            let expr = number(&mut arena, 25);
+            let stmt = arena.alloc_stmt(Stmt::Expr(expr));
+
             assert_eq!(
-                Block {
-                    stmts: vec![Stmt::Expr(Expr::Number(Number(25)))],
-                }
-                .eval(&Env::default()),
+                eval(arena, vec![stmt], &Env::default()),
                 Ok(Val::Number(25)),
             );
         }
 
         #[test]
         fn eval_block_with_binding_def_and_usage() {
+            let mut arena = Arena::default();
+            let one = number(&mut arena, 1);
+            let def = binding_def(&mut arena, "one", one);
+            let usage = binding_usage(&mut arena, "one", 0, 0);
+            let usage = arena.alloc_stmt(Stmt::Expr(usage));
+
             assert_eq!(
-                Block {
-                    stmts: vec![
-                        Stmt::BindingDef(BindingDef {
-                            name: "one".to_string(),
-                            val: Expr::Number(Number(1)),
-                        }),
-                        Stmt::Expr(Expr::BindingUsage(BindingUsage {
-                            name: "one".to_string(),
-                        })),
-                    ],
-                }
-                .eval(&Env::default()),
+                eval(arena, vec![def, usage], &Env::default()),
                 Ok(Val::Number(1)),
             );
         }
 
         #[test]
         fn eval_block_with_multiple_binding_defs() {
-            assert_eq!(
-                Block {
-                    stmts: vec![
-                        Stmt::BindingDef(BindingDef {
-                            name: "foo".to_string(),
-                            val: Expr::Number(Number(5)),
-                        }),
-                        Stmt::BindingDef(BindingDef {
-                            name: "bar".to_string(),
-                            val: Expr::Number(Number(4)),
-                        }),
-                        Stmt::BindingDef(BindingDef {
-                            name: "baz".to_string(),
-                            val: Expr::Number(Number(3)),
-                        }),
-                    ],
-                }
-                .eval(&Env::default()),
-                Ok(Val::Unit),
-            );
+            let mut arena = Arena::default();
+            let stmts = [("foo", 5), ("bar", 4), ("baz", 3)]
+                .into_iter()
+                .map(|(name, n)| {
+                    let val = number(&mut arena, n);
+                    binding_def(&mut arena, name, val)
+                })
+                .collect();
+
+            assert_eq!(eval(arena, stmts, &Env::default()), Ok(Val::Unit));
         }
 
         #[test]
         fn eval_block_with_multiple_exprs() {
-            assert_eq!(
-                Block {
-                    stmts: vec![
-                        Stmt::Expr(Expr::Number(Number(100))),
-                        Stmt::Expr(Expr::Number(Number(30))),
-                        Stmt::Expr(Expr::Operation {
-                            lhs: Number(10),
-                            rhs: Number(7),
-                            op: Op::Sub,
-                        }),
-                    ],
-                }
-                .eval(&Env::default()),
-                Ok(Val::Number(3)),
-            );
+            let mut arena = Arena::default();
+            let hundred = number(&mut arena, 100);
+            let thirty = number(&mut arena, 30);
+            let lhs = number(&mut arena, 10);
+            let rhs = number(&mut arena, 7);
+            let sub = arena.alloc_expr(ExprKind::Operation {
+                lhs,
+                rhs,
+                op: Op::Sub,
+            });
+            let stmts = [hundred, thirty, sub]
+                .into_iter()
+                .map(|expr| arena.alloc_stmt(Stmt::Expr(expr)))
+                .collect();
+
+            assert_eq!(eval(arena, stmts, &Env::default()), Ok(Val::Number(3)),);
+        }
+
+        #[test]
+        fn eval_block_with_trailing_semi() {
+            let mut arena = Arena::default();
+            let one = number(&mut arena, 1);
+            let one = arena.alloc_stmt(Stmt::Expr(one));
+            let two = number(&mut arena, 2);
+            let two = arena.alloc_stmt(Stmt::Semi(two));
+
+            assert_eq!(eval(arena, vec![one, two], &Env::default()), Ok(Val::Unit),);
         }
 
         #[test]
         fn eval_block_using_bindings_from_parent_env() {
             let mut env = Env::default();
-            env.store_binding("foo", Val::Number(2));
+            env.store_binding("foo".into(), Val::Number(2));
 
-            assert_eq!(
-                Block {
-                    stmts: vec![
-                        Stmt::BindingDef(BindingDef {
-                            name: "baz".to_string(),
-                            val: Expr::BindingUsage(BindingUsage {
-                                name: "foo".to_string(),
-                            }),
-                        }),
-                        Stmt::Expr(Expr::BindingUsage(BindingUsage {
-                            name: "baz".to_string(),
-                        })),
-                    ],
-                }
-                .eval(&env),
-                Ok(Val::Number(2)),
-            );
+            let mut arena = Arena::default();
+            let foo = binding_usage(&mut arena, "foo", 1, 0);
+            let def = binding_def(&mut arena, "baz", foo);
+            let baz = binding_usage(&mut arena, "baz", 0, 0);
+            let baz = arena.alloc_stmt(Stmt::Expr(baz));
+
+            assert_eq!(eval(arena, vec![def, baz], &env), Ok(Val::Number(2)));
+        }
+
+        #[test]
+        fn bindings_do_not_leak_out_of_block() {
+            let env = Env::default();
+
+            let mut arena = Arena::default();
+            let one = number(&mut arena, 1);
+            let def = binding_def(&mut arena, "inner", one);
+
+            eval(arena, vec![def], &env).unwrap();
+
+            assert!(env.get_binding_value("inner".into()).is_err());
         }
     }
 }
@@ -0,0 +1,204 @@
+use std::rc::Rc;
+
+use super::{Expr, Tail};
+use crate::{
+    ast::{Arena, ExprId},
+    env::Env,
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    unwind::Unwind,
+    val::Val,
+};
+
+/// `callee(args)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub callee: ExprId,
+    pub args: Vec<ExprId>,
+}
+
+impl Call {
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        let mut children = node.children();
+
+        let callee = Expr::lower(arena, &children.next()?)?;
+
+        let arg_list = children.next()?;
+        if arg_list.kind() != SyntaxKind::ArgList {
+            return None;
+        }
+
+        let args = arg_list
+            .children()
+            .map(|arg| Expr::lower(arena, &arg))
+            .collect::<Option<_>>()?;
+
+        Some(Self { callee, args })
+    }
+
+    pub(crate) fn eval(
+        &self,
+        arena: &Rc<Arena>,
+        env: &Env,
+        span: TextRange,
+    ) -> Result<Val, Unwind> {
+        self.eval_tail(arena, env, span)?.finish()
+    }
+
+    /// Evaluates the callee and the arguments, leaving the call itself to be made.
+    pub(crate) fn eval_tail(
+        &self,
+        arena: &Rc<Arena>,
+        env: &Env,
+        span: TextRange,
+    ) -> Result<Tail, Unwind> {
+        let function = match arena[self.callee].eval(arena, env)? {
+            Val::Fn(function) => function,
+            val => return Err(format!("cannot call a value of type {}", val.type_name()).into()),
+        };
+
+        let args = self
+            .args
+            .iter()
+            .map(|&arg| arena[arg].eval(arena, env))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Tail::Call {
+            function,
+            args,
+            call_site: span,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        expr::{BindingUsage, ExprKind, Number},
+        stmt::Stmt,
+        syntax::TextRange,
+        typeck::TypeEnv,
+        Backend,
+    };
+
+    fn eval(s: &str) -> Result<Val, String> {
+        crate::parse(s).eval(&mut Env::default())
+    }
+
+    #[test]
+    fn parse_call() {
+        let mut arena = Arena::default();
+        let callee = arena.alloc_expr(Expr::new(
+            ExprKind::BindingUsage(BindingUsage {
+                name: "f".into(),
+                slot: None,
+            }),
+            TextRange::new(0, 1),
+        ));
+        let args = vec![
+            arena.alloc_expr(Expr::new(ExprKind::Number(Number(1)), TextRange::new(2, 3))),
+            arena.alloc_expr(Expr::new(ExprKind::Number(Number(2)), TextRange::new(5, 6))),
+        ];
+        let call = arena.alloc_expr(Expr::new(
+            ExprKind::Call(Call { callee, args }),
+            TextRange::new(0, 7),
+        ));
+        let stmt = arena.alloc_stmt(Stmt::Expr(call));
+
+        assert_eq!(crate::parse_stmt("f(1, 2)"), Ok((arena, stmt)));
+    }
+
+    #[test]
+    fn cannot_parse_call_with_missing_paren() {
+        assert_eq!(
+            crate::parse_stmt("f(1, 2"),
+            Err(vec![
+                "error at 6..6: expected ')', but reached end of input".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn eval_call() {
+        assert_eq!(
+            eval("fn add(a, b) { a + b }\nadd(1, 2)"),
+            Ok(Val::Number(3))
+        );
+    }
+
+    #[test]
+    fn eval_recursive_call() {
+        assert_eq!(
+            eval("fn fact(n) { if n == 0 { 1 } else { n * fact(n - 1) } }\nfact(5)"),
+            Ok(Val::Number(120))
+        );
+    }
+
+    #[test]
+    fn tail_calls_run_in_constant_stack_space() {
+        assert_eq!(
+            eval("fn count(n, acc) { if n == 0 { acc } else { count(n - 1, acc + 1) } }\ncount(1000000, 0)"),
+            Ok(Val::Number(1000000))
+        );
+    }
+
+    /// Evaluates `s` with both backends, after checking it like the CLI does.
+    fn check_and_eval(s: &str) -> Result<Val, String> {
+        let mut parse = crate::parse(s);
+        let mut env = Env::default();
+
+        assert_eq!(parse.resolve(&env), Ok(()));
+        assert!(parse.typeck(&mut TypeEnv::default()).is_ok());
+
+        let tree_walker = parse.eval(&mut env);
+        assert_eq!(
+            parse
+                .eval_with(&mut Env::default(), Backend::Vm)
+                .map_err(|error| error.message),
+            tree_walker
+        );
+
+        tree_walker
+    }
+
+    #[test]
+    fn mutually_recursive_tail_calls() {
+        assert_eq!(
+            check_and_eval("fn even(n) -> Bool { if n == 0 { true } else { odd(n - 1) } }\nfn odd(n) -> Bool { if n == 0 { false } else { even(n - 1) } }\neven(100001)"),
+            Ok(Val::Bool(false))
+        );
+    }
+
+    #[test]
+    fn tail_calls_from_alternating_call_sites() {
+        assert_eq!(
+            check_and_eval("fn f(n, flip) -> Int { if n == 0 { 0 } else { if flip { f(n - 1, false) } else { f(n - 1, true) } } }\nf(100000, true)"),
+            Ok(Val::Number(0))
+        );
+    }
+
+    #[test]
+    fn closures_capture_their_environment() {
+        assert_eq!(
+            eval("fn adder(a) { fn add(b) { a + b }; add }\nadder(1)(2)"),
+            Ok(Val::Number(3))
+        );
+    }
+
+    #[test]
+    fn cannot_call_number() {
+        assert_eq!(
+            eval("1(2)"),
+            Err("cannot call a value of type Int".to_string())
+        );
+    }
+
+    #[test]
+    fn cannot_call_with_wrong_number_of_args() {
+        assert_eq!(
+            eval("fn f(a) { a }\nf(1, 2)"),
+            Err("function 'f' takes 1 argument(s), but 2 were given".to_string())
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::rc::Rc;
+
+use super::{Block, Expr, Tail};
+use crate::{
+    ast::{Arena, ExprId},
+    env::Env,
+    syntax::{SyntaxKind, SyntaxNode},
+    unwind::Unwind,
+    val::Val,
+};
+
+/// `if condition { then_branch } else else_branch`, where `else_branch` is either a block or
+/// another `if`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct If {
+    pub condition: ExprId,
+    pub then_branch: Block,
+    pub else_branch: Option<ExprId>,
+}
+
+impl If {
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        let mut children = node.children();
+
+        let condition = Expr::lower(arena, &children.next()?)?;
+
+        let then_branch = children.next()?;
+        if then_branch.kind() != SyntaxKind::Block {
+            return None;
+        }
+        let then_branch = Block::lower(arena, &then_branch)?;
+
+        let else_branch = match children.next() {
+            Some(else_branch) => Some(Expr::lower(arena, &else_branch)?),
+            None => None,
+        };
+
+        Some(Self {
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    /// Without an `else` branch the value of the `then` branch is discarded, so that both
+    /// possible outcomes have the same type.
+    pub(crate) fn eval(&self, arena: &Rc<Arena>, env: &Env) -> Result<Val, Unwind> {
+        self.eval_tail(arena, env)?.finish()
+    }
+
+    /// Evaluates the `if` in tail position, where the branch taken is too, unless its value is
+    /// discarded.
+    pub(crate) fn eval_tail(&self, arena: &Rc<Arena>, env: &Env) -> Result<Tail, Unwind> {
+        let condition = match arena[self.condition].eval(arena, env)? {
+            Val::Bool(condition) => condition,
+            val => {
+                return Err(format!(
+                    "expected Bool as condition of if, but found {}",
+                    val.type_name()
+                )
+                .into())
+            }
+        };
+
+        match self.else_branch {
+            Some(else_branch) if !condition => arena[else_branch].eval_tail(arena, env),
+            Some(_) => self.then_branch.eval_tail(arena, env),
+            None => {
+                if condition {
+                    self.then_branch.eval(arena, env)?;
+                }
+
+                Ok(Tail::Val(Val::Unit))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        expr::{ExprKind, Number},
+        stmt::Stmt,
+        syntax::TextRange,
+    };
+
+    fn eval(s: &str) -> Result<Val, String> {
+        crate::parse(s).eval(&mut Env::default())
+    }
+
+    #[test]
+    fn parse_if_else() {
+        let mut arena = Arena::default();
+        let condition = arena.alloc_expr(Expr::new(ExprKind::Bool(true), TextRange::new(3, 7)));
+        let one = arena.alloc_expr(Expr::new(
+            ExprKind::Number(Number(1)),
+            TextRange::new(10, 11),
+        ));
+        let one = arena.alloc_stmt(Stmt::Expr(one));
+        let two = arena.alloc_expr(Expr::new(
+            ExprKind::Number(Number(2)),
+            TextRange::new(21, 22),
+        ));
+        let two = arena.alloc_stmt(Stmt::Expr(two));
+        let else_branch = arena.alloc_expr(Expr::new(
+            ExprKind::Block(Block { stmts: vec![two] }),
+            TextRange::new(19, 24),
+        ));
+        let if_expr = arena.alloc_expr(Expr::new(
+            ExprKind::If(If {
+                condition,
+                then_branch: Block { stmts: vec![one] },
+                else_branch: Some(else_branch),
+            }),
+            TextRange::new(0, 24),
+        ));
+        let stmt = arena.alloc_stmt(Stmt::Expr(if_expr));
+
+        assert_eq!(
+            crate::parse_stmt("if true { 1 } else { 2 }"),
+            Ok((arena, stmt))
+        );
+    }
+
+    #[test]
+    fn cannot_parse_if_without_block() {
+        assert_eq!(
+            crate::parse_stmt("if true 1"),
+            Err(vec![
+                "error at 8..9: expected block, but found number '1'".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn eval_if_else() {
+        assert_eq!(eval("if 1 < 2 { 10 } else { 20 }"), Ok(Val::Number(10)));
+        assert_eq!(eval("if 1 > 2 { 10 } else { 20 }"), Ok(Val::Number(20)));
+    }
+
+    #[test]
+    fn eval_else_if_chain() {
+        assert_eq!(
+            eval("let a = 3\nif a == 1 { 1 } else if a == 3 { 3 } else { 0 }"),
+            Ok(Val::Number(3))
+        );
+    }
+
+    #[test]
+    fn eval_if_without_else() {
+        assert_eq!(eval("if true { 1; }"), Ok(Val::Unit));
+    }
+
+    #[test]
+    fn condition_has_to_be_bool() {
+        assert_eq!(
+            eval("if 1 { 2 } else { 3 }"),
+            Err("expected Bool as condition of if, but found Int".to_string())
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::rc::Rc;
+
+use super::{Expr, Tail};
+use crate::{
+    ast::{Arena, ExprId},
+    env::Env,
+    symbol::Symbol,
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    unwind::Unwind,
+    val::Val,
+};
+
+/// `receiver.name(args)`, which calls the method `name` of the impl for the receiver's type with
+/// the receiver as its first argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodCall {
+    pub receiver: ExprId,
+    pub name: Symbol,
+    pub args: Vec<ExprId>,
+}
+
+impl MethodCall {
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        let receiver = Expr::lower(arena, &node.children().next()?)?;
+
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        let args = node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::ArgList)?
+            .children()
+            .map(|arg| Expr::lower(arena, &arg))
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            receiver,
+            name: Symbol::intern(name.text()),
+            args,
+        })
+    }
+
+    pub(crate) fn eval(
+        &self,
+        arena: &Rc<Arena>,
+        env: &Env,
+        span: TextRange,
+    ) -> Result<Val, Unwind> {
+        self.eval_tail(arena, env, span)?.finish()
+    }
+
+    /// Evaluates the receiver and the arguments and looks the method up, leaving the call itself
+    /// to be made.
+    pub(crate) fn eval_tail(
+        &self,
+        arena: &Rc<Arena>,
+        env: &Env,
+        span: TextRange,
+    ) -> Result<Tail, Unwind> {
+        let receiver = arena[self.receiver].eval(arena, env)?;
+
+        let method = env
+            .get_method(receiver.type_name(), self.name.clone())
+            .ok_or_else(|| {
+                format!(
+                    "no method named '{}' found for a value of type {}",
+                    self.name,
+                    receiver.type_name()
+                )
+            })?;
+
+        let mut args = Vec::with_capacity(self.args.len() + 1);
+        args.push(receiver);
+
+        for &arg in &self.args {
+            args.push(arena[arg].eval(arena, env)?);
+        }
+
+        Ok(Tail::Call {
+            function: method,
+            args,
+            call_site: span,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        expr::{BindingUsage, ExprKind, Number},
+        stmt::Stmt,
+        syntax::TextRange,
+    };
+
+    fn eval(s: &str) -> Result<Val, String> {
+        crate::parse(s).eval(&mut Env::default())
+    }
+
+    #[test]
+    fn parse_method_call() {
+        let mut arena = Arena::default();
+        let receiver = arena.alloc_expr(Expr::new(
+            ExprKind::BindingUsage(BindingUsage {
+                name: "p".into(),
+                slot: None,
+            }),
+            TextRange::new(0, 1),
+        ));
+        let arg = arena.alloc_expr(Expr::new(ExprKind::Number(Number(1)), TextRange::new(6, 7)));
+        let method_call = arena.alloc_expr(Expr::new(
+            ExprKind::MethodCall(MethodCall {
+                receiver,
+                name: "add".into(),
+                args: vec![arg],
+            }),
+            TextRange::new(0, 8),
+        ));
+        let stmt = arena.alloc_stmt(Stmt::Expr(method_call));
+
+        assert_eq!(crate::parse_stmt("p.add(1)"), Ok((arena, stmt)));
+    }
+
+    #[test]
+    fn dispatch_on_struct() {
+        assert_eq!(
+            eval(
+                "trait Area { fn area(self) -> Int }
+struct Rect { w: Int, h: Int }
+impl Area for Rect { fn area(self) -> Int { self.w * self.h } }
+Rect { w: 2, h: 3 }.area()"
+            ),
+            Ok(Val::Number(6))
+        );
+    }
+
+    #[test]
+    fn dispatch_on_builtin_types() {
+        assert_eq!(
+            eval(
+                "trait Describe { fn describe(self, prefix: Str) -> Str }
+impl Describe for Int { fn describe(self, prefix: Str) -> Str { prefix } }
+impl<T> Describe for List<T> { fn describe(self, prefix: Str) -> Str { \"list\" } }
+[1.describe(\"int\"), [true].describe(\"\")]"
+            ),
+            Ok(Val::List(
+                vec![Val::Str("int".to_string()), Val::Str("list".to_string())].into()
+            ))
+        );
+    }
+
+    #[test]
+    fn methods_can_call_each_other() {
+        assert_eq!(
+            eval(
+                "trait Count { fn down(self) -> Int }
+impl Count for Int { fn down(self) -> Int { if self == 0 { 0 } else { (self - 1).down() } } }
+10.down()"
+            ),
+            Ok(Val::Number(0))
+        );
+    }
+
+    #[test]
+    fn no_such_method() {
+        assert_eq!(
+            eval("true.show()"),
+            Err("no method named 'show' found for a value of type Bool".to_string())
+        );
+    }
+}
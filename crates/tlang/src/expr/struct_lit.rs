@@ -0,0 +1,227 @@
+use std::rc::Rc;
+
+use super::Expr;
+use crate::{
+    ast::{Arena, ExprId},
+    env::{Env, TypeBinding},
+    limits,
+    struct_def::StructDef,
+    symbol::Symbol,
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    unwind::Unwind,
+    val::{StructVal, Val},
+};
+
+/// `Name { field: val, ... }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructLit {
+    pub name: Symbol,
+    pub fields: Vec<FieldInit>,
+}
+
+/// `name: val` in a [`StructLit`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldInit {
+    pub name: Symbol,
+    pub val: ExprId,
+    pub span: TextRange,
+}
+
+impl StructLit {
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        let fields = node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::FieldInitList)?;
+
+        let fields = fields
+            .children()
+            .map(|field| FieldInit::lower(arena, &field))
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            fields,
+        })
+    }
+
+    /// Builds the instance with its fields in the order of the struct's definition, checking them
+    /// against the definition on the way.
+    pub(crate) fn eval(&self, arena: &Rc<Arena>, env: &Env) -> Result<Val, Unwind> {
+        let def = self.def(env)?;
+
+        let mut vals = Vec::with_capacity(self.fields.len());
+
+        for (idx, field) in self.fields.iter().enumerate() {
+            self.check_field(&def, idx)?;
+            vals.push(arena[field.val].eval(arena, env)?);
+        }
+
+        self.build(&def, vals, env)
+    }
+
+    pub(crate) fn def(&self, env: &Env) -> Result<Rc<StructDef>, String> {
+        match env.get_type(self.name.clone()) {
+            Some(TypeBinding::Struct(def)) => Ok(def),
+            _ => Err(format!("struct '{}' does not exist", self.name)),
+        }
+    }
+
+    /// Checks the field at `idx` before its value is evaluated.
+    pub(crate) fn check_field(&self, def: &StructDef, idx: usize) -> Result<(), String> {
+        let name = &self.fields[idx].name;
+
+        if !def.fields.iter().any(|def| def.name == *name) {
+            return Err(format!("struct '{}' has no field '{}'", self.name, name));
+        }
+
+        if self.fields[..idx].iter().any(|field| field.name == *name) {
+            return Err(format!("field '{}' is specified more than once", name));
+        }
+
+        Ok(())
+    }
+
+    /// Builds the instance from the values of the fields, in the order they are specified in.
+    pub(crate) fn build(&self, def: &StructDef, vals: Vec<Val>, env: &Env) -> Result<Val, Unwind> {
+        let mut vals: Vec<_> = self
+            .fields
+            .iter()
+            .map(|field| field.name.clone())
+            .zip(vals)
+            .collect();
+
+        // The field types can refer to the struct's type parameters.
+        let mut type_env = env.create_child();
+        for type_param in &def.type_params {
+            type_env.store_type_param(type_param.name.clone());
+        }
+
+        let mut fields = Vec::with_capacity(def.fields.len());
+
+        for field_def in &def.fields {
+            let idx = vals
+                .iter()
+                .position(|(name, _)| *name == field_def.name)
+                .ok_or_else(|| {
+                    format!(
+                        "missing field '{}' in struct '{}'",
+                        field_def.name, self.name
+                    )
+                })?;
+
+            let (name, val) = vals.swap_remove(idx);
+
+            field_def
+                .ty
+                .check(&val, &type_env)
+                .map_err(|msg| format!("in field '{}' of struct '{}': {}", name, self.name, msg))?;
+
+            fields.push((name, val));
+        }
+
+        limits::allocated(Val::Struct(Rc::new(StructVal {
+            name: self.name.clone(),
+            fields,
+        })))
+    }
+}
+
+impl FieldInit {
+    fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            val: Expr::lower(arena, &node.children().next()?)?,
+            span: node.text_range(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        expr::{ExprKind, Number},
+        stmt::Stmt,
+    };
+
+    fn eval(s: &str) -> Result<Val, String> {
+        crate::parse(s).eval(&mut Env::default())
+    }
+
+    #[test]
+    fn parse_struct_lit() {
+        let mut arena = Arena::default();
+        let val = arena.alloc_expr(Expr::new(ExprKind::Number(Number(1)), TextRange::new(7, 8)));
+        let struct_lit = arena.alloc_expr(Expr::new(
+            ExprKind::StructLit(StructLit {
+                name: "P".into(),
+                fields: vec![FieldInit {
+                    name: "x".into(),
+                    val,
+                    span: TextRange::new(4, 8),
+                }],
+            }),
+            TextRange::new(0, 10),
+        ));
+        let stmt = arena.alloc_stmt(Stmt::Expr(struct_lit));
+
+        assert_eq!(crate::parse_stmt("P { x: 1 }"), Ok((arena, stmt)));
+    }
+
+    #[test]
+    fn eval_struct_lit_in_definition_order() {
+        assert_eq!(
+            eval("struct P { x: Int, y: Bool }\nP { y: true, x: 1 }").map(|val| val.to_string()),
+            Ok("P { x: 1, y: true }".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_field_access() {
+        assert_eq!(
+            eval("struct P<T> { x: T }\nP { x: P { x: 5 } }.x.x"),
+            Ok(Val::Number(5))
+        );
+    }
+
+    #[test]
+    fn missing_field() {
+        assert_eq!(
+            eval("struct P { x: Int, y: Int }\nP { x: 1 }"),
+            Err("missing field 'y' in struct 'P'".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert_eq!(
+            eval("struct P { x: Int }\nP { x: 1, z: 2 }"),
+            Err("struct 'P' has no field 'z'".to_string())
+        );
+    }
+
+    #[test]
+    fn field_types_are_checked() {
+        assert_eq!(
+            eval("struct P { x: Int }\nP { x: true }"),
+            Err("in field 'x' of struct 'P': expected Int, but found Bool".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_struct() {
+        assert_eq!(
+            eval("P { x: 1 }"),
+            Err("struct 'P' does not exist".to_string())
+        );
+    }
+}
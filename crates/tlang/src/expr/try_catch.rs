@@ -0,0 +1,260 @@
+use std::rc::Rc;
+
+use super::Block;
+use crate::{
+    ast::Arena,
+    env::Env,
+    symbol::Symbol,
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    unwind::Unwind,
+    val::Val,
+};
+
+/// `try { body } catch name { handler } finally { finalizer }`, where either the `catch` or the
+/// `finally` clause can be left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryCatch {
+    pub body: Block,
+    pub catch: Option<Catch>,
+    pub finally: Option<Block>,
+}
+
+/// `catch name { body }`, which binds the caught value to `name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catch {
+    pub name: Symbol,
+    pub body: Block,
+    pub span: TextRange,
+}
+
+impl TryCatch {
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        let body = node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::Block)?;
+        let body = Block::lower(arena, &body)?;
+
+        let catch = match node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::CatchClause)
+        {
+            Some(catch) => Some(Catch::lower(arena, &catch)?),
+            None => None,
+        };
+
+        let finally = match node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::FinallyClause)
+        {
+            Some(finally) => Some(Block::lower(arena, &finally.children().next()?)?),
+            None => None,
+        };
+
+        Some(Self {
+            body,
+            catch,
+            finally,
+        })
+    }
+
+    /// Only thrown values are caught: runtime errors and `?` returning from the enclosing
+    /// function pass through, although the `finally` block is run for them too. If the `finally`
+    /// block itself stops early, that takes the place of whatever happened before it.
+    pub(crate) fn eval(&self, arena: &Rc<Arena>, env: &Env) -> Result<Val, Unwind> {
+        let result = match (self.body.eval(arena, env), &self.catch) {
+            (Err(Unwind::Throw { val, .. }), Some(catch)) => {
+                let mut catch_env = env.create_child();
+                catch_env.store_binding(catch.name.clone(), val);
+
+                catch.body.eval(arena, &catch_env)
+            }
+            (result, _) => result,
+        };
+
+        if let Some(finally) = &self.finally {
+            finally.eval(arena, env)?;
+        }
+
+        result
+    }
+}
+
+impl Catch {
+    fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        let body = node.children().next()?;
+
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            body: Block::lower(arena, &body)?,
+            span: node.text_range(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        ast::StmtId,
+        expr::{Expr, ExprKind, Number},
+        stmt::Stmt,
+        Frame,
+    };
+
+    fn eval(s: &str) -> Result<Val, String> {
+        crate::parse(s).eval(&mut Env::default())
+    }
+
+    fn number(arena: &mut Arena, n: i32, start: usize, end: usize) -> StmtId {
+        let expr = arena.alloc_expr(Expr::new(
+            ExprKind::Number(Number(n)),
+            TextRange::new(start, end),
+        ));
+
+        arena.alloc_stmt(Stmt::Expr(expr))
+    }
+
+    #[test]
+    fn parse_try_catch_finally() {
+        let mut arena = Arena::default();
+        let body = number(&mut arena, 1, 6, 7);
+        let catch = number(&mut arena, 2, 20, 21);
+        let finally = number(&mut arena, 3, 34, 35);
+        let try_catch = arena.alloc_expr(Expr::new(
+            ExprKind::TryCatch(TryCatch {
+                body: Block { stmts: vec![body] },
+                catch: Some(Catch {
+                    name: "e".into(),
+                    body: Block { stmts: vec![catch] },
+                    span: TextRange::new(10, 23),
+                }),
+                finally: Some(Block {
+                    stmts: vec![finally],
+                }),
+            }),
+            TextRange::new(0, 37),
+        ));
+        let stmt = arena.alloc_stmt(Stmt::Expr(try_catch));
+
+        assert_eq!(
+            crate::parse_stmt("try { 1 } catch e { 2 } finally { 3 }"),
+            Ok((arena, stmt))
+        );
+    }
+
+    #[test]
+    fn cannot_parse_try_without_catch_or_finally() {
+        assert_eq!(
+            crate::parse_stmt("try { 1 }"),
+            Err(vec![
+                "error at 9..9: expected 'catch' or 'finally', but reached end of input"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn catch_thrown_value() {
+        assert_eq!(
+            eval("try { throw \"oops\" } catch e { e }"),
+            Ok(Val::Str("oops".to_string()))
+        );
+        assert_eq!(eval("try { 1 } catch e { 2 }"), Ok(Val::Number(1)));
+    }
+
+    #[test]
+    fn exceptions_unwind_through_blocks_and_calls() {
+        assert_eq!(
+            eval(
+                "fn check(n) { if n < 0 { throw \"negative\" } else { n } }
+fn sum(a, b) { { let x = check(a); x + check(b) } }
+let caught = try { { let inner = 1; sum(1, -2) } } catch e { e }
+caught"
+            ),
+            Ok(Val::Str("negative".to_string()))
+        );
+    }
+
+    #[test]
+    fn bindings_of_try_do_not_leak() {
+        let mut env = Env::default();
+
+        crate::parse("try { let a = 1; throw a } catch e { let b = e }")
+            .eval(&mut env)
+            .unwrap();
+
+        assert!(env.get_binding_value("a".into()).is_err());
+        assert!(env.get_binding_value("b".into()).is_err());
+        assert!(env.get_binding_value("e".into()).is_err());
+    }
+
+    #[test]
+    fn finally_runs_without_exception() {
+        assert_eq!(eval("try { 1 } finally { 2 }"), Ok(Val::Number(1)));
+        assert_eq!(
+            eval("fn f() { try { 1 } finally { throw \"finally\" } }\ntry { f() } catch e { e }"),
+            Ok(Val::Str("finally".to_string()))
+        );
+    }
+
+    #[test]
+    fn finally_runs_after_catch_and_rethrows() {
+        assert_eq!(
+            eval("fn f() { try { throw 1 } catch e { throw e + 1 } finally { throw 10 } }\ntry { f() } catch e { e }"),
+            Ok(Val::Number(10))
+        );
+        assert_eq!(
+            eval("fn f() { try { throw 1 } catch e { throw e + 1 } finally { 10 } }\ntry { f() } catch e { e }"),
+            Ok(Val::Number(2))
+        );
+        assert_eq!(
+            eval(
+                "fn f() { try { None? } finally { throw \"finally\" } }\ntry { f() } catch e { e }"
+            ),
+            Ok(Val::Str("finally".to_string()))
+        );
+    }
+
+    #[test]
+    fn runtime_errors_are_not_caught() {
+        assert_eq!(
+            eval("try { 1 / 0 } catch e { 0 }"),
+            Err("division by zero".to_string())
+        );
+    }
+
+    #[test]
+    fn uncaught_exception_has_trace() {
+        let parse = crate::parse(
+            "fn inner() { throw \"deep\" }
+fn outer() { 1 + inner() }
+outer()",
+        );
+
+        let error = parse.eval_traced(&mut Env::default()).unwrap_err();
+
+        assert_eq!(error.message, "uncaught exception: \"deep\"");
+        assert_eq!(
+            error.trace,
+            [
+                Frame {
+                    function: Some("inner".to_string()),
+                    span: TextRange::new(13, 25),
+                },
+                Frame {
+                    function: Some("outer".to_string()),
+                    span: TextRange::new(45, 52),
+                },
+                Frame {
+                    function: None,
+                    span: TextRange::new(55, 62),
+                },
+            ]
+        );
+    }
+}
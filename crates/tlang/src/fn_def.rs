@@ -0,0 +1,216 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::Arena,
+    env::Env,
+    expr::Block,
+    symbol::Symbol,
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    type_expr::{TypeExpr, TypeParam},
+    val::{Function, Val},
+};
+
+/// `fn name<type_params>(params) -> ret_ty { body }`, where the type parameters and the return
+/// type annotation are optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FnDef {
+    pub name: Symbol,
+    pub type_params: Vec<TypeParam>,
+    pub params: Vec<Param>,
+    pub ret_ty: Option<TypeExpr>,
+    pub body: Block,
+    pub span: TextRange,
+}
+
+/// A parameter of a [`FnDef`], optionally annotated with its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: Symbol,
+    pub ty: Option<TypeExpr>,
+    pub span: TextRange,
+}
+
+impl FnDef {
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        let params = Param::lower_list(node)?;
+        let ret_ty = TypeExpr::lower_optional_ret_type(node)?;
+
+        let body = node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::Block)?;
+
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            type_params: TypeParam::lower_list(node),
+            params,
+            ret_ty,
+            body: Block::lower(arena, &body)?,
+            span: node.text_range(),
+        })
+    }
+
+    /// Binds the function in `env`, which it also captures, so that the body can call it. The
+    /// body is in `arena`, which the function keeps around.
+    pub(crate) fn eval(&self, arena: &Rc<Arena>, env: &mut Env) {
+        let function = Function::new(self.clone(), Rc::clone(arena), env.clone());
+
+        env.store_binding(self.name.clone(), Val::Fn(function));
+    }
+}
+
+impl Param {
+    /// Lowers the parameters in the `ParamList` child of `node`.
+    pub(crate) fn lower_list(node: &SyntaxNode) -> Option<Vec<Self>> {
+        node.children()
+            .find(|child| child.kind() == SyntaxKind::ParamList)?
+            .children()
+            .map(|param| Self::lower(&param))
+            .collect()
+    }
+
+    fn lower(node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            ty: TypeExpr::lower_annotation(node)?,
+            span: node.text_range(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        expr::{BindingUsage, Expr, ExprKind},
+        stmt::Stmt,
+    };
+
+    fn parse_fn_def(s: &str) -> FnDef {
+        match crate::parse_stmt(s) {
+            Ok((arena, stmt)) => match &arena[stmt] {
+                Stmt::FnDef(fn_def) => fn_def.clone(),
+                stmt => panic!("expected function definition, found {:?}", stmt),
+            },
+            Err(errors) => panic!("expected function definition, found {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn parse_fn_def_with_body() {
+        let mut arena = Arena::default();
+        let x = arena.alloc_expr(Expr::new(
+            ExprKind::BindingUsage(BindingUsage {
+                name: "x".into(),
+                slot: None,
+            }),
+            TextRange::new(12, 13),
+        ));
+        let body = arena.alloc_stmt(Stmt::Expr(x));
+        let stmt = arena.alloc_stmt(Stmt::FnDef(FnDef {
+            name: "id".into(),
+            type_params: Vec::new(),
+            params: vec![Param {
+                name: "x".into(),
+                ty: None,
+                span: TextRange::new(6, 7),
+            }],
+            ret_ty: None,
+            body: Block { stmts: vec![body] },
+            span: TextRange::new(0, 15),
+        }));
+
+        assert_eq!(crate::parse_stmt("fn id(x,) { x }"), Ok((arena, stmt)));
+    }
+
+    #[test]
+    fn parse_fn_def_with_annotations() {
+        let fn_def = parse_fn_def("fn f(a: Int, b) -> Bool { true }");
+
+        let types: Vec<_> = fn_def
+            .params
+            .iter()
+            .map(|param| param.ty.as_ref().map(ToString::to_string))
+            .collect();
+
+        assert_eq!(types, [Some("Int".to_string()), None]);
+        assert_eq!(fn_def.ret_ty.unwrap().to_string(), "Bool");
+    }
+
+    #[test]
+    fn parse_generic_fn_def() {
+        let fn_def = parse_fn_def("fn f<T, U>(t: T) -> U { g(t) }");
+
+        assert_eq!(
+            fn_def.type_params,
+            [
+                TypeParam {
+                    name: "T".into(),
+                    span: TextRange::new(5, 6),
+                },
+                TypeParam {
+                    name: "U".into(),
+                    span: TextRange::new(8, 9),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cannot_parse_fn_def_with_missing_return_type() {
+        assert_eq!(
+            crate::parse_stmt("fn f() -> { 1 }"),
+            Err(vec![
+                "error at 10..11: expected type, but found '{'".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn cannot_parse_fn_def_without_params() {
+        assert_eq!(
+            crate::parse_stmt("fn f { 1 }"),
+            Err(vec![
+                "error at 5..6: expected '(', but found '{'".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn eval_fn_def() {
+        let mut env = Env::default();
+
+        crate::parse("fn f() { 1 }").eval(&mut env).unwrap();
+
+        assert!(matches!(
+            env.get_binding_value("f".into()),
+            Ok(Val::Fn(function)) if function.name() == "f" && function.arity() == 0
+        ));
+    }
+
+    #[test]
+    fn annotations_are_checked_when_called() {
+        let mut env = Env::default();
+
+        crate::parse("fn f(a: Int) -> Bool { a }")
+            .eval(&mut env)
+            .unwrap();
+
+        assert_eq!(
+            crate::parse("f(true)").eval(&mut env),
+            Err("in argument 'a' of function 'f': expected Int, but found Bool".to_string())
+        );
+        assert_eq!(
+            crate::parse("f(1)").eval(&mut env),
+            Err("in return value of function 'f': expected Bool, but found Int".to_string())
+        );
+    }
+}
@@ -0,0 +1,416 @@
+//! Collection of the values reference counting can't free on its own.
+//!
+//! Values are reference counted, which frees most of them as soon as they are no longer used. A
+//! function keeps the environment it was defined in around, though, and that environment usually
+//! holds the function itself, so the two keep each other alive forever once nothing else uses
+//! them. The same goes for lists, structs and the like that end up in an environment they contain
+//! a function of.
+//!
+//! Every such cycle goes through an environment a function has captured, so those are tracked as
+//! they are captured, and a collection finds which of them, and of everything they refer to, is
+//! only referred to from within the tracked values. Those environments are cleared, which breaks
+//! their cycles and lets reference counting free the rest. Collections run automatically once
+//! enough environments have been captured since the last one, with the `gc()` built-in function,
+//! or with [`collect`].
+//!
+//! ```
+//! use tlang::{gc, Env};
+//!
+//! let mut env = Env::default();
+//! tlang::parse("fn f(n) { fn g() { n }\n g() }\nf(1)\nf(2)")
+//!     .eval(&mut env)
+//!     .unwrap();
+//!
+//! // Each call of `f` left behind an environment binding `g`, which `g` has captured.
+//! assert_eq!(gc::collect(), 2);
+//! assert_eq!(gc::collect(), 0);
+//! ```
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    mem,
+    rc::Rc,
+};
+
+use crate::{
+    env::{Env, WeakEnv},
+    val::{Function, StructVal, Val},
+};
+
+/// What the collections on this thread have done so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub collections: usize,
+    /// How many environments the collections have freed in total.
+    pub freed: usize,
+    /// How many environments the next collection is going to start from, which are the ones
+    /// captured since the last collection, and the ones still in use at the time.
+    pub tracked: usize,
+}
+
+/// The environments that may be part of cycles, and what has been collected so far. Values can't
+/// be shared between threads, so each thread collects its own.
+struct Heap {
+    tracked: RefCell<Vec<WeakEnv>>,
+    /// How many environments can be tracked before the next collection runs.
+    threshold: Cell<usize>,
+    collections: Cell<usize>,
+    freed: Cell<usize>,
+}
+
+thread_local! {
+    static HEAP: Heap = const {
+        Heap {
+            tracked: RefCell::new(Vec::new()),
+            threshold: Cell::new(MIN_THRESHOLD),
+            collections: Cell::new(0),
+            freed: Cell::new(0),
+        }
+    };
+}
+
+/// How many environments are tracked before collecting at least, so that collections don't
+/// happen too often while few environments are in use.
+const MIN_THRESHOLD: usize = 1024;
+
+/// Tracks `env` as having been captured by a function, collecting if enough environments have
+/// been tracked since the last collection.
+pub(crate) fn track(env: &Env) {
+    let full = HEAP.with(|heap| {
+        let mut tracked = heap.tracked.borrow_mut();
+
+        // The methods of an impl all capture the same environment.
+        let last = tracked.last().and_then(WeakEnv::upgrade);
+        if last.is_some_and(|last| last.addr() == env.addr()) {
+            return false;
+        }
+
+        tracked.push(env.downgrade());
+        tracked.len() > heap.threshold.get()
+    });
+
+    if full {
+        collect();
+    }
+}
+
+/// Frees every value only kept alive by cycles, returning how many of the environments captured
+/// by functions that freed.
+pub fn collect() -> usize {
+    let tracked = HEAP.with(|heap| heap.tracked.take());
+
+    let mut graph = Graph::default();
+    let mut tracked: Vec<_> = tracked
+        .iter()
+        .filter_map(WeakEnv::upgrade)
+        .map(|env| graph.add(Obj::Scope(env)))
+        .collect();
+    tracked.sort_unstable();
+    tracked.dedup();
+    graph.trace();
+    graph.mark();
+
+    // Every cycle goes through a tracked environment, so clearing those is enough to break them.
+    let (survivors, garbage): (Vec<_>, Vec<_>) = tracked
+        .into_iter()
+        .map(|idx| &graph.nodes[idx])
+        .partition(|node| node.live);
+    let freed = garbage.len();
+
+    for node in garbage {
+        if let Obj::Scope(env) = &node.obj {
+            env.clear();
+        }
+    }
+
+    let survivors: Vec<_> = survivors
+        .into_iter()
+        .filter_map(|node| match &node.obj {
+            Obj::Scope(env) => Some(env.downgrade()),
+            _ => None,
+        })
+        .collect();
+
+    // The graph holds on to what was cleared, which only gets freed once the graph is gone.
+    drop(graph);
+
+    HEAP.with(|heap| {
+        let mut tracked = heap.tracked.borrow_mut();
+        tracked.splice(0..0, survivors);
+        heap.threshold.set(MIN_THRESHOLD.max(2 * tracked.len()));
+
+        heap.collections.set(heap.collections.get() + 1);
+        heap.freed.set(heap.freed.get() + freed);
+    });
+
+    freed
+}
+
+pub fn stats() -> Stats {
+    HEAP.with(|heap| Stats {
+        collections: heap.collections.get(),
+        freed: heap.freed.get(),
+        tracked: heap.tracked.borrow().len(),
+    })
+}
+
+/// A reference-counted value something can refer to.
+#[derive(Clone)]
+pub(crate) enum Obj {
+    Scope(Env),
+    Fn(Function),
+    List(Rc<[Val]>),
+    Struct(Rc<StructVal>),
+    /// The contents of an `Option` or `Result`.
+    Boxed(Rc<Val>),
+}
+
+impl Obj {
+    /// What `val` refers to, if anything.
+    pub(crate) fn of(val: &Val) -> Option<Self> {
+        match val {
+            Val::Fn(function) => Some(Self::Fn(function.clone())),
+            Val::List(elements) => Some(Self::List(Rc::clone(elements))),
+            Val::Struct(instance) => Some(Self::Struct(Rc::clone(instance))),
+            Val::Option(Some(val)) | Val::Result(Ok(val) | Err(val)) => {
+                Some(Self::Boxed(Rc::clone(val)))
+            }
+            Val::Number(_) | Val::Bool(_) | Val::Str(_) | Val::Option(None) | Val::Unit => None,
+        }
+    }
+
+    fn addr(&self) -> usize {
+        match self {
+            Self::Scope(env) => env.addr(),
+            Self::Fn(function) => function.addr(),
+            Self::List(elements) => Rc::as_ptr(elements) as *const Val as usize,
+            Self::Struct(instance) => Rc::as_ptr(instance) as usize,
+            Self::Boxed(val) => Rc::as_ptr(val) as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Self::Scope(env) => env.strong_count(),
+            Self::Fn(function) => function.strong_count(),
+            Self::List(elements) => Rc::strong_count(elements),
+            Self::Struct(instance) => Rc::strong_count(instance),
+            Self::Boxed(val) => Rc::strong_count(val),
+        }
+    }
+
+    /// Adds what this refers to to `refs`, or returns `false` if that can't be known right now.
+    fn trace(&self, refs: &mut Vec<Obj>) -> bool {
+        match self {
+            Self::Scope(env) => return env.trace(refs),
+            Self::Fn(function) => refs.extend(function.env().cloned().map(Self::Scope)),
+            Self::List(elements) => refs.extend(elements.iter().filter_map(Self::of)),
+            Self::Struct(instance) => {
+                refs.extend(instance.fields.iter().filter_map(|(_, val)| Self::of(val)))
+            }
+            Self::Boxed(val) => refs.extend(Self::of(val)),
+        }
+
+        true
+    }
+}
+
+/// Everything reachable from the tracked environments, with the references between them.
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    by_addr: HashMap<usize, usize>,
+}
+
+struct Node {
+    /// The graph's own reference to the value, which is the only one besides those of the
+    /// program, so that the reference count says how often the program refers to it.
+    obj: Obj,
+    /// How many references to the value come from other values in the graph.
+    internal: usize,
+    /// The values this refers to, or `None` if that isn't known.
+    refs: Option<Vec<usize>>,
+    live: bool,
+}
+
+impl Graph {
+    /// The index of the node for `obj`, which is added if it isn't in the graph yet.
+    fn add(&mut self, obj: Obj) -> usize {
+        let addr = obj.addr();
+
+        *self.by_addr.entry(addr).or_insert_with(|| {
+            self.nodes.push(Node {
+                obj,
+                internal: 0,
+                refs: None,
+                live: false,
+            });
+            self.nodes.len() - 1
+        })
+    }
+
+    /// Adds everything reachable from the nodes, and the references between them.
+    fn trace(&mut self) {
+        let mut refs = Vec::new();
+        let mut idx = 0;
+
+        while idx < self.nodes.len() {
+            if self.nodes[idx].obj.trace(&mut refs) {
+                let refs = refs.drain(..).map(|obj| self.add(obj)).collect::<Vec<_>>();
+
+                for &referenced in &refs {
+                    self.nodes[referenced].internal += 1;
+                }
+                self.nodes[idx].refs = Some(refs);
+            }
+
+            idx += 1;
+        }
+    }
+
+    /// Marks what the program refers to from outside of the graph as live, along with
+    /// everything reachable from it. What isn't live is only kept alive by cycles.
+    fn mark(&mut self) {
+        let mut stack: Vec<_> = (0..self.nodes.len())
+            .filter(|&idx| {
+                let node = &self.nodes[idx];
+                node.refs.is_none() || node.obj.strong_count() - 1 > node.internal
+            })
+            .collect();
+
+        while let Some(idx) = stack.pop() {
+            let node = &mut self.nodes[idx];
+            if mem::replace(&mut node.live, true) {
+                continue;
+            }
+
+            stack.extend(node.refs.iter().flatten().copied());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Backend;
+
+    fn eval(s: &str, env: &mut Env, backend: Backend) -> Val {
+        crate::parse(s).eval_with(env, backend).unwrap()
+    }
+
+    #[test]
+    fn collect_recursive_closures() {
+        for backend in [Backend::TreeWalker, Backend::Vm] {
+            let mut env = Env::default();
+            eval(
+                "fn make(n) { fn count(i) { if i == 0 { n } else { count(i - 1) } }\n count }",
+                &mut env,
+                backend,
+            );
+            collect();
+
+            eval("let f = make(1)\nmake(2)(3)", &mut env, backend);
+            assert_eq!(collect(), 1);
+
+            // `f` is still bound, and so is the environment it was defined in.
+            assert_eq!(eval("f(5)", &mut env, backend), Val::Number(1));
+            assert_eq!(collect(), 0);
+
+            // Shadowing `f` keeps it in its slot, but a binding in a block is gone after it.
+            eval("let f = 0\n{ let g = make(3); g(1) }", &mut env, backend);
+            assert_eq!(collect(), 1);
+        }
+    }
+
+    #[test]
+    fn collect_cycles_through_values() {
+        for backend in [Backend::TreeWalker, Backend::Vm] {
+            let mut env = Env::default();
+            eval(
+                "struct Holder { items: List }\n\
+                 fn make() {\n\
+                     let held = { fn get() { 1 }\n Holder { items: [Some(Ok(get))] } }\n\
+                     fn other() { held }\n\
+                     held\n\
+                 }",
+                &mut env,
+                backend,
+            );
+            collect();
+
+            // Each call leaves behind the environment of `get`, and the one of `other` it is in.
+            eval("make()\nmake()\nlet kept = make()", &mut env, backend);
+            assert_eq!(collect(), 4);
+            assert!(matches!(eval("kept", &mut env, backend), Val::Struct(_)));
+            assert_eq!(collect(), 0);
+        }
+    }
+
+    #[test]
+    fn gc_builtin() {
+        for backend in [Backend::TreeWalker, Backend::Vm] {
+            let mut env = Env::default();
+            collect();
+
+            // The value of the statement before last is still around while the last one runs.
+            assert_eq!(
+                eval(
+                    "fn f() { fn g() { g }\n g }\nlet g = f()\nf()\nf()\nlet n = 0\n[gc(), gc()]",
+                    &mut env,
+                    backend
+                ),
+                Val::List([Val::Number(2), Val::Number(0)].into())
+            );
+        }
+    }
+
+    #[test]
+    fn stress_collections() {
+        for backend in [Backend::TreeWalker, Backend::Vm] {
+            let mut env = Env::default();
+            collect();
+            let before = stats();
+
+            // Every call leaves behind a cycle of a few closures, a list and a struct.
+            eval(
+                "struct Node { next: Option }\n\
+                 fn cycle(n) {\n\
+                     fn a() { [b, Node { next: Some(b) }] }\n\
+                     fn b() { a }\n\
+                     n\n\
+                 }\n\
+                 fn run(i) { if i == 0 { 0 } else { cycle(i)\n run(i - 1) } }\n\
+                 run(20000)",
+                &mut env,
+                backend,
+            );
+
+            let after = stats();
+            assert!(after.collections > before.collections);
+            assert!(after.tracked <= 2 * MIN_THRESHOLD);
+
+            collect();
+            assert_eq!(stats().freed - before.freed, 20000);
+        }
+    }
+
+    #[test]
+    fn environments_in_use_are_not_collected() {
+        let mut env = Env::default();
+        eval(
+            "fn outer() { fn inner() { outer }\n let result = gc()\n [inner, result] }",
+            &mut env,
+            Backend::TreeWalker,
+        );
+        collect();
+
+        // The environment of the call of `outer` is being used while it collects.
+        assert_eq!(
+            eval("outer()[1]", &mut env, Backend::TreeWalker),
+            Val::Number(0)
+        );
+        assert_eq!(collect(), 1);
+    }
+}
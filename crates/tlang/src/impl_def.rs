@@ -0,0 +1,245 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::Arena,
+    env::{Env, TypeBinding},
+    fn_def::FnDef,
+    symbol::Symbol,
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    type_expr::{TypeExpr, TypeExprKind, TypeParam},
+    val::Function,
+};
+
+/// `impl<type_params> Trait for Type { methods }`, where the type parameters are optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImplDef {
+    pub type_params: Vec<TypeParam>,
+    pub trait_name: Symbol,
+    pub target: TypeExpr,
+    pub methods: Vec<FnDef>,
+    pub span: TextRange,
+}
+
+impl ImplDef {
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<Self> {
+        let trait_name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        let target = node.children().find(|child| child.kind().is_type())?;
+
+        let methods = node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::ImplItemList)?
+            .children()
+            .map(|method| FnDef::lower(arena, &method))
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            type_params: TypeParam::lower_list(node),
+            trait_name: Symbol::intern(trait_name.text()),
+            target: TypeExpr::lower(&target)?,
+            methods,
+            span: node.text_range(),
+        })
+    }
+
+    /// Checks that the impl defines exactly the methods of its trait, and makes them callable on
+    /// every value whose type has the name of the target type. As methods are looked up by that
+    /// name alone, no other impl for a type of the same name can have defined a method of the
+    /// same name already.
+    pub(crate) fn eval(&self, arena: &Rc<Arena>, env: &mut Env) -> Result<(), String> {
+        self.define(env, |idx, methods_env| {
+            Function::new(self.methods[idx].clone(), Rc::clone(arena), methods_env)
+        })
+    }
+
+    /// Like [`eval`](Self::eval), but with `function` making the function for the method at
+    /// the given index, capturing the environment shared by the methods.
+    pub(crate) fn define(
+        &self,
+        env: &mut Env,
+        mut function: impl FnMut(usize, Env) -> Function,
+    ) -> Result<(), String> {
+        let trait_def = env
+            .get_trait(self.trait_name.clone())
+            .ok_or_else(|| format!("trait '{}' does not exist", self.trait_name))?;
+
+        let target = self.target_name(env)?;
+
+        for (idx, method) in self.methods.iter().enumerate() {
+            let sig = trait_def.method(&method.name).ok_or_else(|| {
+                format!(
+                    "method '{}' is not a member of trait '{}'",
+                    method.name, self.trait_name
+                )
+            })?;
+
+            if sig.params.len() != method.params.len() {
+                return Err(format!(
+                    "method '{}' takes {} parameter(s) in trait '{}', but {} in the impl",
+                    method.name,
+                    sig.params.len(),
+                    self.trait_name,
+                    method.params.len()
+                ));
+            }
+
+            if self.methods[..idx]
+                .iter()
+                .any(|other| other.name == method.name)
+            {
+                return Err(format!(
+                    "method '{}' is defined more than once",
+                    method.name
+                ));
+            }
+        }
+
+        for sig in &trait_def.methods {
+            if !self.methods.iter().any(|method| method.name == sig.name) {
+                return Err(format!(
+                    "missing method '{}' in impl of trait '{}' for {}",
+                    sig.name, self.trait_name, self.target
+                ));
+            }
+        }
+
+        for method in &self.methods {
+            match env.get_method_trait(target.clone(), method.name.clone()) {
+                Some(other) if other == self.trait_name => {
+                    return Err(format!(
+                        "trait '{}' is already implemented for {}",
+                        self.trait_name, target
+                    ));
+                }
+                Some(other) => {
+                    return Err(format!(
+                        "method '{}' of trait '{}' is ambiguous, as {} already has one of trait '{}'",
+                        method.name, self.trait_name, target, other
+                    ));
+                }
+                None => {}
+            }
+        }
+
+        // The methods can refer to the type parameters of the impl in their annotations.
+        let mut methods_env = env.create_child();
+        for type_param in &self.type_params {
+            methods_env.store_type_param(type_param.name.clone());
+        }
+
+        for (idx, method) in self.methods.iter().enumerate() {
+            env.store_method(
+                target.clone(),
+                self.trait_name.clone(),
+                method.name.clone(),
+                function(idx, methods_env.clone()),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The name that values of the target type have at runtime, which is what methods are looked
+    /// up by.
+    fn target_name(&self, env: &Env) -> Result<Symbol, String> {
+        let TypeExprKind::Named { name, .. } = &self.target.kind else {
+            return Err(format!("cannot implement a trait for {}", self.target));
+        };
+
+        let is_type_param = self.type_params.iter().any(|param| param.name == *name);
+
+        match (name.as_str(), env.get_type(name.clone())) {
+            _ if is_type_param => {}
+            ("Int" | "Bool" | "Str" | "Unit" | "List" | "Option" | "Result", _)
+            | (_, Some(TypeBinding::Struct(_))) => return Ok(name.clone()),
+            (_, Some(TypeBinding::Param)) => {}
+            (_, None) => return Err(format!("unknown type '{}'", name)),
+        }
+
+        Err(format!(
+            "cannot implement a trait for the type parameter '{}'",
+            name
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::val::Val;
+
+    fn eval(s: &str) -> Result<Val, String> {
+        crate::parse(s).eval(&mut Env::default())
+    }
+
+    #[test]
+    fn lower_impl_def() {
+        let (arena, stmt) =
+            crate::parse_stmt("impl<T> Show for List<T> { fn show(self) { \"\" } }").unwrap();
+        let impl_def = match &arena[stmt] {
+            crate::stmt::Stmt::ImplDef(impl_def) => impl_def,
+            stmt => panic!("expected an impl, found {:?}", stmt),
+        };
+
+        assert_eq!(impl_def.type_params[0].name, "T");
+        assert_eq!(impl_def.trait_name, "Show");
+        assert_eq!(impl_def.target.to_string(), "List<T>");
+        assert_eq!(impl_def.methods[0].name, "show");
+        assert_eq!(impl_def.span, TextRange::new(0, 49));
+    }
+
+    #[test]
+    fn impls_must_define_every_method() {
+        assert_eq!(
+            eval("trait T { fn a(self); fn b(self) }\nimpl T for Int { fn a(self) {} }"),
+            Err("missing method 'b' in impl of trait 'T' for Int".to_string())
+        );
+    }
+
+    #[test]
+    fn impls_cannot_define_other_methods() {
+        assert_eq!(
+            eval("trait T { fn a(self) }\nimpl T for Int { fn a(self) {}; fn b(self) {} }"),
+            Err("method 'b' is not a member of trait 'T'".to_string())
+        );
+    }
+
+    #[test]
+    fn methods_must_match_the_number_of_params() {
+        assert_eq!(
+            eval("trait T { fn a(self) }\nimpl T for Int { fn a(self, x) {} }"),
+            Err("method 'a' takes 1 parameter(s) in trait 'T', but 2 in the impl".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_trait_or_type() {
+        assert_eq!(
+            eval("impl T for Int {}"),
+            Err("trait 'T' does not exist".to_string())
+        );
+        assert_eq!(
+            eval("trait T {}\nimpl T for P {}"),
+            Err("unknown type 'P'".to_string())
+        );
+        assert_eq!(
+            eval("trait T {}\nimpl<X> T for X {}"),
+            Err("cannot implement a trait for the type parameter 'X'".to_string())
+        );
+    }
+
+    #[test]
+    fn impls_cannot_overlap() {
+        assert_eq!(
+            eval("trait Show { fn show(self) -> Str }\nimpl Show for List<Int> { fn show(self) { \"ints\" } }\nimpl Show for List<Str> { fn show(self) { \"strs\" } }"),
+            Err("trait 'Show' is already implemented for List".to_string())
+        );
+        assert_eq!(
+            eval("struct P {}\ntrait A { fn f(self) }\ntrait B { fn f(self) }\nimpl A for P { fn f(self) {} }\nimpl B for P { fn f(self) {} }"),
+            Err("method 'f' of trait 'B' is ambiguous, as P already has one of trait 'A'".to_string())
+        );
+    }
+}
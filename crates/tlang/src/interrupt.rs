@@ -0,0 +1,144 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Stops the evaluation of an input in an [`Env`](crate::Env) from the outside, such as from
+/// another thread or a Ctrl-C handler.
+///
+/// Evaluation checks the handle every so often, and stops with an error of the kind
+/// [`ErrorKind::Interrupted`](crate::ErrorKind::Interrupted) once it has been interrupted. The
+/// environment is left as it was at that point, with the bindings defined before the interruption
+/// still in place. An interrupt stays in effect until it has stopped an evaluation, so one that
+/// arrives just before an evaluation starts stops that evaluation; [`reset`](Self::reset)
+/// withdraws it instead.
+///
+/// ```
+/// use std::{thread, time::Duration};
+///
+/// use tlang::{Env, ErrorKind};
+///
+/// let mut env = Env::default();
+/// let interrupt = env.interrupt_handle();
+///
+/// thread::spawn(move || {
+///     thread::sleep(Duration::from_millis(10));
+///     interrupt.interrupt();
+/// });
+///
+/// let error = tlang::parse("fn forever() { forever() }\nforever()")
+///     .eval_traced(&mut env)
+///     .unwrap_err();
+/// assert_eq!(error.kind, ErrorKind::Interrupted);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    #[inline]
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Withdraws an interrupt that hasn't stopped an evaluation yet.
+    #[inline]
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    /// Whether evaluation has been interrupted, which the interrupt is used up by.
+    #[inline]
+    pub(crate) fn take(&self) -> bool {
+        self.0.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Handles are equal if they interrupt the same evaluations.
+impl PartialEq for InterruptHandle {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use crate::{Backend, Env, ErrorKind, Val};
+
+    #[test]
+    fn interrupt_from_another_thread() {
+        for backend in [Backend::TreeWalker, Backend::Vm] {
+            let mut env = Env::default();
+            let interrupt = env.interrupt_handle();
+
+            let interrupter = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                interrupt.interrupt();
+            });
+
+            let error = crate::parse("let a = 1\nfn forever(n) { forever(n + 1) }\nforever(0)")
+                .eval_with(&mut env, backend)
+                .unwrap_err();
+            interrupter.join().unwrap();
+
+            assert_eq!(error.kind, ErrorKind::Interrupted);
+            assert_eq!(error.message, "evaluation was interrupted");
+
+            // The environment keeps working, including the bindings defined before.
+            assert_eq!(
+                crate::parse("a + 1").eval_with(&mut env, backend),
+                Ok(Val::Number(2))
+            );
+        }
+    }
+
+    #[test]
+    fn interrupt_before_evaluation_is_kept() {
+        let mut env = Env::default();
+        env.interrupt_handle().interrupt();
+
+        let s = "fn f(n) { if n == 0 { 0 } else { f(n - 1) } }\nf(5000)";
+        assert_eq!(
+            crate::parse(s).eval_traced(&mut env).unwrap_err().kind,
+            ErrorKind::Interrupted
+        );
+
+        // Stopping the evaluation used up the interrupt.
+        assert_eq!(crate::parse(s).eval(&mut env), Ok(Val::Number(0)));
+    }
+
+    #[test]
+    fn reset_withdraws_interrupt() {
+        let mut env = Env::default();
+        let interrupt = env.interrupt_handle();
+        interrupt.interrupt();
+        interrupt.reset();
+
+        assert_eq!(
+            crate::parse("fn f(n) { if n == 0 { 0 } else { f(n - 1) } }\nf(5000)").eval(&mut env),
+            Ok(Val::Number(0))
+        );
+    }
+
+    #[test]
+    fn interrupts_cannot_be_caught() {
+        let mut env = Env::default();
+        let interrupt = env.interrupt_handle();
+
+        let interrupter = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            interrupt.interrupt();
+        });
+
+        let error = crate::parse(
+            "fn forever() { forever() }\ntry { forever() } catch e { 0 } finally { 1 }",
+        )
+        .eval_traced(&mut env)
+        .unwrap_err();
+        interrupter.join().unwrap();
+
+        assert_eq!(error.kind, ErrorKind::Interrupted);
+    }
+}
@@ -0,0 +1,513 @@
+use crate::syntax::{SyntaxKind, TextRange};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub(crate) kind: SyntaxKind,
+    pub(crate) text: &'a str,
+    pub(crate) range: TextRange,
+}
+
+pub(crate) struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Lexer<'a> {
+    #[inline]
+    pub(crate) fn new(input: &'a str) -> Self {
+        Self { input, offset: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.offset..]
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest();
+        let first = rest.chars().next()?;
+
+        let (kind, len) = match first {
+            c if is_whitespace(c) => (SyntaxKind::Whitespace, take_while(rest, is_whitespace)),
+            '#' => (SyntaxKind::Comment, take_while(rest, |c| c != '\n')),
+            c if c.is_ascii_digit() => {
+                (SyntaxKind::Number, take_while(rest, |c| c.is_ascii_digit()))
+            }
+            c if is_ident_start(c) => {
+                let len = take_while(rest, is_ident_continue);
+
+                let kind = match &rest[..len] {
+                    "let" => SyntaxKind::LetKw,
+                    "fn" => SyntaxKind::FnKw,
+                    "struct" => SyntaxKind::StructKw,
+                    "trait" => SyntaxKind::TraitKw,
+                    "impl" => SyntaxKind::ImplKw,
+                    "for" => SyntaxKind::ForKw,
+                    "if" => SyntaxKind::IfKw,
+                    "else" => SyntaxKind::ElseKw,
+                    "try" => SyntaxKind::TryKw,
+                    "catch" => SyntaxKind::CatchKw,
+                    "finally" => SyntaxKind::FinallyKw,
+                    "throw" => SyntaxKind::ThrowKw,
+                    "true" => SyntaxKind::TrueKw,
+                    "false" => SyntaxKind::FalseKw,
+                    _ => SyntaxKind::Ident,
+                };
+
+                (kind, len)
+            }
+            '"' => (SyntaxKind::String, string_len(rest)),
+            '+' => (SyntaxKind::Plus, 1),
+            '-' if rest.starts_with("->") => (SyntaxKind::Arrow, 2),
+            '-' => (SyntaxKind::Minus, 1),
+            '*' => (SyntaxKind::Star, 1),
+            '/' => (SyntaxKind::Slash, 1),
+            '=' if rest.starts_with("==") => (SyntaxKind::EqualsEquals, 2),
+            '=' => (SyntaxKind::Equals, 1),
+            '!' if rest.starts_with("!=") => (SyntaxKind::BangEquals, 2),
+            '!' => (SyntaxKind::Bang, 1),
+            '<' if rest.starts_with("<=") => (SyntaxKind::LessEquals, 2),
+            '<' => (SyntaxKind::Less, 1),
+            '>' if rest.starts_with(">=") => (SyntaxKind::GreaterEquals, 2),
+            '>' => (SyntaxKind::Greater, 1),
+            ',' => (SyntaxKind::Comma, 1),
+            '.' => (SyntaxKind::Dot, 1),
+            '?' => (SyntaxKind::Question, 1),
+            ':' => (SyntaxKind::Colon, 1),
+            ';' => (SyntaxKind::Semicolon, 1),
+            '(' => (SyntaxKind::LParen, 1),
+            ')' => (SyntaxKind::RParen, 1),
+            '{' => (SyntaxKind::LBrace, 1),
+            '}' => (SyntaxKind::RBrace, 1),
+            '[' => (SyntaxKind::LBracket, 1),
+            ']' => (SyntaxKind::RBracket, 1),
+            c => (SyntaxKind::Error, c.len_utf8()),
+        };
+
+        let range = TextRange::at(self.offset, len);
+        self.offset += len;
+
+        Some(Token {
+            kind,
+            text: &rest[..len],
+            range,
+        })
+    }
+}
+
+const WHITESPACE: &[char] = &[' ', '\n', '\t', '\r'];
+
+#[inline]
+fn is_whitespace(c: char) -> bool {
+    WHITESPACE.contains(&c)
+}
+
+/// Turns the source text of a string literal (quotes included) into the string it denotes, or
+/// returns `None` if the literal is unterminated or contains an unknown escape sequence.
+pub(crate) fn unescape_string(text: &str) -> Option<String> {
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut unescaped = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        let escaped = match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            '"' => '"',
+            '\\' => '\\',
+            _ => return None,
+        };
+
+        unescaped.push(escaped);
+    }
+
+    Some(unescaped)
+}
+
+#[inline]
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+#[inline]
+fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Length of the string literal at the start of `s`, including both quotes. An unterminated
+/// literal extends to the end of the input, and is reported by the parser.
+fn string_len(s: &str) -> usize {
+    let mut escaped = false;
+
+    for (idx, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return idx + 1,
+            _ => {}
+        }
+    }
+
+    s.len()
+}
+
+fn take_while(s: &str, accept: impl Fn(char) -> bool) -> usize {
+    s.char_indices()
+        .find_map(|(idx, c)| if accept(c) { None } else { Some(idx) })
+        .unwrap_or(s.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(input: &str, kind: SyntaxKind) {
+        let mut lexer = Lexer::new(input);
+
+        assert_eq!(
+            lexer.next(),
+            Some(Token {
+                kind,
+                text: input,
+                range: TextRange::at(0, input.len()),
+            })
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
+    fn kinds(input: &str) -> Vec<(SyntaxKind, &str)> {
+        Lexer::new(input)
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    mod digit {
+        use super::*;
+
+        #[test]
+        fn lex_one_digit() {
+            check("1", SyntaxKind::Number);
+        }
+
+        #[test]
+        fn lex_multiple_digits() {
+            check("100", SyntaxKind::Number);
+        }
+
+        #[test]
+        fn lex_digits_followed_by_op() {
+            assert_eq!(
+                kinds("10-20"),
+                [
+                    (SyntaxKind::Number, "10"),
+                    (SyntaxKind::Minus, "-"),
+                    (SyntaxKind::Number, "20"),
+                ]
+            );
+        }
+    }
+
+    mod op {
+        use super::*;
+
+        #[test]
+        fn lex_plus() {
+            check("+", SyntaxKind::Plus);
+        }
+
+        #[test]
+        fn lex_minus() {
+            check("-", SyntaxKind::Minus);
+        }
+
+        #[test]
+        fn lex_star() {
+            check("*", SyntaxKind::Star);
+        }
+
+        #[test]
+        fn lex_slash() {
+            check("/", SyntaxKind::Slash);
+        }
+    }
+
+    mod whitespace {
+        use super::*;
+
+        #[test]
+        fn lex_spaces() {
+            check("    ", SyntaxKind::Whitespace);
+        }
+
+        #[test]
+        fn lex_newlines_and_spaces() {
+            check(" \n   \n\n", SyntaxKind::Whitespace);
+        }
+    }
+
+    mod comment {
+        use super::*;
+
+        #[test]
+        fn lex_comment() {
+            check("# hello", SyntaxKind::Comment);
+        }
+
+        #[test]
+        fn comment_stops_at_newline() {
+            assert_eq!(
+                kinds("# hello\n1"),
+                [
+                    (SyntaxKind::Comment, "# hello"),
+                    (SyntaxKind::Whitespace, "\n"),
+                    (SyntaxKind::Number, "1"),
+                ]
+            );
+        }
+    }
+
+    mod ident {
+        use super::*;
+
+        #[test]
+        fn lex_alphabetic_ident() {
+            check("abcdEFG", SyntaxKind::Ident);
+        }
+
+        #[test]
+        fn lex_alphanumeric_ident() {
+            check("foobar1", SyntaxKind::Ident);
+        }
+
+        #[test]
+        fn ident_cannot_begin_with_number() {
+            assert_eq!(
+                kinds("123abc"),
+                [(SyntaxKind::Number, "123"), (SyntaxKind::Ident, "abc")]
+            );
+        }
+
+        #[test]
+        fn lex_let_keyword() {
+            check("let", SyntaxKind::LetKw);
+        }
+
+        #[test]
+        fn ident_starting_with_let_is_not_keyword() {
+            check("letaaa", SyntaxKind::Ident);
+        }
+    }
+
+    mod keyword {
+        use super::*;
+
+        #[test]
+        fn lex_fn_keyword() {
+            check("fn", SyntaxKind::FnKw);
+        }
+
+        #[test]
+        fn lex_if_keyword() {
+            check("if", SyntaxKind::IfKw);
+        }
+
+        #[test]
+        fn lex_else_keyword() {
+            check("else", SyntaxKind::ElseKw);
+        }
+
+        #[test]
+        fn lex_true_keyword() {
+            check("true", SyntaxKind::TrueKw);
+        }
+
+        #[test]
+        fn lex_false_keyword() {
+            check("false", SyntaxKind::FalseKw);
+        }
+    }
+
+    mod string {
+        use super::*;
+
+        #[test]
+        fn lex_string() {
+            check(r#""hello, world""#, SyntaxKind::String);
+        }
+
+        #[test]
+        fn lex_string_with_escaped_quote() {
+            check(r#""say \"hi\"""#, SyntaxKind::String);
+        }
+
+        #[test]
+        fn lex_unterminated_string() {
+            check(r#""oops"#, SyntaxKind::String);
+        }
+
+        #[test]
+        fn unescape() {
+            assert_eq!(
+                unescape_string(r#""a\"b\\c\nd""#),
+                Some("a\"b\\c\nd".to_string())
+            );
+        }
+
+        #[test]
+        fn cannot_unescape_unknown_escape() {
+            assert_eq!(unescape_string(r#""\q""#), None);
+        }
+
+        #[test]
+        fn cannot_unescape_unterminated() {
+            assert_eq!(unescape_string(r#""abc"#), None);
+            assert_eq!(unescape_string(r#"""#), None);
+        }
+
+        #[test]
+        fn string_ends_at_closing_quote() {
+            assert_eq!(
+                kinds(r#""a" + "b""#),
+                [
+                    (SyntaxKind::String, r#""a""#),
+                    (SyntaxKind::Whitespace, " "),
+                    (SyntaxKind::Plus, "+"),
+                    (SyntaxKind::Whitespace, " "),
+                    (SyntaxKind::String, r#""b""#),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn lex_ident_with_underscores() {
+        check("_is_even2", SyntaxKind::Ident);
+    }
+
+    #[test]
+    fn lex_comparison_ops() {
+        assert_eq!(
+            kinds("== != < <= > >= ! ="),
+            [
+                (SyntaxKind::EqualsEquals, "=="),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::BangEquals, "!="),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::Less, "<"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::LessEquals, "<="),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::Greater, ">"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::GreaterEquals, ">="),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::Bang, "!"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::Equals, "="),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_type_annotation_punctuation() {
+        assert_eq!(
+            kinds("a: fn() -> - >"),
+            [
+                (SyntaxKind::Ident, "a"),
+                (SyntaxKind::Colon, ":"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::FnKw, "fn"),
+                (SyntaxKind::LParen, "("),
+                (SyntaxKind::RParen, ")"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::Arrow, "->"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::Minus, "-"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::Greater, ">"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_struct_and_list_punctuation() {
+        assert_eq!(
+            kinds("struct p.x[0]"),
+            [
+                (SyntaxKind::StructKw, "struct"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::Ident, "p"),
+                (SyntaxKind::Dot, "."),
+                (SyntaxKind::Ident, "x"),
+                (SyntaxKind::LBracket, "["),
+                (SyntaxKind::Number, "0"),
+                (SyntaxKind::RBracket, "]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_trait_keywords() {
+        assert_eq!(
+            kinds("trait impl for"),
+            [
+                (SyntaxKind::TraitKw, "trait"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::ImplKw, "impl"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::ForKw, "for"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_exception_keywords() {
+        assert_eq!(
+            kinds("try catch finally throw"),
+            [
+                (SyntaxKind::TryKw, "try"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::CatchKw, "catch"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::FinallyKw, "finally"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::ThrowKw, "throw"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_question_mark() {
+        check("?", SyntaxKind::Question);
+    }
+
+    #[test]
+    fn lex_unrecognized_char() {
+        check("@", SyntaxKind::Error);
+    }
+
+    #[test]
+    fn lex_punctuation() {
+        assert_eq!(
+            kinds("{=;,()}"),
+            [
+                (SyntaxKind::LBrace, "{"),
+                (SyntaxKind::Equals, "="),
+                (SyntaxKind::Semicolon, ";"),
+                (SyntaxKind::Comma, ","),
+                (SyntaxKind::LParen, "("),
+                (SyntaxKind::RParen, ")"),
+                (SyntaxKind::RBrace, "}"),
+            ]
+        );
+    }
+}
@@ -1,28 +1,416 @@
+use std::rc::Rc;
+
+pub mod ast;
 mod binding_def;
+mod builtins;
 mod env;
 mod expr;
+mod fn_def;
+pub mod gc;
+mod impl_def;
+mod interrupt;
+mod lexer;
+pub mod limits;
+pub mod lint;
+pub mod optimize;
+mod parser;
+mod reparse;
+pub mod resolve;
+mod source_file;
 mod stmt;
-mod util;
+mod struct_def;
+mod symbol;
+pub mod syntax;
+mod trait_def;
+mod type_expr;
+pub mod typeck;
+mod unwind;
 mod val;
+mod vm;
+
+pub use env::{Env, EnvCheckpoint};
+pub use interrupt::InterruptHandle;
+pub use parser::ParseError;
+pub use reparse::TextEdit;
+pub use symbol::Symbol;
+pub use unwind::{ErrorKind, EvalError, Frame};
+pub use val::{Function, StructVal, Val};
 
-pub use env::Env;
-pub use val::Val;
+use lint::{LintConfig, Warning};
+use optimize::Passes;
+use resolve::ResolveError;
+use syntax::{GreenNode, SyntaxNode};
+use typeck::{Type, TypeEnv, TypeError};
 
 #[derive(Debug, PartialEq, Eq)]
-pub struct Parse(stmt::Stmt);
+pub struct Parse {
+    green: Rc<GreenNode>,
+    errors: Vec<ParseError>,
+    source_file: Option<source_file::SourceFile>,
+}
 
 impl Parse {
+    /// The lossless syntax tree of the parsed input. Printing it gives back the input unchanged.
+    #[inline]
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(Rc::clone(&self.green))
+    }
+
+    /// The AST of the input, which is only available if the input has no syntax errors.
+    #[inline]
+    pub fn source_file(&self) -> Option<&ast::SourceFile> {
+        self.source_file.as_ref()
+    }
+
+    /// Mutable access to the AST, so that transformations can be applied before evaluating it.
+    #[inline]
+    pub fn source_file_mut(&mut self) -> Option<&mut ast::SourceFile> {
+        self.source_file.as_mut()
+    }
+
+    /// Every syntax error found in the input, in source order.
+    #[inline]
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    #[inline]
+    pub fn debug_tree(&self) -> String {
+        self.syntax().debug_tree()
+    }
+
+    /// Parses the input again with `edit` applied, giving the same result as [`parse`] on the
+    /// edited input would.
+    ///
+    /// Only the innermost block containing the edit is parsed again if that block stays a block
+    /// on its own, with the rest of the syntax tree shared with this parse. Edits outside of any
+    /// block, or changing how the input around the block is parsed, fall back to parsing the
+    /// whole input.
+    ///
+    /// Only the syntax tree is reparsed incrementally. The AST is lowered again from the whole new
+    /// syntax tree, giving every node a new ID and dropping any changes made to the old AST, so a
+    /// reparse still takes time linear in the size of the input however small the edit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range of the edit doesn't lie within the input on character boundaries.
+    pub fn reparse(&self, edit: &TextEdit) -> Parse {
+        let root = self.syntax();
+
+        let Some((green, errors)) = reparse::reparse_block(&root, &self.errors, edit) else {
+            return parse(&edit.apply(&root.to_string()));
+        };
+
+        let source_file = if errors.is_empty() {
+            source_file::SourceFile::lower(&SyntaxNode::new_root(Rc::clone(&green)))
+        } else {
+            None
+        };
+
+        Parse {
+            green,
+            errors,
+            source_file,
+        }
+    }
+
+    /// Finds every use of a binding that doesn't exist in the input, with the bindings in `env`
+    /// in scope at the top level. On success each binding usage in the AST is annotated with the
+    /// slot its binding's value is kept in.
+    pub fn resolve(&mut self, env: &Env) -> Result<(), Vec<ResolveError>> {
+        match &mut self.source_file {
+            Some(source_file) => resolve::resolve(source_file, env),
+            None => Err(vec![ResolveError {
+                message: "cannot resolve input containing syntax errors".to_string(),
+                suggestion: None,
+                span: self.syntax().text_range(),
+            }]),
+        }
+    }
+
+    /// Type checks the input against the types of the bindings in `env`, returning the type of the
+    /// value it evaluates to. On success the types of its top-level bindings are added to `env`.
+    pub fn typeck(&self, env: &mut TypeEnv) -> Result<Type, Vec<TypeError>> {
+        match &self.source_file {
+            Some(source_file) => typeck::check(source_file, env),
+            None => Err(vec![TypeError::new(
+                "cannot type check input containing syntax errors",
+                self.syntax().text_range(),
+            )]),
+        }
+    }
+
+    /// Warnings about the input that `config` doesn't allow, in source order. Input containing
+    /// syntax errors has none, as it can't be evaluated anyway.
+    pub fn lint(&self, config: &LintConfig) -> Vec<Warning> {
+        match &self.source_file {
+            Some(source_file) => lint::check(source_file, config),
+            None => Vec::new(),
+        }
+    }
+
+    /// Runs the enabled optimization passes over the AST. Input containing syntax errors is left
+    /// alone, as it can't be evaluated anyway.
+    pub fn optimize(&mut self, passes: &Passes) {
+        if let Some(source_file) = &mut self.source_file {
+            optimize::optimize(source_file, passes);
+        }
+    }
+
     pub fn eval(&self, env: &mut Env) -> Result<Val, String> {
-        self.0.eval(env)
+        self.eval_traced(env).map_err(|error| error.to_string())
+    }
+
+    /// Like [`eval`](Self::eval), but errors also come with a trace of where they happened and
+    /// which function calls they were passed out of.
+    pub fn eval_traced(&self, env: &mut Env) -> Result<Val, EvalError> {
+        self.eval_with(env, Backend::default())
+    }
+
+    /// Like [`eval_traced`](Self::eval_traced), but with the given backend.
+    ///
+    /// Evaluation is subject to the [limits](limits) set on `env`, and can be stopped with its
+    /// [interrupt handle](Env::interrupt_handle).
+    pub fn eval_with(&self, env: &mut Env, backend: Backend) -> Result<Val, EvalError> {
+        let source_file = self.source_file.as_ref().ok_or_else(|| EvalError {
+            kind: ErrorKind::Runtime,
+            message: "cannot evaluate input containing syntax errors".to_string(),
+            trace: Vec::new(),
+        })?;
+
+        // Binding usages are resolved against `env` as it is now, whether or not the input was
+        // resolved before, so that both backends find every binding in the slot it ends up in.
+        let mut source_file = source_file.clone();
+        let _ = resolve::resolve(&mut source_file, env);
+
+        limits::enforce(env.limits(), env.interrupt(), || match backend {
+            Backend::TreeWalker => source_file.eval(env),
+            Backend::Vm => vm::eval(&source_file, env),
+        })
     }
 }
 
-pub fn parse(s: &str) -> Result<Parse, String> {
-    let (s, stmt) = stmt::Stmt::new(s)?;
+/// How an input is evaluated. Both backends behave exactly the same apart from their speed, and
+/// can be used one after the other with the same [`Env`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Walks the AST, evaluating each node as it is reached.
+    #[default]
+    TreeWalker,
+    /// Compiles the AST to bytecode, which is then run by a stack-based virtual machine.
+    Vm,
+}
 
-    if s.is_empty() {
-        Ok(Parse(stmt))
+/// Parses `s`, recovering from syntax errors.
+///
+/// The returned [`Parse`] always holds a syntax tree covering the whole input, with the parts that
+/// couldn't be parsed wrapped in error nodes. Only error-free input can be evaluated.
+pub fn parse(s: &str) -> Parse {
+    let (green, errors) = parser::parse(s);
+
+    let source_file = if errors.is_empty() {
+        source_file::SourceFile::lower(&SyntaxNode::new_root(Rc::clone(&green)))
     } else {
-        Err("input was not consumed fully by parser".to_string())
+        None
+    };
+
+    Parse {
+        green,
+        errors,
+        source_file,
+    }
+}
+
+/// Parses input consisting of exactly one statement, returning the arena it was lowered into
+/// along with it.
+#[cfg(test)]
+pub(crate) fn parse_stmt(s: &str) -> Result<(ast::Arena, ast::StmtId), Vec<String>> {
+    let parse = parse(s);
+
+    match parse.source_file {
+        Some(source_file) => {
+            assert_eq!(source_file.stmts.len(), 1);
+            Ok((source_file.arena().clone(), source_file.stmts[0]))
+        }
+        None => Err(parse.errors.iter().map(ToString::to_string).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syntax_reproduces_input() {
+        let input = "{\n  let a = 5 # five\n  a\n}\n";
+
+        assert_eq!(parse(input).syntax().to_string(), input);
+    }
+
+    #[test]
+    fn syntax_reproduces_input_with_errors() {
+        let input = "{ let = 1 + }\n} @";
+
+        assert_eq!(parse(input).syntax().to_string(), input);
+    }
+
+    #[test]
+    fn report_all_errors() {
+        assert_eq!(
+            parse("{ let = 5 let b 1 + }")
+                .errors()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "error at 6..7: expected identifier, but found '='",
+                "error at 16..17: expected '=', but found number '1'",
+                "error at 20..21: expected expression, but found '}'",
+            ]
+        );
+    }
+
+    #[test]
+    fn eval_multiple_stmts_in_callers_env() {
+        let mut env = Env::default();
+
+        assert_eq!(parse("let a = 1\nlet b = 2").eval(&mut env), Ok(Val::Unit));
+        assert_eq!(parse("{ let c = a; b }").eval(&mut env), Ok(Val::Number(2)));
+    }
+
+    #[test]
+    fn eval_finds_bindings_in_slots() {
+        let mut env = Env::default();
+        parse("let a = 1").eval(&mut env).unwrap();
+
+        // `f` uses the `a` in scope where it is defined, not the one its caller's scope binds.
+        assert_eq!(
+            parse("{ fn f() { a }; let a = 2; [f(), a] }").eval(&mut env),
+            Ok(Val::List(Rc::from([Val::Number(1), Val::Number(2)])))
+        );
+    }
+
+    #[test]
+    fn shadowed_bindings_keep_their_type_and_value() {
+        let parse = parse("let a = 1\nfn f() { a + 1 }\nlet a = \"s\"\nf()");
+
+        assert_eq!(
+            parse
+                .typeck(&mut TypeEnv::default())
+                .map(|ty| ty.to_string()),
+            Ok("Int".to_string())
+        );
+        assert_eq!(parse.eval(&mut Env::default()), Ok(Val::Number(2)));
+    }
+
+    #[test]
+    fn restore_env_after_failed_input() {
+        let mut env = Env::default();
+        parse("let a = true").eval(&mut env).unwrap();
+
+        let checkpoint = env.checkpoint();
+        assert!(parse("let a = 5\nstruct P {}\nthrow \"x\"")
+            .eval(&mut env)
+            .is_err());
+        env.restore(checkpoint);
+
+        assert_eq!(parse("a").eval(&mut env), Ok(Val::Bool(true)));
+        assert!(parse("P {}").eval(&mut env).is_err());
+    }
+
+    /// Input nested at most `levels` deep, in each way nesting comes about.
+    fn deeply_nested_inputs(levels: usize) -> Vec<String> {
+        vec![
+            vec!["1"; levels - 1].join(" + "),
+            format!("{}1", "-".repeat(levels - 1)),
+            format!("{}1{}", "(".repeat(levels - 1), ")".repeat(levels - 1)),
+            format!("{}1{}", "[".repeat(levels - 1), "]".repeat(levels - 1)),
+            format!(
+                "{}1{}",
+                "{ ".repeat(levels / 2 - 1),
+                " }".repeat(levels / 2 - 1)
+            ),
+            format!(
+                "{}1{}",
+                "fn f() { ".repeat(levels - 2),
+                " }".repeat(levels - 2)
+            ),
+            format!(
+                "fn f(x: {}Int{}) {{ 1 }}",
+                "Option<".repeat(levels - 2),
+                ">".repeat(levels - 2)
+            ),
+        ]
+    }
+
+    #[test]
+    fn check_and_eval_deeply_nested_input() {
+        for input in deeply_nested_inputs(128) {
+            let mut parse = parse(&input);
+            assert_eq!(parse.errors(), [], "input: {}", input);
+
+            let mut env = Env::default();
+            assert_eq!(parse.resolve(&env), Ok(()));
+            assert!(parse.typeck(&mut TypeEnv::default()).is_ok());
+            parse.lint(&LintConfig::default());
+            parse.optimize(&Passes::default());
+
+            for backend in [Backend::TreeWalker, Backend::Vm] {
+                assert!(parse.eval_with(&mut env, backend).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn reject_input_nested_too_deeply() {
+        for input in deeply_nested_inputs(256)
+            .into_iter()
+            .chain([vec!["1"; 3000].join(" + "), "(".repeat(100_000)])
+        {
+            let errors = parse(&input).errors().to_vec();
+            assert_eq!(errors.len(), 1, "input: {}", input);
+            assert!(errors[0]
+                .to_string()
+                .contains("expected at most 128 levels of nesting"));
+        }
+    }
+
+    #[test]
+    fn minus_on_new_line_is_not_subtracted() {
+        assert_eq!(
+            parse("let a = 1\n-1\na").eval(&mut Env::default()),
+            Ok(Val::Number(1))
+        );
+        assert_eq!(
+            parse("let a = 1 -\n1\na").eval(&mut Env::default()),
+            Ok(Val::Number(0))
+        );
+    }
+
+    #[test]
+    fn cannot_eval_input_with_errors() {
+        assert_eq!(
+            parse("let a =").eval(&mut Env::default()),
+            Err("cannot evaluate input containing syntax errors".to_string())
+        );
+    }
+
+    #[test]
+    fn cannot_typeck_input_with_errors() {
+        assert_eq!(
+            parse("let a =").typeck(&mut TypeEnv::default()),
+            Err(vec![TypeError::new(
+                "cannot type check input containing syntax errors",
+                syntax::TextRange::new(0, 7)
+            )])
+        );
+    }
+
+    #[test]
+    fn eval_parsed_input() {
+        assert_eq!(
+            parse("{ let a = 2 * 3; a }").eval(&mut Env::default()),
+            Ok(Val::Number(6))
+        );
     }
 }
@@ -0,0 +1,443 @@
+//! Limits on the resources evaluating an input can use, so that a host running untrusted input
+//! stays responsive however the input behaves.
+//!
+//! The limits are set on the [`Env`](crate::Env) the input is evaluated in, and apply to each
+//! evaluation separately. Using up any of them stops evaluation with an error of the kind
+//! [`ErrorKind::LimitExceeded`](crate::ErrorKind::LimitExceeded), which `try` expressions can't
+//! catch, and which leaves the environment usable for the next input.
+//!
+//! ```
+//! use tlang::{limits::{Limit, Limits}, Env, ErrorKind};
+//!
+//! let mut env = Env::default();
+//! env.set_limits(Limits {
+//!     steps: Some(10_000),
+//!     ..Limits::default()
+//! });
+//!
+//! let error = tlang::parse("fn f() { f() }\nf()")
+//!     .eval_traced(&mut env)
+//!     .unwrap_err();
+//! assert_eq!(error.kind, ErrorKind::LimitExceeded(Limit::Steps));
+//! ```
+
+use std::{
+    cell::{Cell, RefCell},
+    hint, mem,
+};
+
+use crate::{
+    interrupt::InterruptHandle,
+    symbol::Symbol,
+    unwind::{ErrorKind, Unwind},
+    val::Val,
+};
+
+/// How much evaluating an input may use of each resource, where `None` means there is no limit.
+///
+/// A step is the evaluation of an expression by the tree walker, or the execution of an
+/// instruction by the VM, so the same input takes a different number of steps with either
+/// backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub steps: Option<u64>,
+    /// How many function calls can be in progress at once. Tail calls replace the call they are
+    /// made from, so they don't count towards it.
+    pub call_depth: Option<usize>,
+    /// How many bytes can be allocated for strings, lists, structs and the contents of `Option`s
+    /// and `Result`s, in total.
+    pub allocation: Option<usize>,
+    /// How many bytes of the native stack evaluation can use. Unlike the other limits, this one
+    /// always applies, as running out of stack aborts the whole process: calls the tree walker
+    /// doesn't make as tail calls each take up some of it, however deep `call_depth` allows.
+    pub stack: usize,
+}
+
+/// Enough for a few hundred nested calls in a debug build, while leaving plenty of the 2 MiB that
+/// threads get by default for the host.
+pub const DEFAULT_STACK: usize = 1 << 20;
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            steps: None,
+            call_depth: None,
+            allocation: None,
+            stack: DEFAULT_STACK,
+        }
+    }
+}
+
+/// The resource an evaluation ran out of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Steps,
+    CallDepth,
+    Allocation,
+    Stack,
+}
+
+/// The limits of the evaluation running on this thread, and how much of them it has used so far.
+struct Budget {
+    limits: Cell<Limits>,
+    interrupt: RefCell<Option<InterruptHandle>>,
+    /// The step after which the step limit and the interrupt handle are checked next, so that
+    /// counting a step usually only takes one comparison.
+    checkpoint: Cell<u64>,
+    steps: Cell<u64>,
+    call_depth: Cell<usize>,
+    allocated: Cell<usize>,
+    /// The lowest address the native stack may grow to, or 0 outside of evaluation.
+    stack_end: Cell<usize>,
+}
+
+thread_local! {
+    static BUDGET: Budget = const {
+        Budget {
+            limits: Cell::new(Limits {
+                steps: None,
+                call_depth: None,
+                allocation: None,
+                stack: DEFAULT_STACK,
+            }),
+            interrupt: RefCell::new(None),
+            checkpoint: Cell::new(0),
+            steps: Cell::new(0),
+            call_depth: Cell::new(0),
+            allocated: Cell::new(0),
+            stack_end: Cell::new(0),
+        }
+    };
+}
+
+/// How many steps can pass between checking whether evaluation has been interrupted.
+const CHECK_INTERVAL: u64 = 1024;
+
+/// What an evaluation is subject to, and how much of its limits it has used so far.
+#[derive(Default)]
+struct State {
+    limits: Limits,
+    interrupt: Option<InterruptHandle>,
+    steps: u64,
+    call_depth: usize,
+    allocated: usize,
+    stack_end: usize,
+}
+
+impl Budget {
+    /// Puts `state` in effect, returning what was in effect before.
+    fn replace(&self, state: State) -> State {
+        self.checkpoint.set(state.steps);
+
+        State {
+            limits: self.limits.replace(state.limits),
+            interrupt: self.interrupt.replace(state.interrupt),
+            steps: self.steps.replace(state.steps),
+            call_depth: self.call_depth.replace(state.call_depth),
+            allocated: self.allocated.replace(state.allocated),
+            stack_end: self.stack_end.replace(state.stack_end),
+        }
+    }
+
+    /// Fails if evaluation has been interrupted, has taken more than `steps` steps or has used up
+    /// its stack, and otherwise sets the next checkpoint.
+    #[cold]
+    fn check(&self, steps: u64) -> Result<(), Unwind> {
+        if stack_addr() < self.stack_end.get() {
+            return Err(exceeded(Limit::Stack, self.limits.get().stack as u64));
+        }
+
+        if let Some(interrupt) = &*self.interrupt.borrow() {
+            if interrupt.take() {
+                return Err(Unwind::Error {
+                    kind: ErrorKind::Interrupted,
+                    message: "evaluation was interrupted".to_string(),
+                    trace: Default::default(),
+                });
+            }
+        }
+
+        let max = self.limits.get().steps.unwrap_or(u64::MAX);
+        if steps > max {
+            return Err(exceeded(Limit::Steps, max));
+        }
+
+        self.checkpoint
+            .set(steps.saturating_add(CHECK_INTERVAL).min(max));
+
+        Ok(())
+    }
+}
+
+/// Runs `f` with `limits` in effect and nothing used yet, and stopped by `interrupt`. The budget of
+/// any evaluation this is nested in is restored afterwards.
+pub(crate) fn enforce<T>(
+    limits: Limits,
+    interrupt: Option<InterruptHandle>,
+    f: impl FnOnce() -> T,
+) -> T {
+    /// Puts the outer budget back even if `f` panics.
+    struct Restore(Option<State>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(outer) = self.0.take() {
+                BUDGET.with(|budget| budget.replace(outer));
+            }
+        }
+    }
+
+    let outer = BUDGET.with(|budget| {
+        // An evaluation nested in another one runs on what is left of the same stack.
+        let stack_end = match budget.stack_end.get() {
+            0 => stack_addr().saturating_sub(limits.stack),
+            stack_end => stack_end,
+        };
+
+        budget.replace(State {
+            limits,
+            interrupt,
+            stack_end,
+            ..State::default()
+        })
+    });
+    let _restore = Restore(Some(outer));
+
+    f()
+}
+
+/// Adds `amount` to the amount of a resource used so far, failing if that is more than `max`.
+fn consume(
+    limit: Limit,
+    used: &Cell<usize>,
+    amount: usize,
+    max: Option<usize>,
+) -> Result<(), Unwind> {
+    let used = {
+        let total = used.get().saturating_add(amount);
+        used.set(total);
+        total
+    };
+
+    match max {
+        Some(max) if used > max => Err(exceeded(limit, max as u64)),
+        _ => Ok(()),
+    }
+}
+
+#[cold]
+fn exceeded(limit: Limit, max: u64) -> Unwind {
+    let message = match limit {
+        Limit::Steps => format!("evaluation took more than {} steps", max),
+        Limit::CallDepth => format!("calls were nested more than {} deep", max),
+        Limit::Allocation => format!("evaluation allocated more than {} bytes", max),
+        Limit::Stack => format!("evaluation used more than {} bytes of stack", max),
+    };
+
+    Unwind::Error {
+        kind: ErrorKind::LimitExceeded(limit),
+        message,
+        trace: Default::default(),
+    }
+}
+
+/// The address of the top of the native stack, near enough. Stacks are assumed to grow down, as
+/// they do on every platform the language runs on.
+#[inline(always)]
+fn stack_addr() -> usize {
+    let marker = 0u8;
+    hint::black_box(&marker) as *const u8 as usize
+}
+
+/// Counts a step of evaluation, which is where evaluation stops if it has been interrupted or has
+/// nested too deeply.
+#[inline]
+pub(crate) fn step() -> Result<(), Unwind> {
+    BUDGET.with(|budget| {
+        let steps = budget.steps.get() + 1;
+        budget.steps.set(steps);
+
+        if steps > budget.checkpoint.get() || stack_addr() < budget.stack_end.get() {
+            budget.check(steps)
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// Records that a function is called, which has to be followed by [`leave_call`] once it returns
+/// unless this fails.
+pub(crate) fn enter_call() -> Result<(), Unwind> {
+    BUDGET.with(|budget| {
+        let max = budget.limits.get().call_depth;
+        let result = consume(Limit::CallDepth, &budget.call_depth, 1, max);
+
+        if result.is_err() {
+            budget.call_depth.set(budget.call_depth.get() - 1);
+        }
+
+        result
+    })
+}
+
+pub(crate) fn leave_call() {
+    BUDGET.with(|budget| budget.call_depth.set(budget.call_depth.get() - 1));
+}
+
+/// Accounts for `val` having just been created, returning it if that is within the limit.
+pub(crate) fn allocated(val: Val) -> Result<Val, Unwind> {
+    let size = match &val {
+        Val::Str(s) => s.len(),
+        Val::List(elements) => elements.len() * mem::size_of::<Val>(),
+        Val::Struct(struct_val) => struct_val.fields.len() * mem::size_of::<(Symbol, Val)>(),
+        Val::Option(Some(_)) | Val::Result(_) => mem::size_of::<Val>(),
+        Val::Number(_) | Val::Bool(_) | Val::Fn(_) | Val::Option(None) | Val::Unit => 0,
+    };
+
+    BUDGET.with(|budget| {
+        let max = budget.limits.get().allocation;
+        consume(Limit::Allocation, &budget.allocated, size, max)
+    })?;
+
+    Ok(val)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Backend, Env, EvalError};
+
+    fn eval(s: &str, limits: Limits, backend: Backend) -> Result<Val, EvalError> {
+        let mut env = Env::default();
+        env.set_limits(limits);
+
+        crate::parse(s).eval_with(&mut env, backend)
+    }
+
+    /// The limit evaluating `s` with `limits` exceeds with either backend.
+    #[track_caller]
+    fn exceeded(s: &str, limits: Limits) -> Limit {
+        let kinds: Vec<_> = [Backend::TreeWalker, Backend::Vm]
+            .into_iter()
+            .map(|backend| eval(s, limits, backend).unwrap_err().kind)
+            .collect();
+
+        match kinds[..] {
+            [ErrorKind::LimitExceeded(limit), other] if other == kinds[0] => limit,
+            _ => panic!("no limit exceeded: {:?}", kinds),
+        }
+    }
+
+    #[test]
+    fn stop_infinite_tail_recursion() {
+        assert_eq!(
+            exceeded(
+                "fn f(n) { f(n + 1) }\nf(0)",
+                Limits {
+                    steps: Some(10_000),
+                    ..Limits::default()
+                }
+            ),
+            Limit::Steps
+        );
+    }
+
+    #[test]
+    fn stop_deep_recursion() {
+        assert_eq!(
+            exceeded(
+                "fn f(n) { 1 + f(n + 1) }\nf(0)",
+                Limits {
+                    call_depth: Some(20),
+                    ..Limits::default()
+                }
+            ),
+            Limit::CallDepth
+        );
+    }
+
+    /// Only the tree walker uses the native stack for calls, while the VM keeps its frames on the
+    /// heap.
+    #[test]
+    fn stop_deep_recursion_by_default() {
+        let error = eval(
+            "fn f(n) { if n == 0 { 0 } else { 1 + f(n - 1) } }\nf(100000)",
+            Limits::default(),
+            Backend::TreeWalker,
+        )
+        .unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::LimitExceeded(Limit::Stack));
+    }
+
+    #[test]
+    fn stop_growing_values() {
+        assert_eq!(
+            exceeded(
+                "fn f(list) { f([list, list, list, list]) }\nf([])",
+                Limits {
+                    allocation: Some(1 << 16),
+                    ..Limits::default()
+                }
+            ),
+            Limit::Allocation
+        );
+    }
+
+    #[test]
+    fn limits_cannot_be_caught() {
+        let error = eval(
+            "try { fn f() { f() }; f() } catch e { 0 }",
+            Limits {
+                steps: Some(1_000),
+                ..Limits::default()
+            },
+            Backend::TreeWalker,
+        )
+        .unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::LimitExceeded(Limit::Steps));
+        assert_eq!(error.message, "evaluation took more than 1000 steps");
+    }
+
+    #[test]
+    fn within_limits() {
+        let limits = Limits {
+            steps: Some(1_000),
+            call_depth: Some(20),
+            allocation: Some(1_000),
+            ..Limits::default()
+        };
+
+        for backend in [Backend::TreeWalker, Backend::Vm] {
+            assert_eq!(
+                eval(
+                    "fn fact(n) { if n == 0 { 1 } else { n * fact(n - 1) } }\n[fact(5), fact(6)][1]",
+                    limits,
+                    backend
+                ),
+                Ok(Val::Number(720))
+            );
+        }
+    }
+
+    #[test]
+    fn each_evaluation_gets_the_full_budget() {
+        let mut env = Env::default();
+        env.set_limits(Limits {
+            steps: Some(100),
+            ..Limits::default()
+        });
+
+        crate::parse("fn f(n) { if n == 0 { 0 } else { f(n - 1) } }")
+            .eval(&mut env)
+            .unwrap();
+
+        assert!(crate::parse("f(1000)").eval(&mut env).is_err());
+
+        for _ in 0..10 {
+            assert_eq!(crate::parse("f(5)").eval(&mut env), Ok(Val::Number(0)));
+        }
+    }
+}
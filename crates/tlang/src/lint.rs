@@ -0,0 +1,630 @@
+//! Warnings about code that is valid, but most likely not what was meant: bindings that are
+//! never used, bindings that shadow another one defined in the same block, and statements that
+//! can never be reached because an earlier one always throws.
+//!
+//! Each kind of warning is a [`Lint`], which can be allowed (not reported at all), warned about,
+//! or denied, in which case the caller is expected to treat it like an error.
+//!
+//! ```
+//! use tlang::lint::{Level, Lint, LintConfig};
+//!
+//! let parse = tlang::parse("{ let a = 1\nlet a = 2\n3 }");
+//!
+//! let warnings = parse.lint(&LintConfig::default());
+//! assert_eq!(
+//!     warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+//!     [
+//!         "warning at 2..11: binding 'a' is never used [unused-bindings]",
+//!         "warning at 12..21: binding 'a' shadows a binding of the same name in the same block [shadowed-bindings]",
+//!         "warning at 12..21: binding 'a' is never used [unused-bindings]",
+//!     ],
+//! );
+//!
+//! let mut config = LintConfig::default();
+//! config.set(Lint::UnusedBindings, Level::Allow);
+//! config.set(Lint::ShadowedBindings, Level::Deny);
+//!
+//! let warnings = parse.lint(&config);
+//! assert_eq!(warnings.len(), 1);
+//! assert!(warnings[0].is_denied());
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    ast::{
+        walk_binding_def, Arena, BindingDef, BindingUsage, Block, Catch, ExprId, ExprKind, FnDef,
+        ImplDef, SourceFile, Stmt, StmtId, TraitDef, Visitor,
+    },
+    stmt,
+    symbol::Symbol,
+    syntax::TextRange,
+};
+
+/// A kind of warning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// A binding, parameter, local function or caught value that is never used. Names starting
+    /// with `_` are exempt, as are top-level bindings, which later inputs might still use.
+    UnusedBindings,
+    /// A binding that takes the place of one with the same name defined earlier in the same
+    /// block, which can then no longer be used.
+    ShadowedBindings,
+    /// Statements following one that always throws.
+    UnreachableCode,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 3] = [
+        Lint::UnusedBindings,
+        Lint::ShadowedBindings,
+        Lint::UnreachableCode,
+    ];
+
+    /// The name the lint is referred to by, e.g. on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedBindings => "unused-bindings",
+            Lint::ShadowedBindings => "shadowed-bindings",
+            Lint::UnreachableCode => "unreachable-code",
+        }
+    }
+}
+
+impl Display for Lint {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Lint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Lint::ALL
+            .into_iter()
+            .find(|lint| lint.name() == s)
+            .ok_or_else(|| format!("unknown lint '{}'", s))
+    }
+}
+
+/// How a [`Lint`] is reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    #[default]
+    Warn,
+    Deny,
+}
+
+/// The [`Level`] of each [`Lint`], which is [`Level::Warn`] unless set otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintConfig {
+    levels: HashMap<Lint, Level>,
+}
+
+impl LintConfig {
+    pub fn set(&mut self, lint: Lint, level: Level) -> &mut Self {
+        self.levels.insert(lint, level);
+        self
+    }
+
+    pub fn level(&self, lint: Lint) -> Level {
+        self.levels.get(&lint).copied().unwrap_or_default()
+    }
+}
+
+/// A warning, located at the statement or expression it is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub lint: Lint,
+    /// Either [`Level::Warn`] or [`Level::Deny`], as allowed lints aren't reported.
+    pub level: Level,
+    pub message: String,
+    pub span: TextRange,
+}
+
+impl Warning {
+    #[inline]
+    pub fn is_denied(&self) -> bool {
+        self.level == Level::Deny
+    }
+}
+
+/// Denied lints are shown as errors.
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let level = if self.is_denied() { "error" } else { "warning" };
+
+        write!(
+            f,
+            "{} at {}: {} [{}]",
+            level, self.span, self.message, self.lint
+        )
+    }
+}
+
+/// Every warning about `source_file` that `config` doesn't allow, in source order.
+pub(crate) fn check(source_file: &SourceFile, config: &LintConfig) -> Vec<Warning> {
+    let mut linter = Linter {
+        config,
+        scopes: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    linter.visit_source_file(source_file);
+
+    let mut warnings = linter.warnings;
+    warnings.sort_by_key(|warning| warning.span.start);
+    warnings
+}
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+    /// The bindings defined in each enclosing block, function or `catch` clause, innermost last.
+    scopes: Vec<Scope>,
+    warnings: Vec<Warning>,
+}
+
+struct Scope {
+    /// Whether unused bindings of this scope are reported.
+    report_unused: bool,
+    /// In order of definition, so that later bindings shadow earlier ones.
+    bindings: Vec<Binding>,
+}
+
+struct Binding {
+    name: Symbol,
+    /// What sort of binding this is, to be shown in warnings.
+    what: &'static str,
+    span: TextRange,
+    used: bool,
+}
+
+impl Linter<'_> {
+    fn warn(&mut self, lint: Lint, message: String, span: TextRange) {
+        let level = self.config.level(lint);
+
+        if level != Level::Allow {
+            self.warnings.push(Warning {
+                lint,
+                level,
+                message,
+                span,
+            });
+        }
+    }
+
+    fn with_scope(&mut self, report_unused: bool, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(Scope {
+            report_unused,
+            bindings: Vec::new(),
+        });
+
+        f(self);
+
+        let scope = self.scopes.pop().unwrap();
+        if !scope.report_unused {
+            return;
+        }
+
+        for binding in scope.bindings {
+            if !binding.used && binding.name != "self" && !binding.name.starts_with('_') {
+                self.warn(
+                    Lint::UnusedBindings,
+                    format!("{} '{}' is never used", binding.what, binding.name),
+                    binding.span,
+                );
+            }
+        }
+    }
+
+    /// Only bindings defined with `let` or `fn` can shadow one another, as the parameters of a
+    /// function and the name of a `catch` clause are the only bindings of their scope.
+    fn define(&mut self, name: Symbol, what: &'static str, span: TextRange) {
+        let scope = self.scopes.last_mut().unwrap();
+        let shadows = scope.bindings.iter().any(|binding| binding.name == name);
+
+        scope.bindings.push(Binding {
+            name: name.clone(),
+            what,
+            span,
+            used: false,
+        });
+
+        if shadows {
+            self.warn(
+                Lint::ShadowedBindings,
+                format!(
+                    "{} '{}' shadows a binding of the same name in the same block",
+                    what, name
+                ),
+                span,
+            );
+        }
+    }
+
+    fn function(&mut self, arena: &Arena, fn_def: &FnDef) {
+        self.with_scope(true, |linter| {
+            let scope = linter.scopes.last_mut().unwrap();
+
+            for param in &fn_def.params {
+                scope.bindings.push(Binding {
+                    name: param.name.clone(),
+                    what: "parameter",
+                    span: param.span,
+                    used: false,
+                });
+            }
+
+            linter.visit_block(arena, &fn_def.body);
+        });
+    }
+
+    /// The functions of a group are all defined before any of their bodies is visited, as they can
+    /// call each other, like the [resolver](crate::resolve) has it.
+    fn stmts(&mut self, arena: &Arena, stmts: &[StmtId]) {
+        for group in stmt::groups(arena, stmts) {
+            for &stmt in group {
+                if let Stmt::FnDef(fn_def) = &arena[stmt] {
+                    self.define(fn_def.name.clone(), "function", fn_def.span);
+                }
+            }
+
+            for &stmt in group {
+                self.visit_stmt(arena, stmt);
+            }
+        }
+    }
+
+    fn check_reachable(&mut self, arena: &Arena, stmts: &[StmtId]) {
+        let Some(idx) = stmts.iter().position(|&stmt| stmt_diverges(arena, stmt)) else {
+            return;
+        };
+
+        if let (Some(&first), Some(&last)) = (stmts.get(idx + 1), stmts.last()) {
+            self.warn(
+                Lint::UnreachableCode,
+                "unreachable statement".to_string(),
+                TextRange::new(arena[first].span(arena).start, arena[last].span(arena).end),
+            );
+        }
+    }
+}
+
+impl Visitor for Linter<'_> {
+    fn visit_source_file(&mut self, source_file: &SourceFile) {
+        self.check_reachable(source_file.arena(), &source_file.stmts);
+        self.with_scope(false, |linter| {
+            linter.stmts(source_file.arena(), &source_file.stmts)
+        });
+    }
+
+    fn visit_block(&mut self, arena: &Arena, block: &Block) {
+        self.check_reachable(arena, &block.stmts);
+        self.with_scope(true, |linter| linter.stmts(arena, &block.stmts));
+    }
+
+    /// The value is visited first, as it can still refer to an earlier binding of the same name.
+    fn visit_binding_def(&mut self, arena: &Arena, binding_def: &BindingDef) {
+        walk_binding_def(self, arena, binding_def);
+        self.define(binding_def.name.clone(), "binding", binding_def.span);
+    }
+
+    /// The function is already defined along with the rest of its group, as it can call itself.
+    fn visit_fn_def(&mut self, arena: &Arena, fn_def: &FnDef) {
+        self.function(arena, fn_def);
+    }
+
+    /// Methods aren't bindings, so only their bodies are visited.
+    fn visit_impl_def(&mut self, arena: &Arena, impl_def: &ImplDef) {
+        for method in &impl_def.methods {
+            self.function(arena, method);
+        }
+    }
+
+    fn visit_trait_def(&mut self, _trait_def: &TraitDef) {}
+
+    fn visit_binding_usage(&mut self, binding_usage: &BindingUsage) {
+        let binding = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.bindings.iter_mut().rev())
+            .find(|binding| binding.name == binding_usage.name);
+
+        if let Some(binding) = binding {
+            binding.used = true;
+        }
+    }
+
+    fn visit_catch(&mut self, arena: &Arena, catch: &Catch) {
+        self.with_scope(true, |linter| {
+            linter.scopes.last_mut().unwrap().bindings.push(Binding {
+                name: catch.name.clone(),
+                what: "binding",
+                span: catch.span,
+                used: false,
+            });
+
+            linter.visit_block(arena, &catch.body);
+        });
+    }
+}
+
+/// Whether evaluating `stmt` always throws.
+pub(crate) fn stmt_diverges(arena: &Arena, stmt: StmtId) -> bool {
+    match &arena[stmt] {
+        Stmt::BindingDef(binding_def) => diverges(arena, binding_def.val),
+        &Stmt::Expr(expr) | &Stmt::Semi(expr) => diverges(arena, expr),
+        Stmt::FnDef(_) | Stmt::StructDef(_) | Stmt::TraitDef(_) | Stmt::ImplDef(_) => false,
+    }
+}
+
+fn block_diverges(arena: &Arena, block: &Block) -> bool {
+    block.stmts.iter().any(|&stmt| stmt_diverges(arena, stmt))
+}
+
+/// Whether evaluating `expr` always throws. This is conservative: only expressions that throw
+/// regardless of the values involved are taken into account, e.g. an `if` whose branches both
+/// throw, but not a call of a function that always does.
+fn diverges(arena: &Arena, expr: ExprId) -> bool {
+    let diverges = |expr: ExprId| diverges(arena, expr);
+    let block_diverges = |block: &Block| block_diverges(arena, block);
+
+    match &arena[expr].kind {
+        ExprKind::Number(_) | ExprKind::Bool(_) | ExprKind::Str(_) | ExprKind::BindingUsage(_) => {
+            false
+        }
+        ExprKind::Throw(_) => true,
+        &ExprKind::Operation { lhs, rhs, .. }
+        | &ExprKind::Index {
+            base: lhs,
+            index: rhs,
+        } => diverges(lhs) || diverges(rhs),
+        &ExprKind::Unary { expr, .. }
+        | &ExprKind::Field { base: expr, .. }
+        | &ExprKind::Try(expr) => diverges(expr),
+        ExprKind::Block(block) => block_diverges(block),
+        ExprKind::If(if_expr) => {
+            diverges(if_expr.condition)
+                || (block_diverges(&if_expr.then_branch)
+                    && if_expr.else_branch.is_some_and(diverges))
+        }
+        ExprKind::Call(call) => diverges(call.callee) || call.args.iter().copied().any(diverges),
+        ExprKind::List(elements) => elements.iter().copied().any(diverges),
+        ExprKind::StructLit(struct_lit) => {
+            struct_lit.fields.iter().any(|field| diverges(field.val))
+        }
+        ExprKind::MethodCall(method_call) => {
+            diverges(method_call.receiver) || method_call.args.iter().copied().any(diverges)
+        }
+        ExprKind::TryCatch(try_catch) => {
+            try_catch.finally.as_ref().is_some_and(block_diverges)
+                || (block_diverges(&try_catch.body)
+                    && try_catch
+                        .catch
+                        .as_ref()
+                        .is_none_or(|catch| block_diverges(&catch.body)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The warnings about `s` with the default config, each shown as its lint, the part of `s`
+    /// it is located at and its message.
+    fn lint(s: &str) -> Vec<(Lint, &str, String)> {
+        crate::parse(s)
+            .lint(&LintConfig::default())
+            .into_iter()
+            .map(|warning| {
+                (
+                    warning.lint,
+                    &s[warning.span.start..warning.span.end],
+                    warning.message,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn no_warnings() {
+        assert_eq!(lint("let a = 1\nfn f(x) { let y = x; y + a }\nf(2)"), []);
+    }
+
+    #[test]
+    fn unused_bindings() {
+        assert_eq!(
+            lint("fn f(x, _y) { let a = 1; fn g() { 1 }; 2 }"),
+            [
+                (
+                    Lint::UnusedBindings,
+                    "x",
+                    "parameter 'x' is never used".to_string()
+                ),
+                (
+                    Lint::UnusedBindings,
+                    "let a = 1",
+                    "binding 'a' is never used".to_string()
+                ),
+                (
+                    Lint::UnusedBindings,
+                    "fn g() { 1 }",
+                    "function 'g' is never used".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn top_level_bindings_are_not_reported_as_unused() {
+        assert_eq!(lint("let a = 1\nfn f() { 2 }"), []);
+    }
+
+    #[test]
+    fn methods_are_not_bindings() {
+        assert_eq!(
+            lint("struct P { x: Int }\ntrait Show { fn show(self) -> Str }\nimpl Show for P { fn show(self) { let s = \"P\"; \"P\" } }"),
+            [(
+                Lint::UnusedBindings,
+                "let s = \"P\"",
+                "binding 's' is never used".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn usages_refer_to_innermost_binding() {
+        assert_eq!(
+            lint("fn f() { let a = 1; { let a = 2; a } }"),
+            [(
+                Lint::UnusedBindings,
+                "let a = 1",
+                "binding 'a' is never used".to_string()
+            )]
+        );
+        assert_eq!(lint("fn f() { let a = 1; let a = a + 1; a }").len(), 1);
+    }
+
+    #[test]
+    fn functions_of_a_group_can_use_each_other() {
+        assert_eq!(
+            lint("{ fn even(n) { if n == 0 { true } else { odd(n - 1) } }\nfn odd(n) { if n == 0 { false } else { even(n - 1) } }\neven(3) }"),
+            []
+        );
+    }
+
+    #[test]
+    fn functions_use_bindings_defined_before_them() {
+        assert_eq!(
+            lint("{ let a = 1\nfn f() { a }\nlet a = 2\nf() }"),
+            [
+                (
+                    Lint::ShadowedBindings,
+                    "let a = 2",
+                    "binding 'a' shadows a binding of the same name in the same block".to_string()
+                ),
+                (
+                    Lint::UnusedBindings,
+                    "let a = 2",
+                    "binding 'a' is never used".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            lint("{ let a = 1\nfn f() { a }\nlet a = 2\nf() + a }"),
+            [(
+                Lint::ShadowedBindings,
+                "let a = 2",
+                "binding 'a' shadows a binding of the same name in the same block".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn unused_caught_value() {
+        assert_eq!(
+            lint("try { throw \"a\" } catch e { 1 }"),
+            [(
+                Lint::UnusedBindings,
+                "catch e { 1 }",
+                "binding 'e' is never used".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn shadowing_in_same_block() {
+        assert_eq!(
+            lint("let a = 1\nlet a = 2\nfn a() { a }"),
+            [
+                (
+                    Lint::ShadowedBindings,
+                    "let a = 2",
+                    "binding 'a' shadows a binding of the same name in the same block".to_string()
+                ),
+                (
+                    Lint::ShadowedBindings,
+                    "fn a() { a }",
+                    "function 'a' shadows a binding of the same name in the same block".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn shadowing_outer_binding_is_fine() {
+        assert_eq!(lint("let a = 1\n{ let a = 2; a }\nfn f(a) { a }"), []);
+    }
+
+    #[test]
+    fn unreachable_statements() {
+        assert_eq!(
+            lint("fn f(n) { if n < 0 { throw \"negative\" } else { throw \"positive\" }; let b = n; b }"),
+            [(
+                Lint::UnreachableCode,
+                "let b = n; b",
+                "unreachable statement".to_string()
+            )]
+        );
+        assert_eq!(
+            lint("throw \"a\"\n1"),
+            [(
+                Lint::UnreachableCode,
+                "1",
+                "unreachable statement".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn statements_after_possible_throw_are_reachable() {
+        assert_eq!(
+            lint("fn f(n) { if n < 0 { throw \"negative\" }; n }\ntry { throw \"a\" } catch e { e }\n1"),
+            []
+        );
+    }
+
+    #[test]
+    fn levels() {
+        let parse = crate::parse("throw \"a\"\n{ let a = 1; 2 }");
+
+        let mut config = LintConfig::default();
+        config.set(Lint::UnusedBindings, Level::Allow);
+        config.set(Lint::UnreachableCode, Level::Deny);
+
+        assert_eq!(
+            parse.lint(&config),
+            [Warning {
+                lint: Lint::UnreachableCode,
+                level: Level::Deny,
+                message: "unreachable statement".to_string(),
+                span: TextRange::new(10, 26),
+            }]
+        );
+        assert_eq!(
+            parse.lint(&config)[0].to_string(),
+            "error at 10..26: unreachable statement [unreachable-code]"
+        );
+    }
+
+    #[test]
+    fn parse_lint_names() {
+        for lint in Lint::ALL {
+            assert_eq!(lint.name().parse(), Ok(lint));
+        }
+
+        assert_eq!(
+            "unused".parse::<Lint>(),
+            Err("unknown lint 'unused'".to_string())
+        );
+    }
+}
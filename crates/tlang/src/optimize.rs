@@ -0,0 +1,719 @@
+//! Optimizations of the AST, which make it cheaper to evaluate without changing the value it
+//! evaluates to, the errors it causes or where they are located.
+//!
+//! Each optimization is a [`Pass`], and all of them are run until none of them finds anything
+//! left to change, as one pass can make way for another: folding `2 * 3` makes `let a = 2 * 3` a
+//! binding with a literal value, after which its usages can be inlined and folded in turn. Passes
+//! can be disabled one by one, which is mostly useful for debugging them.
+//!
+//! ```
+//! use tlang::{
+//!     optimize::{Pass, Passes},
+//!     Env, Val,
+//! };
+//!
+//! let mut parse = tlang::parse("let a = 2 * 3\n{ 1; a + 4 }");
+//! parse.optimize(Passes::default().set(Pass::RemoveDeadCode, false));
+//!
+//! assert_eq!(parse.eval(&mut Env::default()), Ok(Val::Number(10)));
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    ast::{
+        walk_binding_def_mut, walk_block_mut, walk_expr_mut, walk_stmt_mut, walk_try_catch_mut,
+        Arena, BindingDef, Block, Catch, ExprId, ExprKind, FnDef, ImplDef, Number, SourceFile,
+        Stmt, StmtId, TryCatch, UnaryOp, VisitorMut,
+    },
+    lint::stmt_diverges,
+    symbol::Symbol,
+    val::Val,
+};
+
+/// An optimization of the AST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pass {
+    /// Replaces operations on literals with their result, unless evaluating them fails or could
+    /// call a method, e.g. `2 * 3` with `6`.
+    FoldConstants,
+    /// Replaces usages of bindings defined with a literal value with the value, where the usage
+    /// can't refer to anything else. Top-level bindings are only inlined outside of functions, as
+    /// the functions could be called after a later input has defined them again.
+    InlineBindings,
+    /// Removes statements following one that always throws, and literals whose values are
+    /// discarded.
+    RemoveDeadCode,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 3] = [
+        Pass::FoldConstants,
+        Pass::InlineBindings,
+        Pass::RemoveDeadCode,
+    ];
+
+    /// The name the pass is referred to by, e.g. on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Pass::FoldConstants => "fold-constants",
+            Pass::InlineBindings => "inline-bindings",
+            Pass::RemoveDeadCode => "remove-dead-code",
+        }
+    }
+}
+
+impl Display for Pass {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Pass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pass::ALL
+            .into_iter()
+            .find(|pass| pass.name() == s)
+            .ok_or_else(|| format!("unknown optimization pass '{}'", s))
+    }
+}
+
+/// Which passes are run, which is all of them unless set otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Passes {
+    disabled: HashSet<Pass>,
+}
+
+impl Passes {
+    pub fn set(&mut self, pass: Pass, enabled: bool) -> &mut Self {
+        if enabled {
+            self.disabled.remove(&pass);
+        } else {
+            self.disabled.insert(pass);
+        }
+
+        self
+    }
+
+    pub fn is_enabled(&self, pass: Pass) -> bool {
+        !self.disabled.contains(&pass)
+    }
+}
+
+/// Runs the enabled passes over `source_file` until none of them changes it anymore.
+pub(crate) fn optimize(source_file: &mut SourceFile, passes: &Passes) {
+    loop {
+        let mut changed = false;
+
+        for pass in Pass::ALL {
+            if !passes.is_enabled(pass) {
+                continue;
+            }
+
+            changed |= match pass {
+                Pass::FoldConstants => run(&mut Folder::default(), source_file),
+                Pass::InlineBindings => run(&mut Inliner::default(), source_file),
+                Pass::RemoveDeadCode => run(&mut DeadCode::default(), source_file),
+            };
+        }
+
+        if !changed {
+            break;
+        }
+    }
+}
+
+trait Optimizer: VisitorMut {
+    fn changed(&self) -> bool;
+}
+
+/// Runs `optimizer` over `source_file`, returning whether it changed anything.
+fn run(optimizer: &mut impl Optimizer, source_file: &mut SourceFile) -> bool {
+    optimizer.visit_source_file_mut(source_file);
+    optimizer.changed()
+}
+
+/// Whether `kind` is a literal that evaluates to the same value without any effects. Strings are
+/// left out, as evaluating one counts against [`Limits::allocation`](crate::limits::Limits::allocation),
+/// which inlining or removing it would change.
+fn is_literal(kind: &ExprKind) -> bool {
+    matches!(kind, ExprKind::Number(_) | ExprKind::Bool(_))
+}
+
+#[derive(Default)]
+struct Folder {
+    changed: bool,
+}
+
+impl Optimizer for Folder {
+    fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+impl Folder {
+    fn fold(arena: &Arena, kind: &ExprKind) -> Option<ExprKind> {
+        match kind {
+            ExprKind::Operation { lhs, rhs, op } => match (&arena[*lhs].kind, &arena[*rhs].kind) {
+                (ExprKind::Number(Number(lhs)), ExprKind::Number(Number(rhs))) => {
+                    match op.eval_numbers(*lhs, *rhs).ok()? {
+                        Val::Number(n) => Some(ExprKind::Number(Number(n))),
+                        Val::Bool(b) => Some(ExprKind::Bool(b)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            ExprKind::Unary { op, expr } => match (op, &arena[*expr].kind) {
+                (UnaryOp::Neg, ExprKind::Number(Number(n))) => {
+                    n.checked_neg().map(|n| ExprKind::Number(Number(n)))
+                }
+                (UnaryOp::Not, ExprKind::Bool(b)) => Some(ExprKind::Bool(!b)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl VisitorMut for Folder {
+    /// Operands are folded first, so that nested operations fold all the way up.
+    fn visit_expr_mut(&mut self, arena: &mut Arena, expr: ExprId) {
+        walk_expr_mut(self, arena, expr);
+
+        if let Some(folded) = Self::fold(arena, &arena[expr].kind) {
+            arena[expr].kind = folded;
+            self.changed = true;
+        }
+    }
+}
+
+/// A scope of the inliner, which mirrors an environment created at runtime.
+#[derive(Default)]
+struct Scope {
+    /// How many times each name is defined anywhere in the scope, including after the code being
+    /// optimized.
+    definitions: HashMap<Symbol, usize>,
+    /// The literal values of the bindings defined so far that are the only definition of their
+    /// name in the scope.
+    literals: HashMap<Symbol, ExprKind>,
+    /// Whether the scope is the top level, whose bindings can be defined again by later inputs.
+    top_level: bool,
+}
+
+impl Scope {
+    fn new(names: impl IntoIterator<Item = Symbol>) -> Self {
+        let mut scope = Scope::default();
+
+        for name in names {
+            *scope.definitions.entry(name).or_default() += 1;
+        }
+
+        scope
+    }
+
+    /// A scope for `stmts`, with the names their `let` and `fn` statements define.
+    fn for_stmts(arena: &Arena, stmts: &[StmtId]) -> Self {
+        Self::new(stmts.iter().filter_map(|&stmt| match &arena[stmt] {
+            Stmt::BindingDef(binding_def) => Some(binding_def.name.clone()),
+            Stmt::FnDef(fn_def) => Some(fn_def.name.clone()),
+            _ => None,
+        }))
+    }
+}
+
+#[derive(Default)]
+struct Inliner {
+    scopes: Vec<Scope>,
+    /// How many functions the code being optimized is nested in.
+    functions: usize,
+    changed: bool,
+}
+
+impl Optimizer for Inliner {
+    fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+impl Inliner {
+    fn in_scope(&mut self, scope: Scope, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(scope);
+        f(self);
+        self.scopes.pop();
+    }
+
+    /// The literal value `name` refers to, if it can only ever refer to one. The innermost scope
+    /// defining the name anywhere is the one the usage might find it in, so any other definition
+    /// in that scope, or one that hasn't been evaluated yet, rules out inlining.
+    fn literal(&self, name: Symbol) -> Option<ExprKind> {
+        let scope = self
+            .scopes
+            .iter()
+            .rev()
+            .find(|scope| scope.definitions.contains_key(&name))?;
+
+        if scope.top_level && self.functions > 0 {
+            return None;
+        }
+
+        scope.literals.get(&name).cloned()
+    }
+
+    fn function(&mut self, arena: &mut Arena, fn_def: &mut FnDef) {
+        let params = Scope::new(fn_def.params.iter().map(|param| param.name.clone()));
+
+        self.functions += 1;
+        self.in_scope(params, |inliner| {
+            inliner.visit_block_mut(arena, &mut fn_def.body)
+        });
+        self.functions -= 1;
+    }
+}
+
+impl VisitorMut for Inliner {
+    fn visit_source_file_mut(&mut self, source_file: &mut SourceFile) {
+        let (stmts, arena) = source_file.parts_mut();
+        let scope = Scope {
+            top_level: true,
+            ..Scope::for_stmts(arena, stmts)
+        };
+
+        self.in_scope(scope, |inliner| {
+            for &mut stmt in stmts {
+                inliner.visit_stmt_mut(arena, stmt);
+            }
+        });
+    }
+
+    fn visit_block_mut(&mut self, arena: &mut Arena, block: &mut Block) {
+        self.in_scope(Scope::for_stmts(arena, &block.stmts), |inliner| {
+            walk_block_mut(inliner, arena, block)
+        });
+    }
+
+    /// The value is optimized first, as it can still refer to an earlier binding of the same
+    /// name.
+    fn visit_binding_def_mut(&mut self, arena: &mut Arena, binding_def: &mut BindingDef) {
+        walk_binding_def_mut(self, arena, binding_def);
+
+        let val = &arena[binding_def.val].kind;
+        let scope = self.scopes.last_mut().unwrap();
+        if scope.definitions[&binding_def.name] == 1 && is_literal(val) {
+            scope.literals.insert(binding_def.name.clone(), val.clone());
+        }
+    }
+
+    fn visit_fn_def_mut(&mut self, arena: &mut Arena, fn_def: &mut FnDef) {
+        self.function(arena, fn_def);
+    }
+
+    /// The methods share a scope, for the reason given where the [resolver](crate::resolve) builds
+    /// scopes.
+    fn visit_impl_def_mut(&mut self, arena: &mut Arena, impl_def: &mut ImplDef) {
+        self.in_scope(Scope::default(), |inliner| {
+            for method in &mut impl_def.methods {
+                inliner.function(arena, method);
+            }
+        });
+    }
+
+    fn visit_catch_mut(&mut self, arena: &mut Arena, catch: &mut Catch) {
+        self.in_scope(Scope::new([catch.name.clone()]), |inliner| {
+            inliner.visit_block_mut(arena, &mut catch.body)
+        });
+    }
+
+    fn visit_expr_mut(&mut self, arena: &mut Arena, expr: ExprId) {
+        match &arena[expr].kind {
+            ExprKind::BindingUsage(binding_usage) => {
+                if let Some(literal) = self.literal(binding_usage.name.clone()) {
+                    arena[expr].kind = literal;
+                    self.changed = true;
+                }
+            }
+            _ => walk_expr_mut(self, arena, expr),
+        }
+    }
+}
+
+#[derive(Default)]
+struct DeadCode {
+    changed: bool,
+}
+
+impl Optimizer for DeadCode {
+    fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+impl DeadCode {
+    /// Removes the dead statements of a block or of the top level, where `used` is whether the
+    /// value of the last statement is used.
+    fn stmts(&mut self, arena: &mut Arena, stmts: &mut Vec<StmtId>, used: bool) {
+        if let Some(idx) = stmts.iter().position(|&stmt| stmt_diverges(arena, stmt)) {
+            if idx + 1 < stmts.len() {
+                stmts.truncate(idx + 1);
+                self.changed = true;
+            }
+        }
+
+        let last = stmts.len().saturating_sub(1);
+
+        for (idx, &stmt) in stmts.iter().enumerate() {
+            match arena[stmt] {
+                Stmt::Semi(expr) => self.discarded(arena, expr),
+                Stmt::Expr(expr) if idx != last || !used => self.discarded(arena, expr),
+                _ => walk_stmt_mut(self, arena, stmt),
+            }
+        }
+
+        let len = stmts.len();
+        let mut idx = 0;
+
+        // The last statement stays even if its value is discarded with a `;`, as the value of the
+        // block would otherwise be that of the statement before it instead of unit.
+        stmts.retain(|&stmt| {
+            let kept = idx == last && used;
+            idx += 1;
+
+            match arena[stmt] {
+                Stmt::Semi(expr) | Stmt::Expr(expr) if !kept => !is_pure(arena, expr),
+                _ => true,
+            }
+        });
+
+        self.changed |= stmts.len() != len;
+    }
+
+    /// Optimizes an expression whose value isn't used.
+    fn discarded(&mut self, arena: &mut Arena, expr: ExprId) {
+        if !matches!(
+            arena[expr].kind,
+            ExprKind::Block(_) | ExprKind::If(_) | ExprKind::TryCatch(_)
+        ) {
+            self.visit_expr_mut(arena, expr);
+            return;
+        }
+
+        arena.with_expr(expr, |arena, expr| match &mut expr.kind {
+            ExprKind::Block(block) => self.stmts(arena, &mut block.stmts, false),
+            ExprKind::If(if_expr) => {
+                self.visit_expr_mut(arena, if_expr.condition);
+                self.stmts(arena, &mut if_expr.then_branch.stmts, false);
+
+                if let Some(else_branch) = if_expr.else_branch {
+                    self.discarded(arena, else_branch);
+                }
+            }
+            ExprKind::TryCatch(try_catch) => {
+                self.stmts(arena, &mut try_catch.body.stmts, false);
+
+                if let Some(catch) = &mut try_catch.catch {
+                    self.stmts(arena, &mut catch.body.stmts, false);
+                }
+
+                if let Some(finally) = &mut try_catch.finally {
+                    self.stmts(arena, &mut finally.stmts, false);
+                }
+            }
+            _ => unreachable!(),
+        });
+    }
+}
+
+impl VisitorMut for DeadCode {
+    fn visit_source_file_mut(&mut self, source_file: &mut SourceFile) {
+        let (stmts, arena) = source_file.parts_mut();
+        self.stmts(arena, stmts, true);
+    }
+
+    fn visit_block_mut(&mut self, arena: &mut Arena, block: &mut Block) {
+        self.stmts(arena, &mut block.stmts, true);
+    }
+
+    /// The value of a `finally` block is never used.
+    fn visit_try_catch_mut(&mut self, arena: &mut Arena, try_catch: &mut TryCatch) {
+        let finally = try_catch.finally.take();
+        walk_try_catch_mut(self, arena, try_catch);
+
+        try_catch.finally = finally.map(|mut finally| {
+            self.stmts(arena, &mut finally.stmts, false);
+            finally
+        });
+    }
+}
+
+/// Whether evaluating `expr` can't fail or have any effect, so that it can be left out if its
+/// value isn't used.
+fn is_pure(arena: &Arena, expr: ExprId) -> bool {
+    match &arena[expr].kind {
+        kind if is_literal(kind) => true,
+        ExprKind::Block(block) => block.stmts.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        ast::{walk_expr, walk_fn_def_mut, walk_stmt, Param, Visitor},
+        env::Env,
+        limits::Limits,
+        syntax::TextRange,
+        Backend, EvalError,
+    };
+
+    /// Clears the spans in an AST, so that it can be compared to one parsed from different input.
+    struct ClearSpans;
+
+    impl VisitorMut for ClearSpans {
+        fn visit_expr_mut(&mut self, arena: &mut Arena, expr: ExprId) {
+            arena[expr].span = TextRange::default();
+            walk_expr_mut(self, arena, expr);
+        }
+
+        fn visit_binding_def_mut(&mut self, arena: &mut Arena, binding_def: &mut BindingDef) {
+            binding_def.span = TextRange::default();
+            walk_binding_def_mut(self, arena, binding_def);
+        }
+
+        fn visit_fn_def_mut(&mut self, arena: &mut Arena, fn_def: &mut FnDef) {
+            fn_def.span = TextRange::default();
+            walk_fn_def_mut(self, arena, fn_def);
+        }
+
+        fn visit_param_mut(&mut self, param: &mut Param) {
+            param.span = TextRange::default();
+        }
+
+        fn visit_catch_mut(&mut self, arena: &mut Arena, catch: &mut Catch) {
+            catch.span = TextRange::default();
+            self.visit_block_mut(arena, &mut catch.body);
+        }
+    }
+
+    /// The statements and expressions of an AST in the order they are visited, without the IDs
+    /// of their children. As each node shows how many children it has, two ASTs have the same
+    /// nodes exactly when they have the same structure, regardless of how their arenas are laid
+    /// out and of any nodes an optimization has left behind in them.
+    struct Nodes(Vec<String>);
+
+    impl Nodes {
+        fn push(&mut self, node: &impl std::fmt::Debug) {
+            let node = format!("{:?}", node);
+            let mut without_ids = String::new();
+            let mut rest = node.as_str();
+
+            while let Some(idx) = rest.find("Id(") {
+                without_ids.push_str(&rest[..idx + 3]);
+                rest = rest[idx + 3..].trim_start_matches(|c: char| c.is_ascii_digit());
+            }
+
+            without_ids.push_str(rest);
+            self.0.push(without_ids);
+        }
+    }
+
+    impl Visitor for Nodes {
+        fn visit_stmt(&mut self, arena: &Arena, stmt: StmtId) {
+            self.push(&arena[stmt]);
+            walk_stmt(self, arena, stmt);
+        }
+
+        fn visit_expr(&mut self, arena: &Arena, expr: ExprId) {
+            self.push(&arena[expr]);
+            walk_expr(self, arena, expr);
+        }
+    }
+
+    fn optimized_with(s: &str, passes: &Passes) -> Vec<String> {
+        let mut parse = crate::parse(s);
+        parse.optimize(passes);
+
+        let mut source_file = parse.source_file().unwrap().clone();
+        ClearSpans.visit_source_file_mut(&mut source_file);
+
+        let mut nodes = Nodes(Vec::new());
+        nodes.visit_source_file(&source_file);
+        nodes.0
+    }
+
+    fn no_passes() -> Passes {
+        let mut passes = Passes::default();
+        for pass in Pass::ALL {
+            passes.set(pass, false);
+        }
+
+        passes
+    }
+
+    fn check(s: &str, expected: &str) {
+        assert_eq!(
+            optimized_with(s, &Passes::default()),
+            optimized_with(expected, &no_passes()),
+            "input: {}",
+            s
+        );
+    }
+
+    fn eval(s: &str, optimize: bool) -> Result<Val, EvalError> {
+        let mut parse = crate::parse(s);
+        if optimize {
+            parse.optimize(&Passes::default());
+        }
+
+        parse.eval_with(&mut Env::default(), Backend::TreeWalker)
+    }
+
+    #[test]
+    fn fold_constants() {
+        check("2 * 3 + 4", "10");
+        check("-(1 + 2) < 0 == !false", "true == true");
+        check("let a = [2 * 3, x + 1]", "let a = [6, x + 1]");
+    }
+
+    #[test]
+    fn leave_failing_and_overloadable_operations() {
+        check("1 / 0", "1 / 0");
+        check("2147483647 + 1", "2147483647 + 1");
+        check("\"a\" + \"b\"", "\"a\" + \"b\"");
+        check("true == true", "true == true");
+    }
+
+    #[test]
+    fn inline_bindings_with_literal_values() {
+        check("let a = 2 * 3\nlet b = a + 1\nb", "let a = 6\nlet b = 7\n7");
+        check(
+            "{ let t = true; fn f() { t }; f() }",
+            "{ let t = true; fn f() { true }; f() }",
+        );
+    }
+
+    #[test]
+    fn inline_only_where_the_binding_is_certain() {
+        // Defined twice in the same scope.
+        check("{ let a = 1; let a = 2; a }", "{ let a = 1; let a = 2; a }");
+        // The function could be called before `a` is defined.
+        check(
+            "{ fn f() { a }; let a = 1; f() }",
+            "{ fn f() { a }; let a = 1; f() }",
+        );
+        // The inner `a` shadows the outer one after the usage.
+        check(
+            "{ let a = 1; { let b = a; let a = 2; [a, b] } }",
+            "{ let a = 1; { let b = a; let a = 2; [2, b] } }",
+        );
+        // A later input could define a top-level binding again before calling the function.
+        check("let a = 1\nfn f() { a }\na", "let a = 1\nfn f() { a }\n1");
+        // Parameters and caught values aren't literals.
+        check("fn f(a) { a }", "fn f(a) { a }");
+        // Every evaluation of a string allocates it anew.
+        check("let s = \"hi\"\n[s, s]", "let s = \"hi\"\n[s, s]");
+    }
+
+    #[test]
+    fn remove_dead_code() {
+        check("1;\n2\n3", "3");
+        check("{ 1; [2, 3]; \"a\"; 4 }", "{ [2, 3]; \"a\"; 4 }");
+        check("fn f() { throw 1; 2 }", "fn f() { throw 1; }");
+        check("{ let a = 1; { 2; 3 }; a }", "{ let a = 1; 1 }");
+        check("try { 1 } finally { 2 }", "try { 1 } finally { }");
+        check("f(); 1; x", "f(); x");
+    }
+
+    #[test]
+    fn keep_last_stmt_discarding_value() {
+        check("1;\n2;", "2;");
+        check("fn f() { g()\n0; }", "fn f() { g()\n0; }");
+        check("{ 1; }", "{ 1; }");
+    }
+
+    #[test]
+    fn passes_can_be_disabled() {
+        let mut passes = Passes::default();
+        passes
+            .set(Pass::InlineBindings, false)
+            .set(Pass::RemoveDeadCode, false);
+
+        assert!(!passes.is_enabled(Pass::InlineBindings));
+        assert_eq!(
+            optimized_with("let a = 1 + 1\n3;\na", &passes),
+            optimized_with("let a = 2\n3;\na", &no_passes()),
+        );
+    }
+
+    #[test]
+    fn same_results_when_optimized() {
+        for s in [
+            "let a = 2\nfn f(x) { x * a }\nf(3 + 4)",
+            "{ let a = 1; fn f() { a }; let a = 2; f() }",
+            "let a = 1\n{ fn f() { a }; let a = 2; f() }",
+            "fn f() { 1 / 0; 2 }\nf()",
+            "fn f() { throw 1 + 1; 2 }\ntry { f() } catch e { e * 10 }",
+            "if 1 < 2 { 3; 4 } else { 5 }",
+            "let a: Bool = 1\na",
+            "try { 1 } finally { 2 + \"a\" }",
+            "fn f() { 42 }\nf()\n1;",
+            "fn h(x) { x * 2 }\nfn k() { h(3)\n0; }\nk()",
+            "struct P { x: Int }\ntrait Add { fn add(self, o: P) -> P }\nimpl Add for P { fn add(self, o) { P { x: self.x + o.x } } }\n(P { x: 1 } + P { x: 2 }).x",
+        ] {
+            assert_eq!(eval(s, true), eval(s, false), "input: {}", s);
+        }
+    }
+
+    #[test]
+    fn same_allocations_when_optimized() {
+        let eval = |s: &str, optimize: bool, allocation: usize| {
+            let mut env = Env::default();
+            env.set_limits(Limits {
+                allocation: Some(allocation),
+                ..Limits::default()
+            });
+
+            let mut parse = crate::parse(s);
+            if optimize {
+                parse.optimize(&Passes::default());
+            }
+
+            parse.eval_with(&mut env, Backend::TreeWalker)
+        };
+
+        for s in [
+            "let s = \"0123456789\"\n[s, s, s, s]",
+            "\"0123456789\";\n[1, 2, 3];\n1",
+        ] {
+            for allocation in 0..200 {
+                assert_eq!(
+                    eval(s, true, allocation),
+                    eval(s, false, allocation),
+                    "input: {}, allocation: {}",
+                    s,
+                    allocation
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parse_pass_names() {
+        for pass in Pass::ALL {
+            assert_eq!(pass.name().parse(), Ok(pass));
+        }
+
+        assert_eq!(
+            "fold".parse::<Pass>(),
+            Err("unknown optimization pass 'fold'".to_string())
+        );
+    }
+}
@@ -0,0 +1,1302 @@
+use std::rc::Rc;
+
+pub use error::ParseError;
+
+use crate::{
+    lexer::{Lexer, Token},
+    syntax::{Checkpoint, GreenNode, GreenNodeBuilder, SyntaxKind, TextRange},
+};
+
+mod error;
+mod grammar;
+
+/// Tokens that are never swallowed into an error node, since they are likely to start or end a
+/// statement or block the parser can continue from.
+const RECOVERY_SET: &[SyntaxKind] = &[
+    SyntaxKind::LetKw,
+    SyntaxKind::FnKw,
+    SyntaxKind::StructKw,
+    SyntaxKind::TraitKw,
+    SyntaxKind::ImplKw,
+    SyntaxKind::RBrace,
+];
+
+/// How deeply expressions, blocks and types can be nested, which keeps the passes over the tree,
+/// most of which recurse into nested nodes, from running out of stack.
+pub(crate) const MAX_NESTING: usize = 128;
+
+/// Parses `input` into a lossless green tree rooted at a [`SyntaxKind::SourceFile`] node.
+///
+/// Every token of the input, trivia included, ends up in the tree, so printing the tree gives back
+/// exactly `input`. Parsing never stops at the first error: tokens that don't fit the grammar are
+/// wrapped in [`SyntaxKind::Error`] nodes and every problem found is returned alongside the tree.
+pub(crate) fn parse(input: &str) -> (Rc<GreenNode>, Vec<ParseError>) {
+    let tokens: Vec<_> = Lexer::new(input).collect();
+
+    let mut parser = Parser {
+        tokens: &tokens,
+        cursor: 0,
+        input_len: input.len(),
+        builder: GreenNodeBuilder::default(),
+        errors: Vec::new(),
+        depth: 0,
+        gave_up: false,
+    };
+
+    grammar::source_file(&mut parser);
+
+    (parser.builder.finish(), parser.errors)
+}
+
+/// Parses `input` on its own as a single [`SyntaxKind::Block`], so that a block whose contents
+/// changed can be swapped into an existing tree.
+///
+/// Returns `None` unless the block's braces enclose the whole input, as the tokens would otherwise
+/// have been grouped differently in place. `follows_ident` tells whether the block comes right
+/// after an identifier, where `Name { field:` is parsed as a struct literal instead. `depth` is how
+/// deeply the block is nested in place; `None` is also returned if that leaves the block nested
+/// too deeply, as the rest of the input would then have been given up on too.
+pub(crate) fn parse_block(
+    input: &str,
+    follows_ident: bool,
+    depth: usize,
+) -> Option<(Rc<GreenNode>, Vec<ParseError>)> {
+    let tokens: Vec<_> = Lexer::new(input).collect();
+
+    if tokens.first()?.kind != SyntaxKind::LBrace || tokens.last()?.kind != SyntaxKind::RBrace {
+        return None;
+    }
+
+    let mut parser = Parser {
+        tokens: &tokens,
+        cursor: 0,
+        input_len: input.len(),
+        builder: GreenNodeBuilder::default(),
+        errors: Vec::new(),
+        depth,
+        gave_up: false,
+    };
+
+    if follows_ident
+        && parser.nth(1) == Some(SyntaxKind::Ident)
+        && parser.nth(2) == Some(SyntaxKind::Colon)
+    {
+        return None;
+    }
+
+    grammar::block(&mut parser);
+
+    if parser.gave_up || parser.cursor != tokens.len() {
+        return None;
+    }
+
+    let green = parser.builder.finish();
+    let closed = green
+        .children()
+        .last()
+        .is_some_and(|child| child.kind() == SyntaxKind::RBrace);
+
+    closed.then_some((green, parser.errors))
+}
+
+/// What the parser expected when it gave up on input nested too deeply.
+fn too_deep() -> String {
+    format!("at most {} levels of nesting", MAX_NESTING)
+}
+
+/// Whether the parser gave up on the input `errors` were found in for being nested too deeply,
+/// wrapping everything after that point in a single error node.
+pub(crate) fn gave_up(errors: &[ParseError]) -> bool {
+    let too_deep = too_deep();
+    errors.iter().any(|error| error.expected == too_deep)
+}
+
+struct Parser<'t, 'input> {
+    tokens: &'t [Token<'input>],
+    cursor: usize,
+    input_len: usize,
+    builder: GreenNodeBuilder,
+    errors: Vec<ParseError>,
+    /// How deeply the node being parsed is nested, in terms of [`MAX_NESTING`].
+    depth: usize,
+    /// Whether the parser gave up on the input for being nested too deeply, after which no more
+    /// errors are reported.
+    gave_up: bool,
+}
+
+impl Parser<'_, '_> {
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.eat_trivia();
+        self.builder.start_node(kind);
+    }
+
+    #[inline]
+    fn finish_node(&mut self) {
+        self.builder.finish_node();
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.eat_trivia();
+        self.builder.checkpoint()
+    }
+
+    #[inline]
+    fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        self.builder.start_node_at(checkpoint, kind);
+    }
+
+    fn peek_token(&self) -> Option<Token<'_>> {
+        self.tokens[self.cursor..]
+            .iter()
+            .find(|token| !token.kind.is_trivia())
+            .copied()
+    }
+
+    /// The kind of the `n`th non-trivia token from the cursor, where the next one is the 0th.
+    fn nth(&self, n: usize) -> Option<SyntaxKind> {
+        self.tokens[self.cursor..]
+            .iter()
+            .filter(|token| !token.kind.is_trivia())
+            .nth(n)
+            .map(|token| token.kind)
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<SyntaxKind> {
+        self.peek_token().map(|token| token.kind)
+    }
+
+    #[inline]
+    fn at(&self, kind: SyntaxKind) -> bool {
+        self.peek() == Some(kind)
+    }
+
+    #[inline]
+    fn at_set(&self, set: &[SyntaxKind]) -> bool {
+        self.peek().is_some_and(|kind| set.contains(&kind))
+    }
+
+    #[inline]
+    fn at_end(&self) -> bool {
+        self.peek().is_none()
+    }
+
+    /// Whether the trivia in front of the next token contains a line break.
+    fn at_line_break(&self) -> bool {
+        self.tokens[self.cursor..]
+            .iter()
+            .take_while(|token| token.kind.is_trivia())
+            .any(|token| token.kind == SyntaxKind::Whitespace && token.text.contains('\n'))
+    }
+
+    fn bump(&mut self) {
+        self.eat_trivia();
+
+        let token = self.tokens[self.cursor];
+        self.cursor += 1;
+
+        self.builder.token(token.kind, token.text);
+    }
+
+    /// Bumps the next token if it is of the given kind, and otherwise records an error without
+    /// consuming anything, as the token is likely to be useful to whatever comes next.
+    fn expect(&mut self, kind: SyntaxKind) {
+        if self.at(kind) {
+            self.bump();
+        } else {
+            self.error(&kind.to_string());
+        }
+    }
+
+    /// Records an error and, unless the next token is a good place to resume parsing, wraps that
+    /// token in an error node so that the parser makes progress.
+    fn error_and_recover(&mut self, expected: &str) {
+        self.error(expected);
+
+        if !self.at_set(RECOVERY_SET) && !self.at_end() {
+            self.error_node();
+        }
+    }
+
+    fn error_node(&mut self) {
+        self.start_node(SyntaxKind::Error);
+        self.bump();
+        self.finish_node();
+    }
+
+    /// Goes one level of nesting deeper, which has to be followed by [`leave`](Self::leave).
+    /// Returns `false` once that is too deep, after the rest of the input has been wrapped in an
+    /// error node, which leaves nothing to nest any deeper.
+    fn enter(&mut self) -> bool {
+        self.depth += 1;
+
+        if self.depth <= MAX_NESTING {
+            return true;
+        }
+
+        if !self.gave_up {
+            self.error(&too_deep());
+            self.gave_up = true;
+        }
+
+        if !self.at_end() {
+            self.start_node(SyntaxKind::Error);
+            while !self.at_end() {
+                self.bump();
+            }
+            self.finish_node();
+        }
+
+        false
+    }
+
+    #[inline]
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn error(&mut self, expected: &str) {
+        if self.gave_up {
+            return;
+        }
+
+        let (found, range) = match self.peek_token() {
+            Some(token) => (Some(describe_token(token)), token.range),
+            None => (None, TextRange::at(self.input_len, 0)),
+        };
+
+        // Several alternatives failing on the same token are reported once.
+        if self.errors.last().is_some_and(|error| error.range == range) {
+            return;
+        }
+
+        self.errors.push(ParseError::new(expected, found, range));
+    }
+
+    fn eat_trivia(&mut self) {
+        while let Some(token) = self.tokens.get(self.cursor) {
+            if !token.kind.is_trivia() {
+                break;
+            }
+
+            self.cursor += 1;
+            self.builder.token(token.kind, token.text);
+        }
+    }
+}
+
+fn describe_token(token: Token) -> String {
+    match token.kind {
+        SyntaxKind::Ident | SyntaxKind::Number | SyntaxKind::Error => {
+            format!("{} '{}'", token.kind, token.text)
+        }
+        kind => kind.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::syntax::SyntaxNode;
+
+    fn check(input: &str, expected: &str) {
+        let (green, errors) = parse(input);
+        let root = SyntaxNode::new_root(green);
+
+        let mut actual = root.debug_tree();
+        for error in errors {
+            actual.push_str(&format!("{}\n", error));
+        }
+
+        assert_eq!(actual, expected);
+        assert_eq!(root.to_string(), input);
+    }
+
+    #[test]
+    fn parse_number() {
+        check(
+            "123",
+            r#"SourceFile@0..3
+  Literal@0..3
+    Number@0..3 "123"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_operation_with_whitespace() {
+        check(
+            "2 * 2",
+            r#"SourceFile@0..5
+  BinaryExpr@0..5
+    Literal@0..1
+      Number@0..1 "2"
+    Whitespace@1..2 " "
+    Star@2..3 "*"
+    Whitespace@3..4 " "
+    Literal@4..5
+      Number@4..5 "2"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_binding_def() {
+        check(
+            "let a = b",
+            r#"SourceFile@0..9
+  BindingDef@0..9
+    LetKw@0..3 "let"
+    Whitespace@3..4 " "
+    Ident@4..5 "a"
+    Whitespace@5..6 " "
+    Equals@6..7 "="
+    Whitespace@7..8 " "
+    BindingUsage@8..9
+      Ident@8..9 "b"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_block_with_comments() {
+        check(
+            "{ # first\n  1 }",
+            r##"SourceFile@0..15
+  Block@0..15
+    LBrace@0..1 "{"
+    Whitespace@1..2 " "
+    Comment@2..9 "# first"
+    Whitespace@9..12 "\n  "
+    Literal@12..13
+      Number@12..13 "1"
+    Whitespace@13..14 " "
+    RBrace@14..15 "}"
+"##,
+        );
+    }
+
+    #[test]
+    fn leading_and_trailing_trivia_belong_to_root() {
+        check(
+            "  # answer\n42  \n",
+            r##"SourceFile@0..16
+  Whitespace@0..2 "  "
+  Comment@2..10 "# answer"
+  Whitespace@10..11 "\n"
+  Literal@11..13
+    Number@11..13 "42"
+  Whitespace@13..16 "  \n"
+"##,
+        );
+    }
+
+    #[test]
+    fn print_reproduces_input() {
+        let input = "{\n    # the answer\n    let a = 40 + 2\n\n    a # done\n}\n";
+
+        assert_eq!(SyntaxNode::new_root(parse(input).0).to_string(), input);
+    }
+
+    #[test]
+    fn parse_empty_source_file() {
+        check("", "SourceFile@0..0\n");
+    }
+
+    #[test]
+    fn parse_stmts_separated_by_line_breaks() {
+        check(
+            "let a = 1\na # a is one",
+            r##"SourceFile@0..22
+  BindingDef@0..9
+    LetKw@0..3 "let"
+    Whitespace@3..4 " "
+    Ident@4..5 "a"
+    Whitespace@5..6 " "
+    Equals@6..7 "="
+    Whitespace@7..8 " "
+    Literal@8..9
+      Number@8..9 "1"
+  Whitespace@9..10 "\n"
+  BindingUsage@10..11
+    Ident@10..11 "a"
+  Whitespace@11..12 " "
+  Comment@12..22 "# a is one"
+"##,
+        );
+    }
+
+    #[test]
+    fn parse_stmts_separated_by_semicolons() {
+        check(
+            "let a = 1; a;",
+            r#"SourceFile@0..13
+  BindingDef@0..9
+    LetKw@0..3 "let"
+    Whitespace@3..4 " "
+    Ident@4..5 "a"
+    Whitespace@5..6 " "
+    Equals@6..7 "="
+    Whitespace@7..8 " "
+    Literal@8..9
+      Number@8..9 "1"
+  Semicolon@9..10 ";"
+  Whitespace@10..11 " "
+  BindingUsage@11..12
+    Ident@11..12 "a"
+  Semicolon@12..13 ";"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_prefix_call_and_precedence() {
+        check(
+            "-1 + f(2, 3) * 4",
+            r#"SourceFile@0..16
+  BinaryExpr@0..16
+    PrefixExpr@0..2
+      Minus@0..1 "-"
+      Literal@1..2
+        Number@1..2 "1"
+    Whitespace@2..3 " "
+    Plus@3..4 "+"
+    Whitespace@4..5 " "
+    BinaryExpr@5..16
+      CallExpr@5..12
+        BindingUsage@5..6
+          Ident@5..6 "f"
+        ArgList@6..12
+          LParen@6..7 "("
+          Literal@7..8
+            Number@7..8 "2"
+          Comma@8..9 ","
+          Whitespace@9..10 " "
+          Literal@10..11
+            Number@10..11 "3"
+          RParen@11..12 ")"
+      Whitespace@12..13 " "
+      Star@13..14 "*"
+      Whitespace@14..15 " "
+      Literal@15..16
+        Number@15..16 "4"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_fn_def() {
+        check(
+            "fn f(a, b) { a }",
+            r#"SourceFile@0..16
+  FnDef@0..16
+    FnKw@0..2 "fn"
+    Whitespace@2..3 " "
+    Ident@3..4 "f"
+    ParamList@4..10
+      LParen@4..5 "("
+      Param@5..6
+        Ident@5..6 "a"
+      Comma@6..7 ","
+      Whitespace@7..8 " "
+      Param@8..9
+        Ident@8..9 "b"
+      RParen@9..10 ")"
+    Whitespace@10..11 " "
+    Block@11..16
+      LBrace@11..12 "{"
+      Whitespace@12..13 " "
+      BindingUsage@13..14
+        Ident@13..14 "a"
+      Whitespace@14..15 " "
+      RBrace@15..16 "}"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_else_if_chain() {
+        check(
+            "if !a { 1 } else if b { 2 } else { 3 }",
+            r#"SourceFile@0..38
+  IfExpr@0..38
+    IfKw@0..2 "if"
+    Whitespace@2..3 " "
+    PrefixExpr@3..5
+      Bang@3..4 "!"
+      BindingUsage@4..5
+        Ident@4..5 "a"
+    Whitespace@5..6 " "
+    Block@6..11
+      LBrace@6..7 "{"
+      Whitespace@7..8 " "
+      Literal@8..9
+        Number@8..9 "1"
+      Whitespace@9..10 " "
+      RBrace@10..11 "}"
+    Whitespace@11..12 " "
+    ElseKw@12..16 "else"
+    Whitespace@16..17 " "
+    IfExpr@17..38
+      IfKw@17..19 "if"
+      Whitespace@19..20 " "
+      BindingUsage@20..21
+        Ident@20..21 "b"
+      Whitespace@21..22 " "
+      Block@22..27
+        LBrace@22..23 "{"
+        Whitespace@23..24 " "
+        Literal@24..25
+          Number@24..25 "2"
+        Whitespace@25..26 " "
+        RBrace@26..27 "}"
+      Whitespace@27..28 " "
+      ElseKw@28..32 "else"
+      Whitespace@32..33 " "
+      Block@33..38
+        LBrace@33..34 "{"
+        Whitespace@34..35 " "
+        Literal@35..36
+          Number@35..36 "3"
+        Whitespace@36..37 " "
+        RBrace@37..38 "}"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_type_annotations() {
+        check(
+            "let f: fn(Int) -> Bool = g",
+            r#"SourceFile@0..26
+  BindingDef@0..26
+    LetKw@0..3 "let"
+    Whitespace@3..4 " "
+    Ident@4..5 "f"
+    Colon@5..6 ":"
+    Whitespace@6..7 " "
+    FnType@7..22
+      FnKw@7..9 "fn"
+      ParamTypeList@9..14
+        LParen@9..10 "("
+        NamedType@10..13
+          Ident@10..13 "Int"
+        RParen@13..14 ")"
+      Whitespace@14..15 " "
+      RetType@15..22
+        Arrow@15..17 "->"
+        Whitespace@17..18 " "
+        NamedType@18..22
+          Ident@18..22 "Bool"
+    Whitespace@22..23 " "
+    Equals@23..24 "="
+    Whitespace@24..25 " "
+    BindingUsage@25..26
+      Ident@25..26 "g"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_struct_def() {
+        check(
+            "struct Pair<A, B> { first: A, second: List<B> }",
+            r#"SourceFile@0..47
+  StructDef@0..47
+    StructKw@0..6 "struct"
+    Whitespace@6..7 " "
+    Ident@7..11 "Pair"
+    TypeParamList@11..17
+      Less@11..12 "<"
+      TypeParam@12..13
+        Ident@12..13 "A"
+      Comma@13..14 ","
+      Whitespace@14..15 " "
+      TypeParam@15..16
+        Ident@15..16 "B"
+      Greater@16..17 ">"
+    Whitespace@17..18 " "
+    FieldDefList@18..47
+      LBrace@18..19 "{"
+      Whitespace@19..20 " "
+      FieldDef@20..28
+        Ident@20..25 "first"
+        Colon@25..26 ":"
+        Whitespace@26..27 " "
+        NamedType@27..28
+          Ident@27..28 "A"
+      Comma@28..29 ","
+      Whitespace@29..30 " "
+      FieldDef@30..45
+        Ident@30..36 "second"
+        Colon@36..37 ":"
+        Whitespace@37..38 " "
+        NamedType@38..45
+          Ident@38..42 "List"
+          TypeArgList@42..45
+            Less@42..43 "<"
+            NamedType@43..44
+              Ident@43..44 "B"
+            Greater@44..45 ">"
+      Whitespace@45..46 " "
+      RBrace@46..47 "}"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_generic_fn_def() {
+        check(
+            "fn first<T>(xs: List<T>) -> T { xs[0] }",
+            r#"SourceFile@0..39
+  FnDef@0..39
+    FnKw@0..2 "fn"
+    Whitespace@2..3 " "
+    Ident@3..8 "first"
+    TypeParamList@8..11
+      Less@8..9 "<"
+      TypeParam@9..10
+        Ident@9..10 "T"
+      Greater@10..11 ">"
+    ParamList@11..24
+      LParen@11..12 "("
+      Param@12..23
+        Ident@12..14 "xs"
+        Colon@14..15 ":"
+        Whitespace@15..16 " "
+        NamedType@16..23
+          Ident@16..20 "List"
+          TypeArgList@20..23
+            Less@20..21 "<"
+            NamedType@21..22
+              Ident@21..22 "T"
+            Greater@22..23 ">"
+      RParen@23..24 ")"
+    Whitespace@24..25 " "
+    RetType@25..29
+      Arrow@25..27 "->"
+      Whitespace@27..28 " "
+      NamedType@28..29
+        Ident@28..29 "T"
+    Whitespace@29..30 " "
+    Block@30..39
+      LBrace@30..31 "{"
+      Whitespace@31..32 " "
+      IndexExpr@32..37
+        BindingUsage@32..34
+          Ident@32..34 "xs"
+        LBracket@34..35 "["
+        Literal@35..36
+          Number@35..36 "0"
+        RBracket@36..37 "]"
+      Whitespace@37..38 " "
+      RBrace@38..39 "}"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_list_index_and_field() {
+        check(
+            "[p.x, 2][1]",
+            r#"SourceFile@0..11
+  IndexExpr@0..11
+    ListExpr@0..8
+      LBracket@0..1 "["
+      FieldExpr@1..4
+        BindingUsage@1..2
+          Ident@1..2 "p"
+        Dot@2..3 "."
+        Ident@3..4 "x"
+      Comma@4..5 ","
+      Whitespace@5..6 " "
+      Literal@6..7
+        Number@6..7 "2"
+      RBracket@7..8 "]"
+    LBracket@8..9 "["
+    Literal@9..10
+      Number@9..10 "1"
+    RBracket@10..11 "]"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_struct_lit() {
+        check(
+            "P { x: 1, y: [] }",
+            r#"SourceFile@0..17
+  StructLit@0..17
+    Ident@0..1 "P"
+    Whitespace@1..2 " "
+    FieldInitList@2..17
+      LBrace@2..3 "{"
+      Whitespace@3..4 " "
+      FieldInit@4..8
+        Ident@4..5 "x"
+        Colon@5..6 ":"
+        Whitespace@6..7 " "
+        Literal@7..8
+          Number@7..8 "1"
+      Comma@8..9 ","
+      Whitespace@9..10 " "
+      FieldInit@10..15
+        Ident@10..11 "y"
+        Colon@11..12 ":"
+        Whitespace@12..13 " "
+        ListExpr@13..15
+          LBracket@13..14 "["
+          RBracket@14..15 "]"
+      Whitespace@15..16 " "
+      RBrace@16..17 "}"
+"#,
+        );
+    }
+
+    #[test]
+    fn index_on_new_line_starts_new_statement() {
+        check(
+            "a\n[1]",
+            r#"SourceFile@0..5
+  BindingUsage@0..1
+    Ident@0..1 "a"
+  Whitespace@1..2 "\n"
+  ListExpr@2..5
+    LBracket@2..3 "["
+    Literal@3..4
+      Number@3..4 "1"
+    RBracket@4..5 "]"
+"#,
+        );
+    }
+
+    #[test]
+    fn minus_on_new_line_starts_new_statement() {
+        check(
+            "a\n-1",
+            r#"SourceFile@0..4
+  BindingUsage@0..1
+    Ident@0..1 "a"
+  Whitespace@1..2 "\n"
+  PrefixExpr@2..4
+    Minus@2..3 "-"
+    Literal@3..4
+      Number@3..4 "1"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_trait_def() {
+        check(
+            "trait Show {\n    fn show(self) -> Str\n    fn eq(self, other: Self) -> Bool;\n}",
+            r#"SourceFile@0..77
+  TraitDef@0..77
+    TraitKw@0..5 "trait"
+    Whitespace@5..6 " "
+    Ident@6..10 "Show"
+    Whitespace@10..11 " "
+    TraitItemList@11..77
+      LBrace@11..12 "{"
+      Whitespace@12..17 "\n    "
+      FnSig@17..37
+        FnKw@17..19 "fn"
+        Whitespace@19..20 " "
+        Ident@20..24 "show"
+        ParamList@24..30
+          LParen@24..25 "("
+          Param@25..29
+            Ident@25..29 "self"
+          RParen@29..30 ")"
+        Whitespace@30..31 " "
+        RetType@31..37
+          Arrow@31..33 "->"
+          Whitespace@33..34 " "
+          NamedType@34..37
+            Ident@34..37 "Str"
+      Whitespace@37..42 "\n    "
+      FnSig@42..74
+        FnKw@42..44 "fn"
+        Whitespace@44..45 " "
+        Ident@45..47 "eq"
+        ParamList@47..66
+          LParen@47..48 "("
+          Param@48..52
+            Ident@48..52 "self"
+          Comma@52..53 ","
+          Whitespace@53..54 " "
+          Param@54..65
+            Ident@54..59 "other"
+            Colon@59..60 ":"
+            Whitespace@60..61 " "
+            NamedType@61..65
+              Ident@61..65 "Self"
+          RParen@65..66 ")"
+        Whitespace@66..67 " "
+        RetType@67..74
+          Arrow@67..69 "->"
+          Whitespace@69..70 " "
+          NamedType@70..74
+            Ident@70..74 "Bool"
+      Semicolon@74..75 ";"
+      Whitespace@75..76 "\n"
+      RBrace@76..77 "}"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_impl_def() {
+        check(
+            "impl<T> Show for List<T> { fn show(self) { \"\" } }",
+            r#"SourceFile@0..49
+  ImplDef@0..49
+    ImplKw@0..4 "impl"
+    TypeParamList@4..7
+      Less@4..5 "<"
+      TypeParam@5..6
+        Ident@5..6 "T"
+      Greater@6..7 ">"
+    Whitespace@7..8 " "
+    Ident@8..12 "Show"
+    Whitespace@12..13 " "
+    ForKw@13..16 "for"
+    Whitespace@16..17 " "
+    NamedType@17..24
+      Ident@17..21 "List"
+      TypeArgList@21..24
+        Less@21..22 "<"
+        NamedType@22..23
+          Ident@22..23 "T"
+        Greater@23..24 ">"
+    Whitespace@24..25 " "
+    ImplItemList@25..49
+      LBrace@25..26 "{"
+      Whitespace@26..27 " "
+      FnDef@27..47
+        FnKw@27..29 "fn"
+        Whitespace@29..30 " "
+        Ident@30..34 "show"
+        ParamList@34..40
+          LParen@34..35 "("
+          Param@35..39
+            Ident@35..39 "self"
+          RParen@39..40 ")"
+        Whitespace@40..41 " "
+        Block@41..47
+          LBrace@41..42 "{"
+          Whitespace@42..43 " "
+          Literal@43..45
+            String@43..45 "\"\""
+          Whitespace@45..46 " "
+          RBrace@46..47 "}"
+      Whitespace@47..48 " "
+      RBrace@48..49 "}"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_method_call() {
+        check(
+            "p.add(q).x",
+            r#"SourceFile@0..10
+  FieldExpr@0..10
+    MethodCallExpr@0..8
+      BindingUsage@0..1
+        Ident@0..1 "p"
+      Dot@1..2 "."
+      Ident@2..5 "add"
+      ArgList@5..8
+        LParen@5..6 "("
+        BindingUsage@6..7
+          Ident@6..7 "q"
+        RParen@7..8 ")"
+    Dot@8..9 "."
+    Ident@9..10 "x"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_try() {
+        check(
+            "f(x)?.y?",
+            r#"SourceFile@0..8
+  TryExpr@0..8
+    FieldExpr@0..7
+      TryExpr@0..5
+        CallExpr@0..4
+          BindingUsage@0..1
+            Ident@0..1 "f"
+          ArgList@1..4
+            LParen@1..2 "("
+            BindingUsage@2..3
+              Ident@2..3 "x"
+            RParen@3..4 ")"
+        Question@4..5 "?"
+      Dot@5..6 "."
+      Ident@6..7 "y"
+    Question@7..8 "?"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_throw_and_try() {
+        check(
+            "try { throw \"a\" } catch e {}\nfinally {}",
+            r#"SourceFile@0..39
+  TryCatchExpr@0..39
+    TryKw@0..3 "try"
+    Whitespace@3..4 " "
+    Block@4..17
+      LBrace@4..5 "{"
+      Whitespace@5..6 " "
+      ThrowExpr@6..15
+        ThrowKw@6..11 "throw"
+        Whitespace@11..12 " "
+        Literal@12..15
+          String@12..15 "\"a\""
+      Whitespace@15..16 " "
+      RBrace@16..17 "}"
+    Whitespace@17..18 " "
+    CatchClause@18..28
+      CatchKw@18..23 "catch"
+      Whitespace@23..24 " "
+      Ident@24..25 "e"
+      Whitespace@25..26 " "
+      Block@26..28
+        LBrace@26..27 "{"
+        RBrace@27..28 "}"
+    Whitespace@28..29 "\n"
+    FinallyClause@29..39
+      FinallyKw@29..36 "finally"
+      Whitespace@36..37 " "
+      Block@37..39
+        LBrace@37..38 "{"
+        RBrace@38..39 "}"
+"#,
+        );
+    }
+
+    mod recovery {
+        use super::*;
+
+        #[test]
+        fn stmts_without_separator() {
+            check(
+                "1 2",
+                r#"SourceFile@0..3
+  Literal@0..1
+    Number@0..1 "1"
+  Whitespace@1..2 " "
+  Literal@2..3
+    Number@2..3 "2"
+error at 2..3: expected ';' or line break after statement, but found number '2'
+"#,
+            );
+        }
+
+        #[test]
+        fn skip_stray_closing_brace() {
+            check(
+                "1\n}\n2",
+                r#"SourceFile@0..5
+  Literal@0..1
+    Number@0..1 "1"
+  Whitespace@1..2 "\n"
+  Error@2..3
+    RBrace@2..3 "}"
+  Whitespace@3..4 "\n"
+  Literal@4..5
+    Number@4..5 "2"
+error at 2..3: expected statement, but found '}'
+"#,
+            );
+        }
+
+        #[test]
+        fn binding_def_without_ident() {
+            check(
+                "let = 1",
+                r#"SourceFile@0..7
+  BindingDef@0..7
+    LetKw@0..3 "let"
+    Whitespace@3..4 " "
+    Equals@4..5 "="
+    Whitespace@5..6 " "
+    Literal@6..7
+      Number@6..7 "1"
+error at 4..5: expected identifier, but found '='
+"#,
+            );
+        }
+
+        #[test]
+        fn binding_def_without_value() {
+            check(
+                "let a =",
+                r#"SourceFile@0..7
+  BindingDef@0..7
+    LetKw@0..3 "let"
+    Whitespace@3..4 " "
+    Ident@4..5 "a"
+    Whitespace@5..6 " "
+    Equals@6..7 "="
+error at 7..7: expected expression, but reached end of input
+"#,
+            );
+        }
+
+        #[test]
+        fn recover_at_next_stmt_in_block() {
+            check(
+                "{ let a = = let b = 1 }",
+                r#"SourceFile@0..23
+  Block@0..23
+    LBrace@0..1 "{"
+    Whitespace@1..2 " "
+    BindingDef@2..11
+      LetKw@2..5 "let"
+      Whitespace@5..6 " "
+      Ident@6..7 "a"
+      Whitespace@7..8 " "
+      Equals@8..9 "="
+      Whitespace@9..10 " "
+      Error@10..11
+        Equals@10..11 "="
+    Whitespace@11..12 " "
+    BindingDef@12..21
+      LetKw@12..15 "let"
+      Whitespace@15..16 " "
+      Ident@16..17 "b"
+      Whitespace@17..18 " "
+      Equals@18..19 "="
+      Whitespace@19..20 " "
+      Literal@20..21
+        Number@20..21 "1"
+    Whitespace@21..22 " "
+    RBrace@22..23 "}"
+error at 10..11: expected expression, but found '='
+"#,
+            );
+        }
+
+        #[test]
+        fn recover_at_block_end() {
+            check(
+                "{ 1 + }",
+                r#"SourceFile@0..7
+  Block@0..7
+    LBrace@0..1 "{"
+    Whitespace@1..2 " "
+    BinaryExpr@2..5
+      Literal@2..3
+        Number@2..3 "1"
+      Whitespace@3..4 " "
+      Plus@4..5 "+"
+    Whitespace@5..6 " "
+    RBrace@6..7 "}"
+error at 6..7: expected expression, but found '}'
+"#,
+            );
+        }
+
+        #[test]
+        fn fn_def_without_param_list() {
+            check(
+                "fn f { 1 }\n2",
+                r#"SourceFile@0..12
+  FnDef@0..10
+    FnKw@0..2 "fn"
+    Whitespace@2..3 " "
+    Ident@3..4 "f"
+    Whitespace@4..5 " "
+    ParamList@5..5
+    Block@5..10
+      LBrace@5..6 "{"
+      Whitespace@6..7 " "
+      Literal@7..8
+        Number@7..8 "1"
+      Whitespace@8..9 " "
+      RBrace@9..10 "}"
+  Whitespace@10..11 "\n"
+  Literal@11..12
+    Number@11..12 "2"
+error at 5..6: expected '(', but found '{'
+"#,
+            );
+        }
+
+        #[test]
+        fn unclosed_block() {
+            check(
+                "{ 1",
+                r#"SourceFile@0..3
+  Block@0..3
+    LBrace@0..1 "{"
+    Whitespace@1..2 " "
+    Literal@2..3
+      Number@2..3 "1"
+error at 3..3: expected '}', but reached end of input
+"#,
+            );
+        }
+
+        #[test]
+        fn unrecognized_token() {
+            check(
+                "{ @ 1 }",
+                r#"SourceFile@0..7
+  Block@0..7
+    LBrace@0..1 "{"
+    Whitespace@1..2 " "
+    Error@2..3
+      Error@2..3 "@"
+    Whitespace@3..4 " "
+    Literal@4..5
+      Number@4..5 "1"
+    Whitespace@5..6 " "
+    RBrace@6..7 "}"
+error at 2..3: expected statement, but found unrecognized token '@'
+"#,
+            );
+        }
+
+        #[test]
+        fn number_out_of_range() {
+            check(
+                "99999999999",
+                r#"SourceFile@0..11
+  Literal@0..11
+    Number@0..11 "99999999999"
+error at 0..11: expected 32-bit integer, but found number '99999999999'
+"#,
+            );
+        }
+
+        #[test]
+        fn binding_def_with_missing_type() {
+            check(
+                "let a: = 1",
+                r#"SourceFile@0..10
+  BindingDef@0..10
+    LetKw@0..3 "let"
+    Whitespace@3..4 " "
+    Ident@4..5 "a"
+    Colon@5..6 ":"
+    Whitespace@6..7 " "
+    Equals@7..8 "="
+    Whitespace@8..9 " "
+    Literal@9..10
+      Number@9..10 "1"
+error at 7..8: expected type, but found '='
+"#,
+            );
+        }
+
+        #[test]
+        fn recover_from_missing_field_type() {
+            check(
+                "struct P { x: , y: Int }",
+                r#"SourceFile@0..24
+  StructDef@0..24
+    StructKw@0..6 "struct"
+    Whitespace@6..7 " "
+    Ident@7..8 "P"
+    Whitespace@8..9 " "
+    FieldDefList@9..24
+      LBrace@9..10 "{"
+      Whitespace@10..11 " "
+      FieldDef@11..13
+        Ident@11..12 "x"
+        Colon@12..13 ":"
+      Whitespace@13..14 " "
+      Comma@14..15 ","
+      Whitespace@15..16 " "
+      FieldDef@16..22
+        Ident@16..17 "y"
+        Colon@17..18 ":"
+        Whitespace@18..19 " "
+        NamedType@19..22
+          Ident@19..22 "Int"
+      Whitespace@22..23 " "
+      RBrace@23..24 "}"
+error at 14..15: expected type, but found ','
+"#,
+            );
+        }
+
+        #[test]
+        fn recover_from_non_fn_in_trait() {
+            check(
+                "trait T { let x = 1 }\nfn f() {}",
+                r#"SourceFile@0..31
+  TraitDef@0..9
+    TraitKw@0..5 "trait"
+    Whitespace@5..6 " "
+    Ident@6..7 "T"
+    Whitespace@7..8 " "
+    TraitItemList@8..9
+      LBrace@8..9 "{"
+  Whitespace@9..10 " "
+  BindingDef@10..19
+    LetKw@10..13 "let"
+    Whitespace@13..14 " "
+    Ident@14..15 "x"
+    Whitespace@15..16 " "
+    Equals@16..17 "="
+    Whitespace@17..18 " "
+    Literal@18..19
+      Number@18..19 "1"
+  Whitespace@19..20 " "
+  Error@20..21
+    RBrace@20..21 "}"
+  Whitespace@21..22 "\n"
+  FnDef@22..31
+    FnKw@22..24 "fn"
+    Whitespace@24..25 " "
+    Ident@25..26 "f"
+    ParamList@26..28
+      LParen@26..27 "("
+      RParen@27..28 ")"
+    Whitespace@28..29 " "
+    Block@29..31
+      LBrace@29..30 "{"
+      RBrace@30..31 "}"
+error at 10..13: expected 'fn', but found 'let'
+error at 20..21: expected ';' or line break after statement, but found '}'
+"#,
+            );
+        }
+
+        #[test]
+        fn recover_from_missing_catch_name() {
+            check(
+                "try {} catch { 1 }",
+                r#"SourceFile@0..18
+  TryCatchExpr@0..18
+    TryKw@0..3 "try"
+    Whitespace@3..4 " "
+    Block@4..6
+      LBrace@4..5 "{"
+      RBrace@5..6 "}"
+    Whitespace@6..7 " "
+    CatchClause@7..18
+      CatchKw@7..12 "catch"
+      Whitespace@12..13 " "
+      Block@13..18
+        LBrace@13..14 "{"
+        Whitespace@14..15 " "
+        Literal@15..16
+          Number@15..16 "1"
+        Whitespace@16..17 " "
+        RBrace@17..18 "}"
+error at 13..14: expected identifier, but found '{'
+"#,
+            );
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::syntax::TextRange;
+
+/// A syntax error, pointing at the token the parser didn't expect (or at the end of the input).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub expected: String,
+    pub found: Option<String>,
+    pub range: TextRange,
+}
+
+impl ParseError {
+    pub(crate) fn new(expected: &str, found: Option<String>, range: TextRange) -> Self {
+        Self {
+            expected: expected.to_string(),
+            found,
+            range,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "error at {}: expected {}", self.range, self.expected)?;
+
+        match &self.found {
+            Some(found) => write!(f, ", but found {}", found),
+            None => write!(f, ", but reached end of input"),
+        }
+    }
+}
@@ -0,0 +1,715 @@
+use super::Parser;
+use crate::{lexer, syntax::SyntaxKind};
+
+pub(super) fn source_file(p: &mut Parser) {
+    // Leading trivia has to end up inside the root, so the node is started without eating it.
+    p.builder.start_node(SyntaxKind::SourceFile);
+
+    stmts(p, None);
+
+    // Picks up trailing trivia so that the tree covers the whole input.
+    p.eat_trivia();
+    p.finish_node();
+}
+
+/// Parses statements until the end of input or the `terminator`, whichever comes first.
+///
+/// Statements have to be separated by either a `;` or a line break. A `;` after the last statement
+/// is allowed, as it is what turns an expression into a statement evaluating to `Unit`.
+fn stmts(p: &mut Parser, terminator: Option<SyntaxKind>) {
+    let at_terminator = |p: &Parser| p.at_end() || terminator.is_some_and(|kind| p.at(kind));
+
+    while !at_terminator(p) {
+        if p.at(SyntaxKind::Semicolon) {
+            p.bump();
+            continue;
+        }
+
+        // Tokens that can't start a statement (e.g. a stray `}`) are skipped one at a time.
+        if !at_stmt_start(p) {
+            p.error("statement");
+            p.error_node();
+            continue;
+        }
+
+        let errors_before = p.errors.len();
+
+        stmt(p);
+
+        // A broken statement would most likely cause a bogus separator error too.
+        if p.errors.len() != errors_before {
+            continue;
+        }
+
+        if p.at(SyntaxKind::Semicolon) {
+            p.bump();
+        } else if !at_terminator(p) && !p.at_line_break() {
+            p.error("';' or line break after statement");
+        }
+    }
+}
+
+fn at_stmt_start(p: &Parser) -> bool {
+    p.at(SyntaxKind::LetKw)
+        || p.at(SyntaxKind::FnKw)
+        || p.at(SyntaxKind::StructKw)
+        || p.at(SyntaxKind::TraitKw)
+        || p.at(SyntaxKind::ImplKw)
+        || at_expr_start(p)
+}
+
+fn stmt(p: &mut Parser) {
+    match p.peek() {
+        Some(SyntaxKind::LetKw) => binding_def(p),
+        Some(SyntaxKind::FnKw) => fn_def(p),
+        Some(SyntaxKind::StructKw) => struct_def(p),
+        Some(SyntaxKind::TraitKw) => trait_def(p),
+        Some(SyntaxKind::ImplKw) => impl_def(p),
+        _ => expr(p),
+    }
+}
+
+fn binding_def(p: &mut Parser) {
+    p.start_node(SyntaxKind::BindingDef);
+    p.bump();
+
+    p.expect(SyntaxKind::Ident);
+    type_annotation(p);
+    p.expect(SyntaxKind::Equals);
+
+    expr(p);
+
+    p.finish_node();
+}
+
+fn fn_def(p: &mut Parser) {
+    p.start_node(SyntaxKind::FnDef);
+    p.bump();
+
+    p.expect(SyntaxKind::Ident);
+
+    if p.at(SyntaxKind::Less) {
+        type_param_list(p);
+    }
+
+    param_list(p);
+
+    if p.at(SyntaxKind::Arrow) {
+        ret_type(p);
+    }
+
+    block_or_error(p);
+
+    p.finish_node();
+}
+
+fn param_list(p: &mut Parser) {
+    p.start_node(SyntaxKind::ParamList);
+
+    if p.at(SyntaxKind::LParen) {
+        p.bump();
+
+        comma_separated(
+            p,
+            SyntaxKind::RParen,
+            "parameter",
+            |p| p.at(SyntaxKind::Ident),
+            param,
+        );
+    } else {
+        p.error(&SyntaxKind::LParen.to_string());
+    }
+
+    p.finish_node();
+}
+
+/// `<T, U>`
+fn type_param_list(p: &mut Parser) {
+    p.start_node(SyntaxKind::TypeParamList);
+    p.bump();
+
+    comma_separated(
+        p,
+        SyntaxKind::Greater,
+        "type parameter",
+        |p| p.at(SyntaxKind::Ident),
+        |p| {
+            p.start_node(SyntaxKind::TypeParam);
+            p.bump();
+            p.finish_node();
+        },
+    );
+
+    p.finish_node();
+}
+
+/// `struct Name<T> { field: T, ... }`
+fn struct_def(p: &mut Parser) {
+    p.start_node(SyntaxKind::StructDef);
+    p.bump();
+
+    p.expect(SyntaxKind::Ident);
+
+    if p.at(SyntaxKind::Less) {
+        type_param_list(p);
+    }
+
+    p.start_node(SyntaxKind::FieldDefList);
+    if p.at(SyntaxKind::LBrace) {
+        p.bump();
+        comma_separated(
+            p,
+            SyntaxKind::RBrace,
+            "field",
+            |p| p.at(SyntaxKind::Ident),
+            field_def,
+        );
+    } else {
+        p.error(&SyntaxKind::LBrace.to_string());
+    }
+    p.finish_node();
+
+    p.finish_node();
+}
+
+fn field_def(p: &mut Parser) {
+    p.start_node(SyntaxKind::FieldDef);
+    p.bump();
+
+    p.expect(SyntaxKind::Colon);
+    ty(p);
+
+    p.finish_node();
+}
+
+/// `trait Name { fn method(self, ...) -> Ret ... }`
+fn trait_def(p: &mut Parser) {
+    p.start_node(SyntaxKind::TraitDef);
+    p.bump();
+
+    p.expect(SyntaxKind::Ident);
+    fn_items(p, SyntaxKind::TraitItemList, fn_sig);
+
+    p.finish_node();
+}
+
+/// A function definition without a body.
+fn fn_sig(p: &mut Parser) {
+    p.start_node(SyntaxKind::FnSig);
+    p.bump();
+
+    p.expect(SyntaxKind::Ident);
+    param_list(p);
+
+    if p.at(SyntaxKind::Arrow) {
+        ret_type(p);
+    }
+
+    p.finish_node();
+}
+
+/// `impl<T> Trait for Type { fn method(self, ...) { ... } ... }`
+fn impl_def(p: &mut Parser) {
+    p.start_node(SyntaxKind::ImplDef);
+    p.bump();
+
+    if p.at(SyntaxKind::Less) {
+        type_param_list(p);
+    }
+
+    p.expect(SyntaxKind::Ident);
+    p.expect(SyntaxKind::ForKw);
+    ty(p);
+
+    fn_items(p, SyntaxKind::ImplItemList, fn_def);
+
+    p.finish_node();
+}
+
+/// Parses a `{ ... }` node of `kind` containing `fn` items, which are separated just like
+/// statements are.
+fn fn_items(p: &mut Parser, kind: SyntaxKind, item: fn(&mut Parser)) {
+    p.start_node(kind);
+
+    if !p.at(SyntaxKind::LBrace) {
+        p.error(&SyntaxKind::LBrace.to_string());
+        p.finish_node();
+        return;
+    }
+
+    p.bump();
+
+    while !p.at(SyntaxKind::RBrace) && !p.at_end() {
+        if p.at(SyntaxKind::Semicolon) {
+            p.bump();
+            continue;
+        }
+
+        if !p.at(SyntaxKind::FnKw) {
+            p.error_and_recover("'fn'");
+
+            if p.at_set(super::RECOVERY_SET) && !p.at(SyntaxKind::FnKw) {
+                break;
+            }
+
+            continue;
+        }
+
+        let errors_before = p.errors.len();
+
+        item(p);
+
+        if p.errors.len() == errors_before
+            && !p.at(SyntaxKind::Semicolon)
+            && !p.at(SyntaxKind::RBrace)
+            && !p.at_line_break()
+        {
+            p.error("';' or line break after item");
+        }
+    }
+
+    p.expect(SyntaxKind::RBrace);
+    p.finish_node();
+}
+
+fn param(p: &mut Parser) {
+    p.start_node(SyntaxKind::Param);
+    p.bump();
+    type_annotation(p);
+    p.finish_node();
+}
+
+fn ret_type(p: &mut Parser) {
+    p.start_node(SyntaxKind::RetType);
+    p.bump();
+    ty(p);
+    p.finish_node();
+}
+
+/// Parses an optional `: type`.
+fn type_annotation(p: &mut Parser) {
+    if p.at(SyntaxKind::Colon) {
+        p.bump();
+        ty(p);
+    }
+}
+
+fn at_type_start(p: &Parser) -> bool {
+    p.at(SyntaxKind::Ident) || p.at(SyntaxKind::FnKw)
+}
+
+fn ty(p: &mut Parser) {
+    if p.enter() {
+        match p.peek() {
+            Some(SyntaxKind::Ident) => {
+                p.start_node(SyntaxKind::NamedType);
+                p.bump();
+
+                if p.at(SyntaxKind::Less) {
+                    p.start_node(SyntaxKind::TypeArgList);
+                    p.bump();
+                    comma_separated(p, SyntaxKind::Greater, "type", at_type_start, ty);
+                    p.finish_node();
+                }
+
+                p.finish_node();
+            }
+            Some(SyntaxKind::FnKw) => fn_type(p),
+            // Nothing is skipped, as a missing type is usually followed by what comes after it.
+            _ => p.error("type"),
+        }
+    }
+
+    p.leave();
+}
+
+/// `fn(params) -> ret`, where the return type can't be left out.
+fn fn_type(p: &mut Parser) {
+    p.start_node(SyntaxKind::FnType);
+    p.bump();
+
+    p.start_node(SyntaxKind::ParamTypeList);
+    if p.at(SyntaxKind::LParen) {
+        p.bump();
+        comma_separated(p, SyntaxKind::RParen, "type", at_type_start, ty);
+    } else {
+        p.error(&SyntaxKind::LParen.to_string());
+    }
+    p.finish_node();
+
+    if p.at(SyntaxKind::Arrow) {
+        ret_type(p);
+    } else {
+        p.error(&SyntaxKind::Arrow.to_string());
+    }
+
+    p.finish_node();
+}
+
+/// Parses `item`s separated by commas (with an optional trailing comma), followed by `close`.
+fn comma_separated(
+    p: &mut Parser,
+    close: SyntaxKind,
+    item_name: &str,
+    at_item: impl Fn(&Parser) -> bool,
+    item: impl Fn(&mut Parser),
+) {
+    while !p.at(close) && !p.at_end() {
+        if at_item(p) {
+            item(p);
+        } else {
+            p.error_and_recover(item_name);
+
+            if p.at_set(super::RECOVERY_SET) || p.at_end() {
+                break;
+            }
+
+            continue;
+        }
+
+        if p.at(SyntaxKind::Comma) {
+            p.bump();
+        } else {
+            break;
+        }
+    }
+
+    p.expect(close);
+}
+
+fn expr(p: &mut Parser) {
+    expr_binding_power(p, 0);
+}
+
+/// Binding power of prefix operators, which bind tighter than any infix operator.
+const PREFIX_BINDING_POWER: u8 = 7;
+
+fn infix_binding_power(kind: SyntaxKind) -> Option<(u8, u8)> {
+    let binding_power = match kind {
+        SyntaxKind::EqualsEquals
+        | SyntaxKind::BangEquals
+        | SyntaxKind::Less
+        | SyntaxKind::LessEquals
+        | SyntaxKind::Greater
+        | SyntaxKind::GreaterEquals => (1, 2),
+        SyntaxKind::Plus | SyntaxKind::Minus => (3, 4),
+        SyntaxKind::Star | SyntaxKind::Slash => (5, 6),
+        _ => return None,
+    };
+
+    Some(binding_power)
+}
+
+fn expr_binding_power(p: &mut Parser, min_binding_power: u8) {
+    // Checked before taking the checkpoint, which would pull the trivia in front of the offending
+    // token into the enclosing node.
+    if !at_expr_start(p) {
+        p.error_and_recover("expression");
+        return;
+    }
+
+    let checkpoint = p.checkpoint();
+    let depth = p.depth;
+
+    // The operand, and every operator applied to it after that, nests it one level deeper.
+    if p.enter() {
+        lhs(p);
+    }
+
+    while at_operator(p, min_binding_power) && p.enter() {
+        if p.at(SyntaxKind::LParen) {
+            p.start_node_at(checkpoint, SyntaxKind::CallExpr);
+            arg_list(p);
+            p.finish_node();
+
+            continue;
+        }
+
+        if p.at(SyntaxKind::LBracket) {
+            p.start_node_at(checkpoint, SyntaxKind::IndexExpr);
+            p.bump();
+            expr(p);
+            p.expect(SyntaxKind::RBracket);
+            p.finish_node();
+
+            continue;
+        }
+
+        if p.at(SyntaxKind::Dot) {
+            p.bump();
+            p.expect(SyntaxKind::Ident);
+
+            if p.at(SyntaxKind::LParen) && !p.at_line_break() {
+                p.start_node_at(checkpoint, SyntaxKind::MethodCallExpr);
+                arg_list(p);
+            } else {
+                p.start_node_at(checkpoint, SyntaxKind::FieldExpr);
+            }
+
+            p.finish_node();
+
+            continue;
+        }
+
+        if p.at(SyntaxKind::Question) {
+            p.start_node_at(checkpoint, SyntaxKind::TryExpr);
+            p.bump();
+            p.finish_node();
+
+            continue;
+        }
+
+        let (_, right_binding_power) = p.peek().and_then(infix_binding_power).unwrap();
+
+        p.start_node_at(checkpoint, SyntaxKind::BinaryExpr);
+        p.bump();
+
+        expr_binding_power(p, right_binding_power);
+
+        p.finish_node();
+    }
+
+    p.depth = depth;
+}
+
+/// Whether the next token applies an operator to the expression before it: a postfix operator,
+/// which binds tighter than anything else, or an infix operator binding at least as tightly as
+/// `min_binding_power`. A `(`, `[` or `-` on the next line starts a new statement instead, just
+/// like a `{` does.
+fn at_operator(p: &Parser, min_binding_power: u8) -> bool {
+    match p.peek() {
+        Some(SyntaxKind::LParen | SyntaxKind::LBracket | SyntaxKind::Minus)
+            if p.at_line_break() =>
+        {
+            false
+        }
+        Some(SyntaxKind::LParen | SyntaxKind::LBracket) => true,
+        Some(SyntaxKind::Dot | SyntaxKind::Question) => true,
+        Some(kind) => infix_binding_power(kind)
+            .is_some_and(|(left_binding_power, _)| left_binding_power >= min_binding_power),
+        None => false,
+    }
+}
+
+fn at_expr_start(p: &Parser) -> bool {
+    matches!(
+        p.peek(),
+        Some(
+            SyntaxKind::Number
+                | SyntaxKind::String
+                | SyntaxKind::TrueKw
+                | SyntaxKind::FalseKw
+                | SyntaxKind::Ident
+                | SyntaxKind::LBrace
+                | SyntaxKind::LParen
+                | SyntaxKind::LBracket
+                | SyntaxKind::Minus
+                | SyntaxKind::Bang
+                | SyntaxKind::IfKw
+                | SyntaxKind::TryKw
+                | SyntaxKind::ThrowKw
+        )
+    )
+}
+
+/// Parses the operand of an expression, which has to be at an expression start.
+fn lhs(p: &mut Parser) {
+    match p.peek() {
+        Some(SyntaxKind::Number) => number(p),
+        Some(SyntaxKind::String) => string(p),
+        Some(SyntaxKind::TrueKw | SyntaxKind::FalseKw) => {
+            p.start_node(SyntaxKind::Literal);
+            p.bump();
+            p.finish_node();
+        }
+        Some(SyntaxKind::Ident) if at_struct_lit(p) => struct_lit(p),
+        Some(SyntaxKind::Ident) => {
+            p.start_node(SyntaxKind::BindingUsage);
+            p.bump();
+            p.finish_node();
+        }
+        Some(SyntaxKind::LBrace) => block(p),
+        Some(SyntaxKind::LParen) => paren_expr(p),
+        Some(SyntaxKind::LBracket) => list_expr(p),
+        Some(SyntaxKind::Minus | SyntaxKind::Bang) => prefix_expr(p),
+        Some(SyntaxKind::IfKw) => if_expr(p),
+        Some(SyntaxKind::TryKw) => try_catch_expr(p),
+        Some(SyntaxKind::ThrowKw) => {
+            p.start_node(SyntaxKind::ThrowExpr);
+            p.bump();
+            expr(p);
+            p.finish_node();
+        }
+        _ => unreachable!("checked by at_expr_start"),
+    }
+}
+
+fn number(p: &mut Parser) {
+    let is_valid = p
+        .peek_token()
+        .is_some_and(|token| token.text.parse::<i32>().is_ok());
+
+    if !is_valid {
+        p.error("32-bit integer");
+    }
+
+    p.start_node(SyntaxKind::Literal);
+    p.bump();
+    p.finish_node();
+}
+
+fn string(p: &mut Parser) {
+    let is_valid = p
+        .peek_token()
+        .is_some_and(|token| lexer::unescape_string(token.text).is_some());
+
+    if !is_valid {
+        p.error("terminated string with valid escape sequences");
+    }
+
+    p.start_node(SyntaxKind::Literal);
+    p.bump();
+    p.finish_node();
+}
+
+/// Whether the next tokens are `Name { field:`, which tells a struct literal apart from a binding
+/// usage followed by a block (as in `if cond { ... }`).
+fn at_struct_lit(p: &Parser) -> bool {
+    p.nth(1) == Some(SyntaxKind::LBrace)
+        && p.nth(2) == Some(SyntaxKind::Ident)
+        && p.nth(3) == Some(SyntaxKind::Colon)
+}
+
+/// `Name { field: expr, ... }`
+fn struct_lit(p: &mut Parser) {
+    p.start_node(SyntaxKind::StructLit);
+    p.bump();
+
+    p.start_node(SyntaxKind::FieldInitList);
+    p.bump();
+    comma_separated(
+        p,
+        SyntaxKind::RBrace,
+        "field",
+        |p| p.at(SyntaxKind::Ident),
+        |p| {
+            p.start_node(SyntaxKind::FieldInit);
+            p.bump();
+            p.expect(SyntaxKind::Colon);
+            expr(p);
+            p.finish_node();
+        },
+    );
+    p.finish_node();
+
+    p.finish_node();
+}
+
+/// `[expr, ...]`
+fn list_expr(p: &mut Parser) {
+    p.start_node(SyntaxKind::ListExpr);
+    p.bump();
+
+    comma_separated(p, SyntaxKind::RBracket, "expression", at_expr_start, expr);
+
+    p.finish_node();
+}
+
+fn paren_expr(p: &mut Parser) {
+    p.start_node(SyntaxKind::ParenExpr);
+    p.bump();
+
+    expr(p);
+
+    p.expect(SyntaxKind::RParen);
+    p.finish_node();
+}
+
+fn prefix_expr(p: &mut Parser) {
+    p.start_node(SyntaxKind::PrefixExpr);
+    p.bump();
+
+    expr_binding_power(p, PREFIX_BINDING_POWER);
+
+    p.finish_node();
+}
+
+fn if_expr(p: &mut Parser) {
+    p.start_node(SyntaxKind::IfExpr);
+    p.bump();
+
+    expr(p);
+    block_or_error(p);
+
+    if p.at(SyntaxKind::ElseKw) {
+        p.bump();
+
+        if p.at(SyntaxKind::IfKw) {
+            if_expr(p);
+        } else {
+            block_or_error(p);
+        }
+    }
+
+    p.finish_node();
+}
+
+/// Either the `catch` or the `finally` clause can be left out, but not both.
+fn try_catch_expr(p: &mut Parser) {
+    p.start_node(SyntaxKind::TryCatchExpr);
+    p.bump();
+
+    block_or_error(p);
+
+    let has_catch = p.at(SyntaxKind::CatchKw);
+
+    if has_catch {
+        p.start_node(SyntaxKind::CatchClause);
+        p.bump();
+        p.expect(SyntaxKind::Ident);
+        block_or_error(p);
+        p.finish_node();
+    }
+
+    if p.at(SyntaxKind::FinallyKw) {
+        p.start_node(SyntaxKind::FinallyClause);
+        p.bump();
+        block_or_error(p);
+        p.finish_node();
+    } else if !has_catch {
+        p.error("'catch' or 'finally'");
+    }
+
+    p.finish_node();
+}
+
+fn arg_list(p: &mut Parser) {
+    p.start_node(SyntaxKind::ArgList);
+    p.bump();
+
+    comma_separated(p, SyntaxKind::RParen, "argument", at_expr_start, expr);
+
+    p.finish_node();
+}
+
+fn block_or_error(p: &mut Parser) {
+    if p.at(SyntaxKind::LBrace) {
+        block(p);
+    } else {
+        p.error_and_recover("block");
+    }
+}
+
+pub(super) fn block(p: &mut Parser) {
+    if p.enter() {
+        p.start_node(SyntaxKind::Block);
+        p.bump();
+
+        stmts(p, Some(SyntaxKind::RBrace));
+
+        p.expect(SyntaxKind::RBrace);
+        p.finish_node();
+    }
+
+    p.leave();
+}
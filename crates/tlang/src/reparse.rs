@@ -0,0 +1,389 @@
+use std::rc::Rc;
+
+use crate::{
+    parser::{self, ParseError},
+    syntax::{GreenElement, GreenNode, SyntaxKind, SyntaxNode, TextRange},
+};
+
+/// A change to the text of an input: the text in `range` is replaced by `new_text`.
+///
+/// Insertions use an empty range and deletions an empty `new_text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: TextRange,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    #[inline]
+    pub fn new(range: TextRange, new_text: impl Into<String>) -> Self {
+        Self {
+            range,
+            new_text: new_text.into(),
+        }
+    }
+
+    /// Returns `text` with the edit applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range of the edit doesn't lie within `text` on character boundaries.
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        text.replace_range(self.range.start..self.range.end, &self.new_text);
+
+        text
+    }
+
+    fn len_delta(&self) -> isize {
+        self.new_text.len() as isize - self.range.len() as isize
+    }
+}
+
+/// Applies `edit` to the tree rooted at `root` by parsing only the innermost block around the
+/// edit again, keeping everything outside that block as it is.
+///
+/// Returns the new root along with the errors of the whole new input, or `None` if no block could
+/// be reparsed on its own, in which case the input has to be parsed from scratch.
+pub(crate) fn reparse_block(
+    root: &SyntaxNode,
+    errors: &[ParseError],
+    edit: &TextEdit,
+) -> Option<(Rc<GreenNode>, Vec<ParseError>)> {
+    // Where the parser gave up, the rest of the input isn't grouped into blocks as it would be
+    // otherwise.
+    if parser::gave_up(errors) {
+        return None;
+    }
+
+    let mut blocks = enclosing_blocks(root, edit.range);
+
+    // Should the innermost block not stay a block on its own, an enclosing one might still do.
+    while let Some(block) = blocks.pop() {
+        let range = block.text_range();
+        let relative_edit = TextEdit::new(
+            TextRange::new(edit.range.start - range.start, edit.range.end - range.start),
+            edit.new_text.as_str(),
+        );
+        let text = relative_edit.apply(&block.to_string());
+        let follows_ident = prev_token_kind(root.green(), range.start) == Some(SyntaxKind::Ident);
+
+        let Some((green, block_errors)) =
+            parser::parse_block(&text, follows_ident, nesting_depth(&block))
+        else {
+            continue;
+        };
+
+        let (before, rest): (Vec<_>, Vec<_>) = errors
+            .iter()
+            .cloned()
+            .partition(|error| error.range.start <= range.start);
+        let (inside, after): (Vec<_>, Vec<_>) = rest
+            .into_iter()
+            .partition(|error| error.range.start < range.end);
+
+        // Statements that contain errors aren't checked for a separator after them, so whether
+        // the block has any errors decides what is parsed after it.
+        if inside.is_empty() != block_errors.is_empty() {
+            continue;
+        }
+
+        let shift = |error: ParseError, by: isize| ParseError {
+            range: TextRange::new(
+                error.range.start.wrapping_add_signed(by),
+                error.range.end.wrapping_add_signed(by),
+            ),
+            ..error
+        };
+
+        let errors = before
+            .into_iter()
+            .chain(
+                block_errors
+                    .into_iter()
+                    .map(|error| shift(error, range.start as isize)),
+            )
+            .chain(
+                after
+                    .into_iter()
+                    .map(|error| shift(error, edit.len_delta())),
+            )
+            .collect();
+
+        return Some((block.replace_with(green), errors));
+    }
+
+    None
+}
+
+/// The blocks whose contents strictly contain `range`, from the outermost to the innermost one.
+fn enclosing_blocks(root: &SyntaxNode, range: TextRange) -> Vec<SyntaxNode> {
+    let mut blocks = Vec::new();
+    let mut node = root.clone();
+
+    loop {
+        let child = node.children().find(|child| {
+            let child_range = child.text_range();
+            child_range.start < range.start && range.end < child_range.end
+        });
+
+        let Some(child) = child else {
+            return blocks;
+        };
+
+        if child.kind() == SyntaxKind::Block {
+            blocks.push(child.clone());
+        }
+
+        node = child;
+    }
+}
+
+/// An upper bound on how deeply the parser is nested when it gets to `block`, in terms of
+/// [`parser::MAX_NESTING`], so that reparsing the block alone stops where parsing everything
+/// would, or earlier.
+///
+/// The parser goes a level deeper for every node it starts around the block, and for every
+/// operator it applied to an operand before getting to the block, which leaves the nodes of those
+/// operators on the left spine of one of the block's ancestors. Blocks count twice, as a block
+/// used as an expression goes one level deeper as an operand, and once more as a block.
+fn nesting_depth(block: &SyntaxNode) -> usize {
+    let weight = |node: &SyntaxNode| match node.kind() {
+        SyntaxKind::Block => 2,
+        _ => 1,
+    };
+    let starts_with =
+        |node: &SyntaxNode, child: &SyntaxNode| child.text_range().start == node.text_range().start;
+
+    let mut depth = weight(block) - 1;
+    let mut node = block.clone();
+
+    while let Some(parent) = node.parent() {
+        depth += weight(&parent);
+
+        let mut spine = parent
+            .children()
+            .next()
+            .filter(|first| *first != node && starts_with(&parent, first));
+
+        while let Some(operand) = spine {
+            depth += weight(&operand);
+            spine = operand
+                .children()
+                .next()
+                .filter(|first| starts_with(&operand, first));
+        }
+
+        node = parent;
+    }
+
+    depth
+}
+
+/// The kind of the last non-trivia token starting before `offset` in `node`.
+fn prev_token_kind(node: &GreenNode, offset: usize) -> Option<SyntaxKind> {
+    let mut start = 0;
+    let mut preceding = Vec::new();
+
+    for child in node.children() {
+        if start >= offset {
+            break;
+        }
+
+        preceding.push((start, child));
+        start += child.text_len();
+    }
+
+    preceding
+        .into_iter()
+        .rev()
+        .find_map(|(start, child)| match child {
+            GreenElement::Token(token) if token.kind().is_trivia() => None,
+            GreenElement::Token(token) => Some(token.kind()),
+            GreenElement::Node(node) => prev_token_kind(node, offset - start),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{parse, Parse};
+
+    /// Parses `input` and an edit replacing the only occurrence of `target` in it with `new_text`.
+    fn edit(input: &str, target: &str, new_text: &str) -> (Parse, TextEdit) {
+        let start = input.find(target).unwrap();
+        assert_eq!(input.rfind(target), Some(start));
+
+        let edit = TextEdit::new(TextRange::at(start, target.len()), new_text);
+
+        (parse(input), edit)
+    }
+
+    fn check(input: &str, target: &str, new_text: &str, reused: bool) -> Parse {
+        let (old, edit) = edit(input, target, new_text);
+
+        assert_eq!(
+            reparse_block(&old.syntax(), old.errors(), &edit).is_some(),
+            reused
+        );
+
+        let new = old.reparse(&edit);
+        assert_eq!(new, parse(&edit.apply(input)));
+
+        new
+    }
+
+    #[test]
+    fn apply_edit() {
+        let edit = TextEdit::new(TextRange::new(4, 5), "bar");
+        assert_eq!(edit.apply("let x = 1"), "let bar = 1");
+    }
+
+    #[test]
+    fn reparse_fn_body() {
+        check("fn f(x) { x + 1 }\nf(2)", "1", "10", true);
+    }
+
+    #[test]
+    fn reparse_innermost_block() {
+        check(
+            "fn f() {\n  if true { 1 } else { 2 }\n}",
+            "2",
+            "3 * 4",
+            true,
+        );
+    }
+
+    #[test]
+    fn reparse_keeps_unchanged_subtrees() {
+        let (old, edit) = edit("fn f() { 1 }\nfn g() { 2 }\nlet x = 3", "1", "42");
+        let new = old.reparse(&edit);
+
+        let old_items: Vec<_> = old.syntax().children().collect();
+        let new_items: Vec<_> = new.syntax().children().collect();
+
+        assert_eq!(
+            new.syntax().to_string(),
+            "fn f() { 42 }\nfn g() { 2 }\nlet x = 3"
+        );
+        assert!(!Rc::ptr_eq(old_items[0].green(), new_items[0].green()));
+        assert!(Rc::ptr_eq(old_items[1].green(), new_items[1].green()));
+        assert!(Rc::ptr_eq(old_items[2].green(), new_items[2].green()));
+    }
+
+    #[test]
+    fn reparse_shifts_errors_after_block() {
+        let new = check("fn f() { 1 }\nlet = 2", "1", "100", true);
+
+        assert_eq!(new.errors().len(), 1);
+        assert_eq!(new.errors()[0].range, TextRange::new(19, 20));
+    }
+
+    #[test]
+    fn reparse_replaces_errors_inside_block() {
+        let input = "fn f() {\n  1 +\n  2 *\n}\nlet = 2";
+
+        let new = check(input, "1 +", "1 + 1", true);
+        assert_eq!(new.errors().len(), 2);
+
+        let new = check(input, "2 *", "2 * 3 -", true);
+        assert_eq!(new.errors().len(), 2);
+        assert_eq!(new.errors()[0].range, TextRange::new(25, 26));
+    }
+
+    #[test]
+    fn reparse_lowers_ast() {
+        let new = check("fn f() { 1 }\nf()", "1", "1 + 1", true);
+        assert!(new.source_file().is_some());
+    }
+
+    /// How many nodes of `new` aren't shared with `old`, comparing the children of nodes that
+    /// aren't in order.
+    fn unshared(old: &SyntaxNode, new: &SyntaxNode) -> usize {
+        if Rc::ptr_eq(old.green(), new.green()) {
+            return 0;
+        }
+
+        let children: usize = old
+            .children()
+            .zip(new.children())
+            .map(|(old, new)| unshared(&old, &new))
+            .sum();
+
+        1 + children
+    }
+
+    #[test]
+    fn reparse_only_rebuilds_path_to_block() {
+        let input: String = (0..500)
+            .map(|i| format!("fn f{i}(x) {{\n  if x > 0 {{ x * {i} }} else {{ 0 }}\n}}\n"))
+            .collect();
+        check(&input, "x * 250", "x * 251", true);
+
+        let (old, edit) = edit(&input, "x * 250", "x * 251");
+        let new = old.reparse(&edit);
+
+        // The source file, the function, its body, the `if` and the reparsed block with the
+        // binary expression and its two operands in it.
+        assert_eq!(unshared(&old.syntax(), &new.syntax()), 8);
+    }
+
+    fn nested(levels: usize, inner: &str) -> String {
+        format!("{}{inner}{}", "{ ".repeat(levels), " }".repeat(levels))
+    }
+
+    #[test]
+    fn reparse_nested_block() {
+        check(&nested(10, "x"), "x", &nested(5, "(x)"), true);
+    }
+
+    #[test]
+    fn nesting_too_deeply_falls_back() {
+        let parens = format!("{}x{}", "(".repeat(40), ")".repeat(40));
+        let new = check(&nested(60, "x"), "x", &parens, false);
+
+        assert!(parser::gave_up(new.errors()));
+    }
+
+    #[test]
+    fn edit_after_nesting_too_deeply_falls_back() {
+        let input = format!("{{ 1 }}\n{}", nested(100, "x"));
+        check(&input, "1", "2", false);
+    }
+
+    #[test]
+    fn edit_touching_brace_falls_back() {
+        check("fn f() { 1 }\nf()", "{", "(", false);
+        check("fn f() { 1 }\nf()", " }", "", false);
+    }
+
+    #[test]
+    fn unbalanced_edit_falls_back() {
+        check("fn f() { 1 }\nlet x = 2", "1", "{ 1", false);
+        check("fn f() { 1 }\nlet x = 2\n}", "1", "1 }", false);
+    }
+
+    #[test]
+    fn swallowed_closing_brace_falls_back() {
+        check("fn f() { 1 }\nf()", "1", "1 # comment", false);
+        check("fn f() { 1 }\nf()", "1", "\"1", false);
+    }
+
+    #[test]
+    fn struct_lit_after_ident_falls_back() {
+        check("if x { y }", "y", "y: 1", false);
+    }
+
+    #[test]
+    fn fixing_or_breaking_statement_falls_back() {
+        check("let x = { 1 + } 2", "1 +", "1 + 2", false);
+        check("let x = { 1 } 2", "1", "1 +", false);
+    }
+
+    #[test]
+    fn top_level_edit_falls_back() {
+        check("let x = 1\nfn f() { 2 }", "1", "5", false);
+        check("let x = 1", "let x = 1", "", false);
+    }
+}
@@ -0,0 +1,412 @@
+//! Name resolution, which finds every use of an undefined binding before any code runs, and
+//! annotates the others with the slot their binding's value is kept in.
+//!
+//! The resolver keeps a tree of scopes that mirrors the environments created during evaluation:
+//! one for each block, one for the parameters of each function call, one for the methods of each
+//! impl and one for the name bound by each `catch` clause. Slots are handed out in the same order
+//! as environments do when bindings are stored in them.
+//!
+//! Bindings have to be defined before the code using them, even within functions, with one
+//! exception: consecutive function definitions can all use each other, which makes mutual
+//! recursion possible. The type checker and both backends follow the same rule.
+//!
+//! ```
+//! use tlang::Env;
+//!
+//! let mut parse = tlang::parse("let length = 1\nfn f() { lenght + 1 }");
+//! let errors = parse.resolve(&Env::default()).unwrap_err();
+//!
+//! assert_eq!(
+//!     errors[0].to_string(),
+//!     "error at 24..30: binding with name 'lenght' does not exist; did you mean 'length'?",
+//! );
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    ast::{
+        walk_binding_def_mut, walk_expr_mut, Arena, BindingDef, BindingUsage, Block, Catch, ExprId,
+        ExprKind, FnDef, ImplDef, Slot, SourceFile, Stmt, StmtId, VisitorMut,
+    },
+    builtins,
+    env::Env,
+    stmt,
+    symbol::Symbol,
+    syntax::TextRange,
+};
+
+/// A use of a binding that doesn't exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveError {
+    pub message: String,
+    /// The name of a binding that does exist and is spelled similarly, if there is one.
+    pub suggestion: Option<String>,
+    pub span: TextRange,
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "error at {}: {}", self.span, self.message)
+    }
+}
+
+/// Resolves every binding usage in `source_file`, where the top-level scope is that of `env`.
+pub(crate) fn resolve(source_file: &mut SourceFile, env: &Env) -> Result<(), Vec<ResolveError>> {
+    let mut resolver = Resolver {
+        scopes: env.slot_names(),
+        errors: Vec::new(),
+    };
+
+    resolver.visit_source_file_mut(source_file);
+
+    if resolver.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(resolver.errors)
+    }
+}
+
+struct Resolver {
+    /// The names defined in each enclosing scope by slot, outermost first.
+    scopes: Vec<Vec<Symbol>>,
+    errors: Vec<ResolveError>,
+}
+
+impl Resolver {
+    fn with_scope(&mut self, names: Vec<Symbol>, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(names);
+        f(self);
+        self.scopes.pop();
+    }
+
+    /// Defining a name again in the same scope gives it a new slot, like the environment does.
+    #[inline]
+    fn define(&mut self, name: Symbol) {
+        self.scopes.last_mut().unwrap().push(name);
+    }
+
+    fn slot(&self, name: Symbol) -> Option<Slot> {
+        self.scopes
+            .iter()
+            .rev()
+            .enumerate()
+            .find_map(|(depth, scope)| {
+                let index = scope.iter().rposition(|defined| *defined == name)?;
+                Some(Slot { depth, index })
+            })
+    }
+
+    /// The closest name to `name` among those in scope, preferring inner scopes, if any is close
+    /// enough to be a likely typo. Names that would have to be changed completely never are.
+    fn suggestion(&self, name: &str) -> Option<String> {
+        let len = name.chars().count();
+        let max_distance = (len / 3).max(1);
+
+        let in_scope = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev().map(|defined| defined.as_str()));
+
+        in_scope
+            .chain(builtins::NAMES)
+            .map(|candidate| (edit_distance(name, candidate), candidate))
+            .filter(|(distance, _)| *distance <= max_distance && *distance < len)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate.to_string())
+    }
+
+    fn binding_usage(&mut self, binding_usage: &mut BindingUsage, span: TextRange) {
+        binding_usage.slot = self.slot(binding_usage.name.clone());
+
+        if binding_usage.slot.is_some() || builtins::NAMES.contains(&binding_usage.name.as_str()) {
+            return;
+        }
+
+        let suggestion = self.suggestion(&binding_usage.name);
+
+        let mut message = format!("binding with name '{}' does not exist", binding_usage.name);
+        if let Some(suggestion) = &suggestion {
+            message.push_str(&format!("; did you mean '{}'?", suggestion));
+        }
+
+        self.errors.push(ResolveError {
+            message,
+            suggestion,
+            span,
+        });
+    }
+
+    /// The functions of a group are all defined before any of their bodies is resolved.
+    fn stmts(&mut self, arena: &mut Arena, stmts: &[StmtId]) {
+        for group in stmt::groups(arena, stmts) {
+            for &stmt in group {
+                if let Stmt::FnDef(fn_def) = &arena[stmt] {
+                    self.define(fn_def.name.clone());
+                }
+            }
+
+            for &stmt in group {
+                self.visit_stmt_mut(arena, stmt);
+            }
+        }
+    }
+
+    /// Calling a function creates a scope for its parameters, in which its body is evaluated.
+    fn function(&mut self, arena: &mut Arena, fn_def: &mut FnDef) {
+        self.with_scope(Vec::new(), |resolver| {
+            for param in &fn_def.params {
+                resolver.define(param.name.clone());
+            }
+
+            resolver.visit_block_mut(arena, &mut fn_def.body)
+        });
+    }
+}
+
+impl VisitorMut for Resolver {
+    fn visit_source_file_mut(&mut self, source_file: &mut SourceFile) {
+        let (stmts, arena) = source_file.parts_mut();
+        self.stmts(arena, stmts);
+    }
+
+    fn visit_block_mut(&mut self, arena: &mut Arena, block: &mut Block) {
+        self.with_scope(Vec::new(), |resolver| resolver.stmts(arena, &block.stmts));
+    }
+
+    /// The value is resolved first, as it can still refer to an earlier binding of the same name.
+    fn visit_binding_def_mut(&mut self, arena: &mut Arena, binding_def: &mut BindingDef) {
+        walk_binding_def_mut(self, arena, binding_def);
+        self.define(binding_def.name.clone());
+    }
+
+    /// The function is already defined along with the rest of its group, as it can call itself.
+    fn visit_fn_def_mut(&mut self, arena: &mut Arena, fn_def: &mut FnDef) {
+        self.function(arena, fn_def);
+    }
+
+    /// The methods of an impl share a scope, which holds the impl's type parameters at runtime.
+    fn visit_impl_def_mut(&mut self, arena: &mut Arena, impl_def: &mut ImplDef) {
+        self.with_scope(Vec::new(), |resolver| {
+            for method in &mut impl_def.methods {
+                resolver.function(arena, method);
+            }
+        });
+    }
+
+    fn visit_catch_mut(&mut self, arena: &mut Arena, catch: &mut Catch) {
+        self.with_scope(vec![catch.name.clone()], |resolver| {
+            resolver.visit_block_mut(arena, &mut catch.body)
+        });
+    }
+
+    fn visit_expr_mut(&mut self, arena: &mut Arena, expr: ExprId) {
+        let span = arena[expr].span;
+
+        match &mut arena[expr].kind {
+            ExprKind::BindingUsage(binding_usage) => self.binding_usage(binding_usage, span),
+            _ => walk_expr_mut(self, arena, expr),
+        }
+    }
+}
+
+/// The number of characters that have to be inserted, removed or replaced, or pairs of adjacent
+/// characters that have to be swapped, to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<_> = a.chars().collect();
+    let b: Vec<_> = b.chars().collect();
+
+    // `distances[i][j]` is the distance between the first `i` characters of `a` and the first
+    // `j` characters of `b`.
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for i in 0..=a.len() {
+        for j in 0..=b.len() {
+            distances[i][j] = if i == 0 || j == 0 {
+                i + j
+            } else {
+                let mut distance = (distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]))
+                    .min(distances[i - 1][j] + 1)
+                    .min(distances[i][j - 1] + 1);
+
+                if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                    distance = distance.min(distances[i - 2][j - 2] + 1);
+                }
+
+                distance
+            };
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ast::Visitor, val::Val};
+
+    /// The name and resolved slot of every binding usage in `s`, in source order.
+    fn slots(s: &str, env: &Env) -> Vec<(String, Option<Slot>)> {
+        struct Slots(Vec<(String, Option<Slot>)>);
+
+        impl Visitor for Slots {
+            fn visit_binding_usage(&mut self, binding_usage: &BindingUsage) {
+                self.0
+                    .push((binding_usage.name.to_string(), binding_usage.slot));
+            }
+        }
+
+        let mut parse = crate::parse(s);
+        parse.resolve(env).unwrap();
+
+        let mut slots = Slots(Vec::new());
+        slots.visit_source_file(parse.source_file().unwrap());
+        slots.0
+    }
+
+    fn errors(s: &str) -> Vec<String> {
+        crate::parse(s)
+            .resolve(&Env::default())
+            .unwrap_err()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn slot(name: &str, depth: usize, index: usize) -> (String, Option<Slot>) {
+        (name.to_string(), Some(Slot { depth, index }))
+    }
+
+    #[test]
+    fn slots_of_usages() {
+        assert_eq!(
+            slots(
+                "let a = 1\nfn f(x) { let y = x; { y + a } }\nf(a)",
+                &Env::default()
+            ),
+            [
+                slot("x", 1, 0),
+                slot("y", 1, 0),
+                slot("a", 3, 0),
+                slot("f", 0, 1),
+                slot("a", 0, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn slots_in_methods_and_catch_clauses() {
+        assert_eq!(
+            slots(
+                "struct P { x: Int }
+trait T { fn t(self) -> Int }
+impl T for P { fn t(self) { try { throw \"a\" } catch e { self.x } } }",
+                &Env::default()
+            ),
+            [slot("self", 3, 0)]
+        );
+    }
+
+    #[test]
+    fn shadowing_bindings_and_builtins() {
+        assert_eq!(
+            slots("let a = 1\n{ let a = a; a }\nSome(a)", &Env::default()),
+            [
+                slot("a", 1, 0),
+                slot("a", 0, 0),
+                ("Some".to_string(), None),
+                slot("a", 0, 0)
+            ]
+        );
+        assert_eq!(
+            slots("let Some = 1\nSome", &Env::default()),
+            [slot("Some", 0, 0)]
+        );
+    }
+
+    #[test]
+    fn bindings_of_env_are_in_scope() {
+        let mut env = Env::default();
+        env.store_binding("unused".into(), Val::Number(1));
+        env.store_binding("outer".into(), Val::Number(2));
+
+        assert_eq!(slots("{ outer }", &env), [slot("outer", 1, 1)]);
+    }
+
+    #[test]
+    fn report_all_undefined_bindings() {
+        assert_eq!(
+            errors("let a = b + 1\n{ let c = 2 }\nc(d)"),
+            [
+                "error at 8..9: binding with name 'b' does not exist",
+                "error at 28..29: binding with name 'c' does not exist",
+                "error at 30..31: binding with name 'd' does not exist",
+            ]
+        );
+    }
+
+    #[test]
+    fn bindings_have_to_be_defined_before_use() {
+        assert_eq!(
+            errors("{ fn f() { b }\n let b = 7\n f() }"),
+            ["error at 11..12: binding with name 'b' does not exist"]
+        );
+        assert_eq!(
+            errors("fn f() { g() }\nlet x = 1\nfn g() { x }"),
+            ["error at 9..10: binding with name 'g' does not exist"]
+        );
+    }
+
+    #[test]
+    fn consecutive_functions_can_use_each_other() {
+        assert_eq!(
+            slots(
+                "fn even(n) { odd(n) }\nfn odd(n) { even(n) }",
+                &Env::default()
+            ),
+            [
+                slot("odd", 2, 1),
+                slot("n", 1, 0),
+                slot("even", 2, 0),
+                slot("n", 1, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn suggest_similar_names() {
+        let mut parse = crate::parse("fn count(items) { let total = 0; totl + itmes + unwrap_ro }");
+
+        let errors = parse.resolve(&Env::default()).unwrap_err();
+        let suggestions: Vec<_> = errors
+            .iter()
+            .map(|error| error.suggestion.as_deref())
+            .collect();
+
+        assert_eq!(
+            suggestions,
+            [Some("total"), Some("items"), Some("unwrap_or")]
+        );
+    }
+
+    #[test]
+    fn no_suggestion_for_dissimilar_names() {
+        assert_eq!(
+            errors("let apple = 1\npear"),
+            ["error at 14..18: binding with name 'pear' does not exist"]
+        );
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("lenght", "length"), 1);
+        assert_eq!(edit_distance("ab", "ba"), 1);
+        assert_eq!(edit_distance("total", "totl"), 1);
+    }
+}
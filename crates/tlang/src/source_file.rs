@@ -0,0 +1,182 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::{Arena, StmtId},
+    env::Env,
+    stmt::Stmt,
+    syntax::SyntaxNode,
+    unwind::{EvalError, Unwind},
+    val::Val,
+};
+
+/// The root of the AST: all top-level statements of the input, and the arena every node of the
+/// AST is in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    pub stmts: Vec<StmtId>,
+    /// Shared with the functions defined by evaluating the source file, which need their bodies
+    /// for as long as they are around.
+    arena: Rc<Arena>,
+}
+
+impl SourceFile {
+    #[inline]
+    pub fn new(arena: Arena, stmts: Vec<StmtId>) -> Self {
+        Self {
+            stmts,
+            arena: Rc::new(arena),
+        }
+    }
+
+    pub(crate) fn lower(node: &SyntaxNode) -> Option<Self> {
+        let mut arena = Arena::default();
+        let stmts = Stmt::lower_all(&mut arena, node)?;
+
+        Some(Self::new(arena, stmts))
+    }
+
+    #[inline]
+    pub fn arena(&self) -> &Arena {
+        &self.arena
+    }
+
+    /// Mutable access to the arena, which copies it first if functions defined by evaluating the
+    /// source file still use it, so that they aren't affected by changes to the AST.
+    #[inline]
+    pub fn arena_mut(&mut self) -> &mut Arena {
+        Rc::make_mut(&mut self.arena)
+    }
+
+    /// The statements and the arena together, for changes to both at once.
+    #[inline]
+    pub fn parts_mut(&mut self) -> (&mut Vec<StmtId>, &mut Arena) {
+        (&mut self.stmts, Rc::make_mut(&mut self.arena))
+    }
+
+    /// Evaluates every statement in order directly in `env`, so that bindings stay around after
+    /// the source file has been evaluated. The value of the last statement is returned.
+    pub(crate) fn eval(&self, env: &mut Env) -> Result<Val, EvalError> {
+        let mut result = Val::Unit;
+
+        for &stmt in &self.stmts {
+            result = self.arena[stmt]
+                .eval(&self.arena, env)
+                .map_err(Unwind::into_error)?;
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        ast::ExprId,
+        binding_def::BindingDef,
+        expr::{Expr, ExprKind, Number, Op},
+        syntax::TextRange,
+    };
+
+    fn number(arena: &mut Arena, n: i32, start: usize, end: usize) -> ExprId {
+        arena.alloc_expr(Expr::new(
+            ExprKind::Number(Number(n)),
+            TextRange::new(start, end),
+        ))
+    }
+
+    #[test]
+    fn parse_multiple_stmts() {
+        let mut arena = Arena::default();
+        let val = number(&mut arena, 1, 8, 9);
+        let a = arena.alloc_stmt(Stmt::BindingDef(BindingDef {
+            name: "a".into(),
+            ty: None,
+            val,
+            span: TextRange::new(0, 9),
+        }));
+        let val = number(&mut arena, 2, 18, 19);
+        let b = arena.alloc_stmt(Stmt::BindingDef(BindingDef {
+            name: "b".into(),
+            ty: None,
+            val,
+            span: TextRange::new(10, 19),
+        }));
+        let lhs = number(&mut arena, 3, 21, 22);
+        let rhs = number(&mut arena, 4, 25, 26);
+        let mul = arena.alloc_expr(Expr::new(
+            ExprKind::Operation {
+                lhs,
+                rhs,
+                op: Op::Mul,
+            },
+            TextRange::new(21, 26),
+        ));
+        let mul = arena.alloc_stmt(Stmt::Expr(mul));
+
+        assert_eq!(
+            crate::parse("let a = 1\nlet b = 2; 3 * 4").source_file,
+            Some(SourceFile::new(arena, vec![a, b, mul])),
+        );
+    }
+
+    #[test]
+    fn eval_empty_source_file() {
+        assert_eq!(
+            SourceFile::new(Arena::default(), Vec::new()).eval(&mut Env::default()),
+            Ok(Val::Unit),
+        );
+    }
+
+    #[test]
+    fn eval_returns_value_of_last_stmt() {
+        let mut arena = Arena::default();
+        let stmts = [1, 2]
+            .into_iter()
+            .map(|n| {
+                let expr = arena.alloc_expr(ExprKind::Number(Number(n)));
+                arena.alloc_stmt(Stmt::Expr(expr))
+            })
+            .collect();
+
+        assert_eq!(
+            SourceFile::new(arena, stmts).eval(&mut Env::default()),
+            Ok(Val::Number(2)),
+        );
+    }
+
+    #[test]
+    fn eval_with_trailing_semicolon() {
+        assert_eq!(
+            crate::parse("1 + 2;").eval(&mut Env::default()),
+            Ok(Val::Unit),
+        );
+    }
+
+    #[test]
+    fn eval_keeps_bindings_in_env() {
+        let mut env = Env::default();
+
+        crate::parse("let a = 1\nlet b = 2").eval(&mut env).unwrap();
+
+        assert_eq!(env.get_binding_value("a".into()), Ok(Val::Number(1)));
+        assert_eq!(env.get_binding_value("b".into()), Ok(Val::Number(2)));
+    }
+
+    #[test]
+    fn functions_keep_their_bodies_when_the_ast_changes() {
+        let mut env = Env::default();
+        let mut parse = crate::parse("fn f() { 1 }");
+        parse.eval(&mut env).unwrap();
+
+        let source_file = parse.source_file_mut().unwrap();
+        let arena = source_file.arena_mut();
+        let ids: Vec<_> = arena.exprs().map(|(id, _)| id).collect();
+        for id in ids {
+            arena[id].kind = ExprKind::Number(Number(2));
+        }
+
+        assert_eq!(crate::parse("f()").eval(&mut env), Ok(Val::Number(1)));
+    }
+}
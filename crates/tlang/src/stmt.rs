@@ -1,89 +1,278 @@
-use crate::{binding_def::BindingDef, env::Env, expr::Expr, val::Val};
+use std::rc::Rc;
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) enum Stmt {
+use crate::{
+    ast::{Arena, ExprId, StmtId},
+    binding_def::BindingDef,
+    env::Env,
+    expr::Expr,
+    fn_def::FnDef,
+    impl_def::ImplDef,
+    struct_def::StructDef,
+    syntax::{SyntaxElement, SyntaxKind, SyntaxNode, TextRange},
+    trait_def::TraitDef,
+    unwind::Unwind,
+    val::Val,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Stmt {
     BindingDef(BindingDef),
-    Expr(Expr),
+    FnDef(FnDef),
+    StructDef(StructDef),
+    TraitDef(TraitDef),
+    ImplDef(ImplDef),
+    /// An expression whose value becomes the value of the statement.
+    Expr(ExprId),
+    /// An expression followed by `;`, which discards its value.
+    Semi(ExprId),
 }
 
 impl Stmt {
-    pub(crate) fn new(s: &str) -> Result<(&str, Self), String> {
-        BindingDef::new(s)
-            .map(|(s, binding_def)| (s, Self::BindingDef(binding_def)))
-            .or_else(|_| Expr::new(s).map(|(s, expr)| (s, Self::Expr(expr))))
+    /// Lowers `node` and its children into `arena`, returning the ID of the statement.
+    pub(crate) fn lower(arena: &mut Arena, node: &SyntaxNode) -> Option<StmtId> {
+        let stmt = match node.kind() {
+            SyntaxKind::BindingDef => Self::BindingDef(BindingDef::lower(arena, node)?),
+            SyntaxKind::FnDef => Self::FnDef(FnDef::lower(arena, node)?),
+            SyntaxKind::StructDef => Self::StructDef(StructDef::lower(node)?),
+            SyntaxKind::TraitDef => Self::TraitDef(TraitDef::lower(node)?),
+            SyntaxKind::ImplDef => Self::ImplDef(ImplDef::lower(arena, node)?),
+            _ => Self::Expr(Expr::lower(arena, node)?),
+        };
+
+        Some(arena.alloc_stmt(stmt))
+    }
+
+    /// Lowers all statements that are children of `node`, turning expressions followed by `;`
+    /// into [`Stmt::Semi`].
+    pub(crate) fn lower_all(arena: &mut Arena, node: &SyntaxNode) -> Option<Vec<StmtId>> {
+        let mut stmts = Vec::new();
+        let mut last_is_terminated = true;
+
+        for child in node.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => {
+                    stmts.push(Self::lower(arena, &node)?);
+                    last_is_terminated = false;
+                }
+                SyntaxElement::Token(token) if token.kind() == SyntaxKind::Semicolon => {
+                    if let Some(&last) = stmts.last().filter(|_| !last_is_terminated) {
+                        if let Stmt::Expr(expr) = arena[last] {
+                            arena[last] = Stmt::Semi(expr);
+                        }
+                    }
+
+                    last_is_terminated = true;
+                }
+                SyntaxElement::Token(_) => {}
+            }
+        }
+
+        Some(stmts)
+    }
+
+    /// The range of the input the statement was lowered from, not including a trailing `;`.
+    pub fn span(&self, arena: &Arena) -> TextRange {
+        match self {
+            Stmt::BindingDef(binding_def) => binding_def.span,
+            Stmt::FnDef(fn_def) => fn_def.span,
+            Stmt::StructDef(struct_def) => struct_def.span,
+            Stmt::TraitDef(trait_def) => trait_def.span,
+            Stmt::ImplDef(impl_def) => impl_def.span,
+            Stmt::Expr(expr) | Stmt::Semi(expr) => arena[*expr].span,
+        }
+    }
+
+    /// Errors that haven't been pinned to a part of the input yet are pinned to this statement.
+    pub(crate) fn eval(&self, arena: &Rc<Arena>, env: &mut Env) -> Result<Val, Unwind> {
+        self.eval_kind(arena, env)
+            .map_err(|unwind| unwind.located(self.span(arena)))
     }
 
-    pub(crate) fn eval(&self, env: &mut Env) -> Result<Val, String> {
+    fn eval_kind(&self, arena: &Rc<Arena>, env: &mut Env) -> Result<Val, Unwind> {
         match self {
             Stmt::BindingDef(binding_def) => {
-                binding_def.eval(env)?;
+                binding_def.eval(arena, env)?;
+                Ok(Val::Unit)
+            }
+            Stmt::FnDef(fn_def) => {
+                fn_def.eval(arena, env);
+                Ok(Val::Unit)
+            }
+            Stmt::StructDef(struct_def) => {
+                struct_def.eval(env);
+                Ok(Val::Unit)
+            }
+            Stmt::TraitDef(trait_def) => {
+                trait_def.eval(env)?;
+                Ok(Val::Unit)
+            }
+            Stmt::ImplDef(impl_def) => {
+                impl_def.eval(arena, env)?;
+                Ok(Val::Unit)
+            }
+            Stmt::Expr(expr) => arena[*expr].eval(arena, env),
+            Stmt::Semi(expr) => {
+                arena[*expr].eval(arena, env)?;
                 Ok(Val::Unit)
             }
-            Stmt::Expr(expr) => expr.eval(env),
         }
     }
 }
 
+/// Splits `stmts` into groups of consecutive function definitions and single other statements, in
+/// order. The functions of a group can call each other, as none of them can be called before all
+/// of them are defined.
+pub(crate) fn groups<'a>(arena: &Arena, stmts: &'a [StmtId]) -> Vec<&'a [StmtId]> {
+    stmts
+        .chunk_by(|&a, &b| matches!(arena[a], Stmt::FnDef(_)) && matches!(arena[b], Stmt::FnDef(_)))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::expr::{Number, Op};
+    use crate::{
+        ast::ExprId,
+        expr::{ExprKind, Number, Op},
+    };
+
+    fn number(arena: &mut Arena, n: i32, start: usize, end: usize) -> ExprId {
+        arena.alloc_expr(Expr::new(
+            ExprKind::Number(Number(n)),
+            TextRange::new(start, end),
+        ))
+    }
 
     mod parse {
         use super::*;
 
         #[test]
         fn parse_binding_def() {
-            assert_eq!(
-                Stmt::new("let a = 10"),
-                Ok((
-                    "",
-                    Stmt::BindingDef(BindingDef {
-                        name: "a".to_string(),
-                        val: Expr::Number(Number(10)),
-                    }),
-                )),
-            );
+            let mut arena = Arena::default();
+            let val = number(&mut arena, 10, 8, 10);
+            let stmt = arena.alloc_stmt(Stmt::BindingDef(BindingDef {
+                name: "a".into(),
+                ty: None,
+                val,
+                span: TextRange::new(0, 10),
+            }));
+
+            assert_eq!(crate::parse_stmt("let a = 10"), Ok((arena, stmt)));
         }
 
         #[test]
         fn parse_expr() {
-            assert_eq!(
-                Stmt::new("1+1"),
-                Ok((
-                    "",
-                    Stmt::Expr(Expr::Operation {
-                        lhs: Number(1),
-                        rhs: Number(1),
-                        op: Op::Add,
-                    }),
-                )),
-            );
+            let mut arena = Arena::default();
+            let lhs = number(&mut arena, 1, 0, 1);
+            let rhs = number(&mut arena, 1, 2, 3);
+            let expr = arena.alloc_expr(Expr::new(
+                ExprKind::Operation {
+                    lhs,
+                    rhs,
+                    op: Op::Add,
+                },
+                TextRange::new(0, 3),
+            ));
+            let stmt = arena.alloc_stmt(Stmt::Expr(expr));
+
+            assert_eq!(crate::parse_stmt("1+1"), Ok((arena, stmt)));
         }
     }
 
+    mod lower_all {
+        use super::*;
+
+        fn lower_all(s: &str) -> Option<(Arena, Vec<StmtId>)> {
+            let mut arena = Arena::default();
+            let stmts = Stmt::lower_all(&mut arena, &crate::parse(s).syntax())?;
+
+            Some((arena, stmts))
+        }
+
+        #[test]
+        fn expr_followed_by_semicolon() {
+            let mut arena = Arena::default();
+            let one = number(&mut arena, 1, 0, 1);
+            let one = arena.alloc_stmt(Stmt::Semi(one));
+            let two = number(&mut arena, 2, 3, 4);
+            let two = arena.alloc_stmt(Stmt::Expr(two));
+
+            assert_eq!(lower_all("1; 2"), Some((arena, vec![one, two])));
+        }
+
+        #[test]
+        fn trailing_semicolon() {
+            let mut arena = Arena::default();
+            let val = number(&mut arena, 1, 8, 9);
+            let a = arena.alloc_stmt(Stmt::BindingDef(BindingDef {
+                name: "a".into(),
+                ty: None,
+                val,
+                span: TextRange::new(0, 9),
+            }));
+            let two = number(&mut arena, 2, 11, 12);
+            let two = arena.alloc_stmt(Stmt::Semi(two));
+
+            assert_eq!(lower_all("let a = 1;\n2;"), Some((arena, vec![a, two])));
+        }
+
+        #[test]
+        fn extra_semicolons_are_ignored() {
+            let mut arena = Arena::default();
+            let one = number(&mut arena, 1, 3, 4);
+            let one = arena.alloc_stmt(Stmt::Semi(one));
+
+            assert_eq!(lower_all(";; 1;;"), Some((arena, vec![one])));
+        }
+    }
+
+    #[test]
+    fn span_does_not_include_semicolon() {
+        assert_eq!(
+            crate::parse_stmt("  1 + 2 ;").map(|(arena, stmt)| arena[stmt].span(&arena)),
+            Ok(TextRange::new(2, 7)),
+        );
+    }
+
     mod eval {
         use super::*;
 
+        fn eval(arena: Arena, stmt: StmtId) -> Result<Val, Unwind> {
+            arena[stmt].eval(&Rc::new(arena.clone()), &mut Env::default())
+        }
+
         #[test]
         fn eval_binding_def() {
-            assert_eq!(
-                Stmt::BindingDef(BindingDef {
-                    name: "whatever".to_string(),
-                    val: Expr::Number(Number(-10)),
-                })
-                .eval(&mut Env::default()),
-                Ok(Val::Unit),
-            );
+            let mut arena = Arena::default();
+            let val = arena.alloc_expr(ExprKind::Number(Number(-10)));
+            let stmt = arena.alloc_stmt(Stmt::BindingDef(BindingDef {
+                name: "whatever".into(),
+                ty: None,
+                val,
+                span: TextRange::default(),
+            }));
+
+            assert_eq!(eval(arena, stmt), Ok(Val::Unit));
+        }
+
+        #[test]
+        fn eval_semi() {
+            let mut arena = Arena::default();
+            let five = arena.alloc_expr(ExprKind::Number(Number(5)));
+            let stmt = arena.alloc_stmt(Stmt::Semi(five));
+
+            assert_eq!(eval(arena, stmt), Ok(Val::Unit));
         }
 
         #[test]
         fn eval_expr() {
-            assert_eq!(
-                Stmt::Expr(Expr::Number(Number(5))).eval(&mut Env::default()),
-                Ok(Val::Number(5)),
-            );
+            let mut arena = Arena::default();
+            let five = arena.alloc_expr(ExprKind::Number(Number(5)));
+            let stmt = arena.alloc_stmt(Stmt::Expr(five));
+
+            assert_eq!(eval(arena, stmt), Ok(Val::Number(5)));
         }
     }
 }
@@ -0,0 +1,112 @@
+use std::rc::Rc;
+
+use crate::{
+    env::Env,
+    symbol::Symbol,
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    type_expr::{TypeExpr, TypeParam},
+};
+
+/// `struct Name<type_params> { fields }`, where the type parameters are optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructDef {
+    pub name: Symbol,
+    pub type_params: Vec<TypeParam>,
+    pub fields: Vec<FieldDef>,
+    pub span: TextRange,
+}
+
+/// `name: ty` in a [`StructDef`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDef {
+    pub name: Symbol,
+    pub ty: TypeExpr,
+    pub span: TextRange,
+}
+
+impl StructDef {
+    pub(crate) fn lower(node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        let fields = node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::FieldDefList)?;
+
+        let fields = fields
+            .children()
+            .map(|field| FieldDef::lower(&field))
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            type_params: TypeParam::lower_list(node),
+            fields,
+            span: node.text_range(),
+        })
+    }
+
+    pub(crate) fn eval(&self, env: &mut Env) {
+        env.store_struct(Rc::new(self.clone()));
+    }
+}
+
+impl FieldDef {
+    fn lower(node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        let ty = node.children().find(|child| child.kind().is_type())?;
+
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            ty: TypeExpr::lower(&ty)?,
+            span: node.text_range(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{stmt::Stmt, type_expr::TypeExprKind};
+
+    #[test]
+    fn parse_struct_def() {
+        assert_eq!(
+            crate::parse_stmt("struct P<T> { x: T, }").map(|(arena, stmt)| arena[stmt].clone()),
+            Ok(Stmt::StructDef(StructDef {
+                name: "P".into(),
+                type_params: vec![TypeParam {
+                    name: "T".into(),
+                    span: TextRange::new(9, 10),
+                }],
+                fields: vec![FieldDef {
+                    name: "x".into(),
+                    ty: TypeExpr::new(
+                        TypeExprKind::Named {
+                            name: "T".into(),
+                            args: Vec::new(),
+                        },
+                        TextRange::new(17, 18)
+                    ),
+                    span: TextRange::new(14, 18),
+                }],
+                span: TextRange::new(0, 21),
+            }))
+        );
+    }
+
+    #[test]
+    fn cannot_parse_field_without_type() {
+        assert_eq!(
+            crate::parse_stmt("struct P { x }"),
+            Err(vec![
+                "error at 13..14: expected ':', but found '}'".to_string()
+            ])
+        );
+    }
+}
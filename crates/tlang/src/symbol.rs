@@ -0,0 +1,212 @@
+//! Interned identifiers.
+//!
+//! Every name in the AST and in an [`Env`](crate::Env) is a [`Symbol`]: a handle to a single
+//! shared copy of the name's text, so that a name is only allocated once while it's in use and
+//! comparing names is a pointer comparison. The text is freed along with the last symbol for it.
+//!
+//! ```
+//! use tlang::Symbol;
+//!
+//! let a = Symbol::intern("total");
+//! let b = Symbol::intern(&String::from("total"));
+//!
+//! assert_eq!(a, b);
+//! assert_eq!(a, "total");
+//! assert_ne!(a, Symbol::intern("count"));
+//! ```
+
+use std::{
+    collections::HashSet,
+    fmt::{self, Debug, Display, Formatter},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    ptr,
+    sync::{Arc, LazyLock, Mutex, MutexGuard},
+};
+
+/// An interned name. Symbols are equal only if they are the same name, and are ordered by their
+/// text.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+/// Declares symbols for names the interpreter itself looks up, which are interned once and kept
+/// around, so that looking them up doesn't have to go through the interner.
+macro_rules! predefined {
+    ($($name:ident = $text:literal,)*) => {
+        $(pub(crate) static $name: LazyLock<Symbol> = LazyLock::new(|| Symbol::intern($text));)*
+    };
+}
+
+predefined! {
+    INT = "Int",
+    BOOL = "Bool",
+    STR = "Str",
+    FUNCTION = "function",
+    LIST = "List",
+    OPTION = "Option",
+    RESULT = "Result",
+    UNIT = "Unit",
+    SELF_TYPE = "Self",
+    ADD = "add",
+    SUB = "sub",
+    MUL = "mul",
+    DIV = "div",
+    EQ = "eq",
+    CMP = "cmp",
+}
+
+/// The text of every name that is in use. A name is only kept around while there are symbols for
+/// it, so that a long-running host doesn't hold on to every name it has ever seen.
+fn interner() -> MutexGuard<'static, HashSet<Arc<str>>> {
+    static INTERNER: LazyLock<Mutex<HashSet<Arc<str>>>> = LazyLock::new(Mutex::default);
+
+    INTERNER
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Symbol {
+    pub fn intern(name: &str) -> Self {
+        let mut interner = interner();
+
+        match interner.get(name) {
+            Some(interned) => Self(Arc::clone(interned)),
+            None => {
+                let interned: Arc<str> = name.into();
+                interner.insert(Arc::clone(&interned));
+                Self(interned)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The interner holds on to one reference, so a count of two means this is the last symbol for
+/// the name. No other can be created meanwhile, as that takes the interner's lock.
+impl Drop for Symbol {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.0) == 2 {
+            let mut interner = interner();
+
+            if Arc::strong_count(&self.0) == 2 {
+                interner.remove(&*self.0);
+            }
+        }
+    }
+}
+
+impl PartialEq for Symbol {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(Arc::as_ptr(&self.0), state);
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Self::intern(name)
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_name_same_symbol() {
+        let a = Symbol::intern("a");
+        let other_a = Symbol::intern(&['a'].iter().collect::<String>());
+
+        assert_eq!(a, other_a);
+        assert!(ptr::eq(a.as_str(), other_a.as_str()));
+        assert_ne!(a, Symbol::intern("b"));
+    }
+
+    #[test]
+    fn unused_names_are_released() {
+        let name = "unused_names_are_released";
+        let symbol = Symbol::intern(name);
+        let other = symbol.clone();
+        drop(symbol);
+        assert!(interner().contains(name));
+
+        drop(other);
+        assert!(!interner().contains(name));
+    }
+
+    #[test]
+    fn predefined_symbols_are_interned() {
+        assert_eq!(Symbol::intern("Int"), *INT);
+        assert_eq!(Symbol::intern("cmp"), *CMP);
+    }
+
+    #[test]
+    fn ordered_by_text() {
+        let mut symbols = [
+            Symbol::intern("c"),
+            Symbol::intern("a"),
+            Symbol::intern("b"),
+        ];
+        symbols.sort();
+
+        assert_eq!(symbols, ["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt::{self, Display, Formatter};
+
+pub(crate) use green::{Checkpoint, GreenNodeBuilder};
+pub use green::{GreenElement, GreenNode, GreenToken};
+pub use red::{SyntaxElement, SyntaxNode, SyntaxToken};
+
+mod green;
+mod red;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    Whitespace,
+    Comment,
+    LetKw,
+    FnKw,
+    StructKw,
+    TraitKw,
+    ImplKw,
+    ForKw,
+    IfKw,
+    ElseKw,
+    TryKw,
+    CatchKw,
+    FinallyKw,
+    ThrowKw,
+    TrueKw,
+    FalseKw,
+    Ident,
+    Number,
+    String,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Bang,
+    Equals,
+    EqualsEquals,
+    BangEquals,
+    Less,
+    LessEquals,
+    Greater,
+    GreaterEquals,
+    Comma,
+    Dot,
+    Question,
+    Colon,
+    Arrow,
+    Semicolon,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Error,
+
+    SourceFile,
+    BindingDef,
+    FnDef,
+    ParamList,
+    Param,
+    TypeParamList,
+    TypeParam,
+    StructDef,
+    FieldDefList,
+    FieldDef,
+    TraitDef,
+    TraitItemList,
+    FnSig,
+    ImplDef,
+    ImplItemList,
+    BinaryExpr,
+    PrefixExpr,
+    ParenExpr,
+    Literal,
+    BindingUsage,
+    Block,
+    IfExpr,
+    CallExpr,
+    ArgList,
+    ListExpr,
+    IndexExpr,
+    FieldExpr,
+    MethodCallExpr,
+    TryExpr,
+    ThrowExpr,
+    TryCatchExpr,
+    CatchClause,
+    FinallyClause,
+    StructLit,
+    FieldInitList,
+    FieldInit,
+    RetType,
+    NamedType,
+    FnType,
+    ParamTypeList,
+    TypeArgList,
+}
+
+impl SyntaxKind {
+    #[inline]
+    pub fn is_trivia(self) -> bool {
+        matches!(self, Self::Whitespace | Self::Comment)
+    }
+
+    /// Whether this is the kind of a node representing a type (as opposed to e.g. an expression).
+    #[inline]
+    pub fn is_type(self) -> bool {
+        matches!(self, Self::NamedType | Self::FnType)
+    }
+}
+
+impl Display for SyntaxKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SyntaxKind::Whitespace => "whitespace",
+            SyntaxKind::Comment => "comment",
+            SyntaxKind::LetKw => "'let'",
+            SyntaxKind::FnKw => "'fn'",
+            SyntaxKind::StructKw => "'struct'",
+            SyntaxKind::TraitKw => "'trait'",
+            SyntaxKind::ImplKw => "'impl'",
+            SyntaxKind::ForKw => "'for'",
+            SyntaxKind::IfKw => "'if'",
+            SyntaxKind::ElseKw => "'else'",
+            SyntaxKind::TryKw => "'try'",
+            SyntaxKind::CatchKw => "'catch'",
+            SyntaxKind::FinallyKw => "'finally'",
+            SyntaxKind::ThrowKw => "'throw'",
+            SyntaxKind::TrueKw => "'true'",
+            SyntaxKind::FalseKw => "'false'",
+            SyntaxKind::Ident => "identifier",
+            SyntaxKind::Number => "number",
+            SyntaxKind::String => "string",
+            SyntaxKind::Plus => "'+'",
+            SyntaxKind::Minus => "'-'",
+            SyntaxKind::Star => "'*'",
+            SyntaxKind::Slash => "'/'",
+            SyntaxKind::Bang => "'!'",
+            SyntaxKind::Equals => "'='",
+            SyntaxKind::EqualsEquals => "'=='",
+            SyntaxKind::BangEquals => "'!='",
+            SyntaxKind::Less => "'<'",
+            SyntaxKind::LessEquals => "'<='",
+            SyntaxKind::Greater => "'>'",
+            SyntaxKind::GreaterEquals => "'>='",
+            SyntaxKind::Comma => "','",
+            SyntaxKind::Dot => "'.'",
+            SyntaxKind::Question => "'?'",
+            SyntaxKind::Colon => "':'",
+            SyntaxKind::Arrow => "'->'",
+            SyntaxKind::Semicolon => "';'",
+            SyntaxKind::LParen => "'('",
+            SyntaxKind::RParen => "')'",
+            SyntaxKind::LBrace => "'{'",
+            SyntaxKind::RBrace => "'}'",
+            SyntaxKind::LBracket => "'['",
+            SyntaxKind::RBracket => "']'",
+            SyntaxKind::Error => "unrecognized token",
+            SyntaxKind::SourceFile => "source file",
+            SyntaxKind::BindingDef => "binding definition",
+            SyntaxKind::FnDef => "function definition",
+            SyntaxKind::ParamList => "parameter list",
+            SyntaxKind::Param => "parameter",
+            SyntaxKind::TypeParamList => "type parameter list",
+            SyntaxKind::TypeParam => "type parameter",
+            SyntaxKind::StructDef => "struct definition",
+            SyntaxKind::FieldDefList => "field list",
+            SyntaxKind::FieldDef => "field definition",
+            SyntaxKind::TraitDef => "trait definition",
+            SyntaxKind::TraitItemList => "trait item list",
+            SyntaxKind::FnSig => "function signature",
+            SyntaxKind::ImplDef => "impl block",
+            SyntaxKind::ImplItemList => "impl item list",
+            SyntaxKind::BinaryExpr => "binary expression",
+            SyntaxKind::PrefixExpr => "prefix expression",
+            SyntaxKind::ParenExpr => "parenthesized expression",
+            SyntaxKind::Literal => "literal",
+            SyntaxKind::BindingUsage => "binding usage",
+            SyntaxKind::Block => "block",
+            SyntaxKind::IfExpr => "if expression",
+            SyntaxKind::CallExpr => "call",
+            SyntaxKind::ArgList => "argument list",
+            SyntaxKind::ListExpr => "list",
+            SyntaxKind::IndexExpr => "index expression",
+            SyntaxKind::FieldExpr => "field access",
+            SyntaxKind::MethodCallExpr => "method call",
+            SyntaxKind::TryExpr => "'?' expression",
+            SyntaxKind::ThrowExpr => "throw expression",
+            SyntaxKind::TryCatchExpr => "try expression",
+            SyntaxKind::CatchClause => "catch clause",
+            SyntaxKind::FinallyClause => "finally clause",
+            SyntaxKind::StructLit => "struct literal",
+            SyntaxKind::FieldInitList => "field initializer list",
+            SyntaxKind::FieldInit => "field initializer",
+            SyntaxKind::RetType => "return type",
+            SyntaxKind::NamedType => "named type",
+            SyntaxKind::FnType => "function type",
+            SyntaxKind::ParamTypeList => "parameter type list",
+            SyntaxKind::TypeArgList => "type argument list",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A half-open byte range into the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TextRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl TextRange {
+    #[inline]
+    pub fn new(start: usize, end: usize) -> Self {
+        assert!(start <= end);
+
+        Self { start, end }
+    }
+
+    #[inline]
+    pub fn at(offset: usize, len: usize) -> Self {
+        Self::new(offset, offset + len)
+    }
+
+    #[inline]
+    pub fn len(self) -> usize {
+        self.end - self.start
+    }
+
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl Display for TextRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    rc::Rc,
+};
+
+use super::SyntaxKind;
+
+/// An immutable leaf of the syntax tree that owns its source text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: &str) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[inline]
+    pub fn text_len(&self) -> usize {
+        self.text.len()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            GreenElement::Node(node) => node.kind(),
+            GreenElement::Token(token) => token.kind(),
+        }
+    }
+
+    #[inline]
+    pub fn text_len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.text_len(),
+            GreenElement::Token(token) => token.text_len(),
+        }
+    }
+}
+
+/// An immutable, position-independent interior node of the syntax tree.
+///
+/// Green nodes only know their kind, their children and the length of the text they cover, which
+/// makes them cheap to share between trees.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    text_len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        let text_len = children.iter().map(GreenElement::text_len).sum();
+
+        Self {
+            kind,
+            text_len,
+            children,
+        }
+    }
+
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+
+    #[inline]
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+impl Display for GreenNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => write!(f, "{}", node)?,
+                GreenElement::Token(token) => write!(f, "{}", token.text())?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Checkpoint(usize);
+
+/// Builds a green tree from a flat stream of `start_node`/`token`/`finish_node` calls.
+#[derive(Debug, Default)]
+pub(crate) struct GreenNodeBuilder {
+    parents: Vec<(SyntaxKind, usize)>,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNodeBuilder {
+    #[inline]
+    pub(crate) fn start_node(&mut self, kind: SyntaxKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    #[inline]
+    pub(crate) fn token(&mut self, kind: SyntaxKind, text: &str) {
+        self.children
+            .push(GreenElement::Token(Rc::new(GreenToken::new(kind, text))));
+    }
+
+    pub(crate) fn finish_node(&mut self) {
+        let (kind, first_child) = self.parents.pop().expect("no node to finish");
+        let children = self.children.split_off(first_child);
+
+        self.children
+            .push(GreenElement::Node(Rc::new(GreenNode::new(kind, children))));
+    }
+
+    #[inline]
+    pub(crate) fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.children.len())
+    }
+
+    /// Starts a node that wraps everything added since `checkpoint` was taken.
+    pub(crate) fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        let Checkpoint(first_child) = checkpoint;
+
+        assert!(first_child <= self.children.len());
+
+        if let Some(&(_, parent_first_child)) = self.parents.last() {
+            assert!(first_child >= parent_first_child);
+        }
+
+        self.parents.push((kind, first_child));
+    }
+
+    pub(crate) fn finish(mut self) -> Rc<GreenNode> {
+        assert!(self.parents.is_empty());
+        assert_eq!(self.children.len(), 1);
+
+        match self.children.pop() {
+            Some(GreenElement::Node(node)) => node,
+            _ => panic!("root of the tree must be a node"),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    rc::Rc,
+};
+
+use super::{GreenElement, GreenNode, GreenToken, SyntaxKind, TextRange};
+
+/// A cursor into a green tree that additionally knows its parent and absolute position.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    offset: usize,
+}
+
+impl SyntaxNode {
+    #[inline]
+    pub fn new_root(green: Rc<GreenNode>) -> Self {
+        Self(Rc::new(NodeData {
+            green,
+            parent: None,
+            offset: 0,
+        }))
+    }
+
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    #[inline]
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    #[inline]
+    pub fn text_range(&self) -> TextRange {
+        TextRange::at(self.0.offset, self.0.green.text_len())
+    }
+
+    #[inline]
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> + '_ {
+        let mut offset = self.0.offset;
+
+        self.0.green.children().iter().map(move |child| {
+            let child_offset = offset;
+            offset += child.text_len();
+
+            match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: Rc::clone(green),
+                    parent: Some(self.clone()),
+                    offset: child_offset,
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: Rc::clone(green),
+                    parent: self.clone(),
+                    offset: child_offset,
+                }),
+            }
+        })
+    }
+
+    #[inline]
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.children_with_tokens()
+            .filter_map(SyntaxElement::into_node)
+    }
+
+    /// Direct child tokens of this node, including trivia.
+    #[inline]
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> + '_ {
+        self.children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+    }
+
+    /// Builds the root of a tree in which this node is replaced by `green`, sharing every node
+    /// that isn't an ancestor of this one with the current tree.
+    pub fn replace_with(&self, green: Rc<GreenNode>) -> Rc<GreenNode> {
+        let Some(parent) = self.parent() else {
+            return green;
+        };
+
+        let mut offset = parent.0.offset;
+        let mut replacement = Some(green);
+
+        let children = parent
+            .0
+            .green
+            .children()
+            .iter()
+            .map(|child| {
+                let child_offset = offset;
+                offset += child.text_len();
+
+                match child {
+                    GreenElement::Node(node)
+                        if child_offset == self.0.offset && Rc::ptr_eq(node, &self.0.green) =>
+                    {
+                        GreenElement::Node(replacement.take().expect("node found twice"))
+                    }
+                    _ => child.clone(),
+                }
+            })
+            .collect();
+
+        parent.replace_with(Rc::new(GreenNode::new(parent.kind(), children)))
+    }
+
+    pub fn debug_tree(&self) -> String {
+        format!("{:#?}", self)
+    }
+
+    fn fmt_tree(&self, f: &mut Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(f, "{:indent$}{:?}", "", self, indent = depth * 2)?;
+
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => node.fmt_tree(f, depth + 1)?,
+                SyntaxElement::Token(token) => {
+                    writeln!(f, "{:indent$}{:?}", "", token, indent = (depth + 1) * 2)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+impl Display for SyntaxNode {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0.green, f)
+    }
+}
+
+impl Debug for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            self.fmt_tree(f, 0)
+        } else {
+            write!(f, "{:?}@{}", self.kind(), self.text_range())
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    offset: usize,
+}
+
+impl SyntaxToken {
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind()
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    #[inline]
+    pub fn text_range(&self) -> TextRange {
+        TextRange::at(self.offset, self.green.text_len())
+    }
+
+    #[inline]
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+}
+
+impl PartialEq for SyntaxToken {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.green, &other.green) && self.offset == other.offset
+    }
+}
+
+impl Eq for SyntaxToken {}
+
+impl Display for SyntaxToken {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+impl Debug for SyntaxToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}@{} {:?}",
+            self.kind(),
+            self.text_range(),
+            self.text()
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+
+    #[inline]
+    pub fn text_range(&self) -> TextRange {
+        match self {
+            SyntaxElement::Node(node) => node.text_range(),
+            SyntaxElement::Token(token) => token.text_range(),
+        }
+    }
+
+    #[inline]
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        }
+    }
+
+    #[inline]
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            SyntaxElement::Node(_) => None,
+            SyntaxElement::Token(token) => Some(token),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{GreenNodeBuilder, SyntaxKind},
+        *,
+    };
+
+    fn sample_tree() -> SyntaxNode {
+        let mut builder = GreenNodeBuilder::default();
+
+        builder.start_node(SyntaxKind::SourceFile);
+        builder.start_node(SyntaxKind::BinaryExpr);
+        builder.start_node(SyntaxKind::Literal);
+        builder.token(SyntaxKind::Number, "10");
+        builder.finish_node();
+        builder.token(SyntaxKind::Whitespace, " ");
+        builder.token(SyntaxKind::Plus, "+");
+        builder.token(SyntaxKind::Whitespace, " ");
+        builder.start_node(SyntaxKind::Literal);
+        builder.token(SyntaxKind::Number, "5");
+        builder.finish_node();
+        builder.finish_node();
+        builder.finish_node();
+
+        SyntaxNode::new_root(builder.finish())
+    }
+
+    #[test]
+    fn display_reproduces_text() {
+        assert_eq!(sample_tree().to_string(), "10 + 5");
+    }
+
+    #[test]
+    fn children_know_their_ranges_and_parents() {
+        let root = sample_tree();
+        let binary_expr = root.children().next().unwrap();
+        let rhs = binary_expr.children().nth(1).unwrap();
+
+        assert_eq!(rhs.text_range(), TextRange::new(5, 6));
+        assert_eq!(rhs.parent(), Some(binary_expr.clone()));
+        assert_eq!(binary_expr.parent(), Some(root));
+    }
+
+    #[test]
+    fn replace_with_shares_untouched_nodes() {
+        let root = sample_tree();
+        let binary_expr = root.children().next().unwrap();
+        let lhs = binary_expr.children().next().unwrap();
+        let rhs = binary_expr.children().nth(1).unwrap();
+
+        let mut builder = GreenNodeBuilder::default();
+        builder.start_node(SyntaxKind::Literal);
+        builder.token(SyntaxKind::Number, "123");
+        builder.finish_node();
+
+        let new_root = SyntaxNode::new_root(rhs.replace_with(builder.finish()));
+        let new_binary_expr = new_root.children().next().unwrap();
+
+        assert_eq!(new_root.to_string(), "10 + 123");
+        assert!(Rc::ptr_eq(
+            new_binary_expr.children().next().unwrap().green(),
+            lhs.green()
+        ));
+    }
+
+    #[test]
+    fn debug_tree() {
+        assert_eq!(
+            sample_tree().debug_tree(),
+            r#"SourceFile@0..6
+  BinaryExpr@0..6
+    Literal@0..2
+      Number@0..2 "10"
+    Whitespace@2..3 " "
+    Plus@3..4 "+"
+    Whitespace@4..5 " "
+    Literal@5..6
+      Number@5..6 "5"
+"#
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::rc::Rc;
+
+use crate::{
+    env::Env,
+    fn_def::Param,
+    symbol::Symbol,
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    type_expr::TypeExpr,
+};
+
+/// `trait Name { fn method(self, params) -> ret_ty ... }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraitDef {
+    pub name: Symbol,
+    pub methods: Vec<FnSig>,
+    pub span: TextRange,
+}
+
+/// `fn name(params) -> ret_ty`, a method of a [`TraitDef`] that every impl of the trait has to
+/// define. The first parameter has to be `self`, which is the value the method is called on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FnSig {
+    pub name: Symbol,
+    pub params: Vec<Param>,
+    pub ret_ty: Option<TypeExpr>,
+    pub span: TextRange,
+}
+
+impl TraitDef {
+    pub(crate) fn lower(node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        let methods = node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::TraitItemList)?
+            .children()
+            .map(|method| FnSig::lower(&method))
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            methods,
+            span: node.text_range(),
+        })
+    }
+
+    pub(crate) fn eval(&self, env: &mut Env) -> Result<(), String> {
+        for (idx, method) in self.methods.iter().enumerate() {
+            if method.params.first().map(|param| param.name.as_str()) != Some("self") {
+                return Err(format!(
+                    "the first parameter of method '{}' must be 'self'",
+                    method.name
+                ));
+            }
+
+            if self.methods[..idx]
+                .iter()
+                .any(|other| other.name == method.name)
+            {
+                return Err(format!(
+                    "method '{}' is defined more than once",
+                    method.name
+                ));
+            }
+        }
+
+        env.store_trait(Rc::new(self.clone()));
+
+        Ok(())
+    }
+
+    pub(crate) fn method(&self, name: &str) -> Option<&FnSig> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+}
+
+impl FnSig {
+    fn lower(node: &SyntaxNode) -> Option<Self> {
+        let name = node
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+        Some(Self {
+            name: Symbol::intern(name.text()),
+            params: Param::lower_list(node)?,
+            ret_ty: TypeExpr::lower_optional_ret_type(node)?,
+            span: node.text_range(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{stmt::Stmt, type_expr::TypeExprKind};
+
+    #[test]
+    fn parse_trait_def() {
+        assert_eq!(
+            crate::parse_stmt("trait Show { fn show(self) -> Str }")
+                .map(|(arena, stmt)| arena[stmt].clone()),
+            Ok(Stmt::TraitDef(TraitDef {
+                name: "Show".into(),
+                methods: vec![FnSig {
+                    name: "show".into(),
+                    params: vec![Param {
+                        name: "self".into(),
+                        ty: None,
+                        span: TextRange::new(21, 25),
+                    }],
+                    ret_ty: Some(TypeExpr::new(
+                        TypeExprKind::Named {
+                            name: "Str".into(),
+                            args: Vec::new(),
+                        },
+                        TextRange::new(30, 33)
+                    )),
+                    span: TextRange::new(13, 33),
+                }],
+                span: TextRange::new(0, 35),
+            }))
+        );
+    }
+
+    #[test]
+    fn methods_need_self() {
+        assert_eq!(
+            crate::parse("trait T { fn f(x: Int) }").eval(&mut Env::default()),
+            Err("the first parameter of method 'f' must be 'self'".to_string())
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    env::{Env, TypeBinding},
+    symbol::Symbol,
+    syntax::{SyntaxKind, SyntaxNode, TextRange},
+    val::Val,
+};
+
+/// A type as written in an annotation, e.g. `Int` in `let a: Int = 1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeExpr {
+    pub kind: TypeExprKind,
+    pub span: TextRange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TypeExprKind {
+    /// A type referred to by its name, such as `Int` or `List<Int>`.
+    Named { name: Symbol, args: Vec<TypeExpr> },
+    /// `fn(params) -> ret`
+    Fn {
+        params: Vec<TypeExpr>,
+        ret: Box<TypeExpr>,
+    },
+}
+
+impl From<TypeExprKind> for TypeExpr {
+    #[inline]
+    fn from(kind: TypeExprKind) -> Self {
+        Self::new(kind, TextRange::default())
+    }
+}
+
+impl TypeExpr {
+    #[inline]
+    pub fn new(kind: TypeExprKind, span: TextRange) -> Self {
+        Self { kind, span }
+    }
+
+    pub(crate) fn lower(node: &SyntaxNode) -> Option<Self> {
+        let kind = match node.kind() {
+            SyntaxKind::NamedType => {
+                let name = node
+                    .tokens()
+                    .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+                let args = match node
+                    .children()
+                    .find(|child| child.kind() == SyntaxKind::TypeArgList)
+                {
+                    Some(args) => args
+                        .children()
+                        .map(|arg| Self::lower(&arg))
+                        .collect::<Option<_>>()?,
+                    None => Vec::new(),
+                };
+
+                TypeExprKind::Named {
+                    name: Symbol::intern(name.text()),
+                    args,
+                }
+            }
+            SyntaxKind::FnType => {
+                let param_types = node
+                    .children()
+                    .find(|child| child.kind() == SyntaxKind::ParamTypeList)?;
+
+                let params = param_types
+                    .children()
+                    .map(|param| Self::lower(&param))
+                    .collect::<Option<_>>()?;
+
+                let ret = node
+                    .children()
+                    .find(|child| child.kind() == SyntaxKind::RetType)?;
+
+                TypeExprKind::Fn {
+                    params,
+                    ret: Box::new(Self::lower_ret_type(&ret)?),
+                }
+            }
+            _ => return None,
+        };
+
+        Some(Self::new(kind, node.text_range()))
+    }
+
+    /// Lowers the type of a `-> type` node.
+    pub(crate) fn lower_ret_type(node: &SyntaxNode) -> Option<Self> {
+        Self::lower(&node.children().next()?)
+    }
+
+    /// Lowers the `-> type` child of `node`, if there is one.
+    pub(crate) fn lower_optional_ret_type(node: &SyntaxNode) -> Option<Option<Self>> {
+        match node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::RetType)
+        {
+            Some(ret_ty) => Self::lower_ret_type(&ret_ty).map(Some),
+            None => Some(None),
+        }
+    }
+
+    /// Lowers the annotated type among the children of `node`, if there is one.
+    pub(crate) fn lower_annotation(node: &SyntaxNode) -> Option<Option<Self>> {
+        match node.children().find(|child| child.kind().is_type()) {
+            Some(ty) => Self::lower(&ty).map(Some),
+            None => Some(None),
+        }
+    }
+
+    /// Checks at runtime that `val` has this type, looking up the names of structs and type
+    /// parameters in `env`.
+    ///
+    /// Only the outermost type is checked: a function's parameter and return types aren't known
+    /// until it gets called, so only its arity is compared, and neither are the elements of a list
+    /// or the type arguments of a struct, `Option` or `Result`. Type parameters stand for any type.
+    pub(crate) fn check(&self, val: &Val, env: &Env) -> Result<(), String> {
+        let matches = match (&self.kind, val) {
+            (TypeExprKind::Named { name, .. }, val) => match name.as_str() {
+                "Int" => matches!(val, Val::Number(_)),
+                "Bool" => matches!(val, Val::Bool(_)),
+                "Str" => matches!(val, Val::Str(_)),
+                "Unit" => matches!(val, Val::Unit),
+                "List" => matches!(val, Val::List(_)),
+                "Option" => matches!(val, Val::Option(_)),
+                "Result" => matches!(val, Val::Result(_)),
+                _ => match env.get_type(name.clone()) {
+                    Some(TypeBinding::Param) => true,
+                    Some(TypeBinding::Struct(_)) => {
+                        matches!(val, Val::Struct(instance) if instance.name == *name)
+                    }
+                    None => return Err(format!("unknown type '{}'", name)),
+                },
+            },
+            (TypeExprKind::Fn { params, .. }, Val::Fn(function)) => {
+                params.len() == function.arity()
+            }
+            (TypeExprKind::Fn { .. }, _) => false,
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(format!("expected {}, but found {}", self, val.type_name()))
+        }
+    }
+}
+
+/// A type parameter of a generic function or struct, e.g. `T` in `fn id<T>(x: T) -> T`. It has no
+/// bounds, so the type checker allows no operators or method calls on its values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeParam {
+    pub name: Symbol,
+    pub span: TextRange,
+}
+
+impl TypeParam {
+    /// Lowers the type parameters among the children of `node`, which may have none.
+    pub(crate) fn lower_list(node: &SyntaxNode) -> Vec<Self> {
+        let Some(type_params) = node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::TypeParamList)
+        else {
+            return Vec::new();
+        };
+
+        type_params
+            .children()
+            .filter_map(|type_param| {
+                let name = type_param
+                    .tokens()
+                    .find(|token| token.kind() == SyntaxKind::Ident)?;
+
+                Some(Self {
+                    name: Symbol::intern(name.text()),
+                    span: type_param.text_range(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Display for TypeExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TypeExprKind::Named { name, args } => {
+                write!(f, "{}", name)?;
+
+                if !args.is_empty() {
+                    write!(f, "<")?;
+
+                    for (idx, arg) in args.iter().enumerate() {
+                        if idx != 0 {
+                            write!(f, ", ")?;
+                        }
+
+                        write!(f, "{}", arg)?;
+                    }
+
+                    write!(f, ">")?;
+                }
+
+                Ok(())
+            }
+            TypeExprKind::Fn { params, ret } => {
+                write!(f, "fn(")?;
+
+                for (idx, param) in params.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}", param)?;
+                }
+
+                write!(f, ") -> {}", ret)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str, start: usize, end: usize) -> TypeExpr {
+        TypeExpr::new(named_type(name).kind, TextRange::new(start, end))
+    }
+
+    fn named_type(name: &str) -> TypeExpr {
+        TypeExpr::from(TypeExprKind::Named {
+            name: name.into(),
+            args: Vec::new(),
+        })
+    }
+
+    fn annotation(s: &str) -> Option<TypeExpr> {
+        let parse = crate::parse(s);
+        assert_eq!(parse.errors(), []);
+
+        let binding_def = parse.syntax().children().next().unwrap();
+        TypeExpr::lower_annotation(&binding_def).unwrap()
+    }
+
+    #[test]
+    fn lower_named_type() {
+        assert_eq!(annotation("let a: Int = 1"), Some(named("Int", 7, 10)));
+    }
+
+    #[test]
+    fn lower_fn_type() {
+        assert_eq!(
+            annotation("let f: fn(Int, Bool) -> Str = g"),
+            Some(TypeExpr::new(
+                TypeExprKind::Fn {
+                    params: vec![named("Int", 10, 13), named("Bool", 15, 19)],
+                    ret: Box::new(named("Str", 24, 27)),
+                },
+                TextRange::new(7, 27)
+            ))
+        );
+    }
+
+    #[test]
+    fn lower_type_args() {
+        assert_eq!(
+            annotation("let a: List<Int> = b"),
+            Some(TypeExpr::new(
+                TypeExprKind::Named {
+                    name: "List".into(),
+                    args: vec![named("Int", 12, 15)],
+                },
+                TextRange::new(7, 16)
+            ))
+        );
+    }
+
+    #[test]
+    fn lower_missing_annotation() {
+        assert_eq!(annotation("let a = 1"), None);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            annotation("let f: fn(Int, fn() -> Unit) -> Map<Str, Int> = g")
+                .unwrap()
+                .to_string(),
+            "fn(Int, fn() -> Unit) -> Map<Str, Int>"
+        );
+    }
+
+    #[test]
+    fn check_vals() {
+        let env = Env::default();
+
+        assert_eq!(named_type("Int").check(&Val::Number(1), &env), Ok(()));
+        assert_eq!(
+            named_type("Int").check(&Val::Bool(true), &env),
+            Err("expected Int, but found Bool".to_string())
+        );
+        assert_eq!(
+            named_type("Foo").check(&Val::Unit, &env),
+            Err("unknown type 'Foo'".to_string())
+        );
+    }
+
+    #[test]
+    fn check_type_params_and_structs() {
+        let mut env = Env::default();
+        crate::parse("struct P { x: Int }").eval(&mut env).unwrap();
+        env.store_type_param("T".into());
+
+        let p = crate::parse("P { x: 1 }").eval(&mut env).unwrap();
+
+        assert_eq!(named_type("T").check(&Val::Bool(true), &env), Ok(()));
+        assert_eq!(named_type("P").check(&p, &env), Ok(()));
+        assert_eq!(
+            named_type("P").check(&Val::Number(1), &env),
+            Err("expected P, but found Int".to_string())
+        );
+    }
+}
@@ -0,0 +1,793 @@
+//! Static type checking of the [AST](crate::ast), which finds type errors before any code runs.
+//!
+//! Types are inferred with Hindley–Milner type inference, so no annotations are needed: bindings
+//! and functions get the most general type their definition allows, and that type is instantiated
+//! anew wherever they are used (let-polymorphism).
+//!
+//! ```
+//! use tlang::typeck::TypeEnv;
+//!
+//! let mut env = TypeEnv::default();
+//!
+//! let parse = tlang::parse("fn id(x) { x }\nid(1) == 1");
+//! assert_eq!(parse.typeck(&mut env).unwrap().to_string(), "Bool");
+//! assert_eq!(env.get("id").unwrap().to_string(), "fn('a) -> 'a");
+//!
+//! let errors = tlang::parse("id(true) + 1").typeck(&mut env).unwrap_err();
+//! assert_eq!(
+//!     errors[0].to_string(),
+//!     "error at 0..8: mismatched types: expected Int, found Bool",
+//! );
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
+
+pub use ty::{Scheme, Type, TypeVar};
+
+use crate::{source_file::SourceFile, symbol::Symbol, syntax::TextRange};
+
+mod infer;
+mod ty;
+
+/// A type error, located at the expression or statement that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    pub message: String,
+    pub span: TextRange,
+}
+
+impl TypeError {
+    pub(crate) fn new(message: impl Into<String>, span: TextRange) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "error at {}: {}", self.span, self.message)
+    }
+}
+
+/// The types of top-level bindings and the top-level declarations of structs, traits and impls,
+/// which are kept around between inputs (e.g. in a REPL), just like [`Env`](crate::Env) keeps
+/// their values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeEnv {
+    bindings: HashMap<Symbol, Scheme>,
+    structs: HashMap<Symbol, StructInfo>,
+    traits: HashMap<Symbol, TraitInfo>,
+    methods: HashMap<(Symbol, Symbol), MethodInfo>,
+    /// The id of the next type parameter, which keeps ids unique across inputs.
+    next_param: usize,
+}
+
+/// A struct declaration, whose field types refer to its type parameters by their ids.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StructInfo {
+    params: Vec<usize>,
+    fields: Vec<(Symbol, Type)>,
+}
+
+/// A trait declaration. The types of its methods take the receiver as their first parameter,
+/// and refer to the type implementing the trait as the type parameter `self_param`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TraitInfo {
+    self_param: usize,
+    methods: Vec<(Symbol, Type)>,
+}
+
+/// A method of an impl of `trait_name` for `target`, whose types refer to the impl's type
+/// parameters by their ids.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MethodInfo {
+    trait_name: Symbol,
+    params: Vec<usize>,
+    target: Type,
+    ty: Type,
+}
+
+impl TypeEnv {
+    /// The type of the top-level binding `name`.
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&Scheme> {
+        self.bindings.get(&Symbol::intern(name))
+    }
+}
+
+/// Type checks `source_file`, returning the type of its value.
+///
+/// `env` is only updated with the top-level bindings of `source_file` if there are no errors.
+pub(crate) fn check(source_file: &SourceFile, env: &mut TypeEnv) -> Result<Type, Vec<TypeError>> {
+    let mut new_env = env.clone();
+    let result = infer::Infer::new(&mut new_env, source_file.arena()).source_file(source_file);
+
+    if result.is_ok() {
+        *env = new_env;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(s: &str) -> Result<String, Vec<String>> {
+        check_in(s, &mut TypeEnv::default())
+    }
+
+    fn check_in(s: &str, env: &mut TypeEnv) -> Result<String, Vec<String>> {
+        crate::parse(s)
+            .typeck(env)
+            .map(|ty| ty.to_string())
+            .map_err(|errors| errors.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(check("1"), Ok("Int".to_string()));
+        assert_eq!(check("true"), Ok("Bool".to_string()));
+        assert_eq!(check("\"hi\""), Ok("Str".to_string()));
+        assert_eq!(check("1;"), Ok("Unit".to_string()));
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(check("1 + 2 * 3"), Ok("Int".to_string()));
+        assert_eq!(check("1 < 2 == !false"), Ok("Bool".to_string()));
+        assert_eq!(check("\"a\" != \"b\""), Ok("Bool".to_string()));
+    }
+
+    #[test]
+    fn mismatched_operands() {
+        assert_eq!(
+            check("1 + true"),
+            Err(vec![
+                "error at 4..8: mismatched types: expected Int, found Bool".to_string()
+            ])
+        );
+        assert_eq!(
+            check("1 == \"1\""),
+            Err(vec![
+                "error at 5..8: mismatched types: expected Int, found Str".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn bindings_and_blocks() {
+        assert_eq!(
+            check("let a = 1\n{ let b = a < 2; b }"),
+            Ok("Bool".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_binding() {
+        assert_eq!(
+            check("a + 1"),
+            Err(vec![
+                "error at 0..1: binding with name 'a' does not exist".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn infer_function_types() {
+        let mut env = TypeEnv::default();
+
+        check_in(
+            "fn add(a, b) { a + b }\nfn twice(f, x) { f(f(x)) }\nfn fact(n) { if n == 0 { 1 } else { n * fact(n - 1) } }",
+            &mut env,
+        )
+        .unwrap();
+
+        assert_eq!(env.get("add").unwrap().to_string(), "fn(Int, Int) -> Int");
+        assert_eq!(
+            env.get("twice").unwrap().to_string(),
+            "fn(fn('a) -> 'a, 'a) -> 'a"
+        );
+        assert_eq!(env.get("fact").unwrap().to_string(), "fn(Int) -> Int");
+    }
+
+    #[test]
+    fn let_polymorphism() {
+        assert_eq!(
+            check(
+                "fn id(x) { x }\nlet also_id = id\nif also_id(true) { id(1) } else { also_id(2) }"
+            ),
+            Ok("Int".to_string())
+        );
+    }
+
+    #[test]
+    fn params_are_monomorphic() {
+        assert_eq!(
+            check("fn f(g) { g(1); g(true) }"),
+            Err(vec![
+                "error at 18..22: mismatched types: expected Int, found Bool".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn closures() {
+        assert_eq!(
+            check("fn adder(a) { fn add(b) { a + b }; add }\nadder(1)"),
+            Ok("fn(Int) -> Int".to_string())
+        );
+    }
+
+    #[test]
+    fn consecutive_fns_can_call_each_other() {
+        let mut env = TypeEnv::default();
+
+        check_in(
+            "fn even(n) { if n == 0 { true } else { odd(n - 1) } }\nfn odd(n) { if n == 0 { false } else { even(n - 1) } }",
+            &mut env,
+        )
+        .unwrap();
+
+        assert_eq!(env.get("even").unwrap().to_string(), "fn(Int) -> Bool");
+        assert_eq!(env.get("odd").unwrap().to_string(), "fn(Int) -> Bool");
+    }
+
+    #[test]
+    fn fns_calling_others_of_their_group_keep_them_polymorphic() {
+        assert_eq!(
+            check("fn f() { [id(1), id(2)]; id(true) }\nfn id(x) { x }\nf()"),
+            Ok("Bool".to_string())
+        );
+        assert_eq!(
+            check("fn f<T>(x: T) { g(x) }\nfn g(y) { f(y) }"),
+            Err(vec![
+                "error at 0..22: type parameter 'T' escapes the function it belongs to".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn bindings_defined_after_fns_are_not_in_scope() {
+        assert_eq!(
+            check("{ fn f() { b }\nlet b = 7\nf() }"),
+            Err(vec![
+                "error at 11..12: binding with name 'b' does not exist".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn wrong_number_of_args() {
+        assert_eq!(
+            check("fn f(a) { a }\nf(1, 2)"),
+            Err(vec![
+                "error at 14..21: function takes 1 argument(s), but 2 were given".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn cannot_call_non_function() {
+        assert_eq!(
+            check("1(2)"),
+            Err(vec![
+                "error at 0..1: cannot call a value of type Int".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn infinite_type() {
+        assert_eq!(
+            check("fn f(x) { x(x) }"),
+            Err(vec![
+                "error at 10..11: cannot construct the infinite type 'a = fn('a) -> 'b".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn if_branches() {
+        assert_eq!(
+            check("if 1 { 2 } else { \"3\" }"),
+            Err(vec![
+                "error at 3..4: mismatched types: expected Bool, found Int".to_string(),
+                "error at 16..23: mismatched types: expected Int, found Str".to_string(),
+            ])
+        );
+        assert_eq!(
+            check("if true { 1 }"),
+            Err(vec![
+                "error at 10..11: mismatched types: expected Unit, found Int".to_string()
+            ])
+        );
+        assert_eq!(check("if true { 1; }"), Ok("Unit".to_string()));
+    }
+
+    #[test]
+    fn env_is_kept_between_inputs_only_without_errors() {
+        let mut env = TypeEnv::default();
+
+        assert!(check_in("let a = 1\nlet b = a + true", &mut env).is_err());
+        assert_eq!(env.get("a"), None);
+
+        check_in("let a = 1", &mut env).unwrap();
+        assert_eq!(check_in("a", &mut env), Ok("Int".to_string()));
+    }
+
+    #[test]
+    fn annotated_bindings() {
+        assert_eq!(check("let a: Int = 1\na"), Ok("Int".to_string()));
+        assert_eq!(
+            check("let a: Bool = 1"),
+            Err(vec![
+                "error at 14..15: mismatched types: expected Bool, found Int".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn annotated_fns() {
+        let mut env = TypeEnv::default();
+
+        check_in("fn apply(f: fn(Int) -> Bool, x) -> Bool { f(x) }", &mut env).unwrap();
+        assert_eq!(
+            env.get("apply").unwrap().to_string(),
+            "fn(fn(Int) -> Bool, Int) -> Bool"
+        );
+
+        assert_eq!(
+            check_in("fn id(x: Str) -> Str { x }\nid(1)", &mut env),
+            Err(vec![
+                "error at 30..31: mismatched types: expected Str, found Int".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn annotations_restrict_polymorphism() {
+        assert_eq!(
+            check("fn id(x: Int) { x }\nid(true)"),
+            Err(vec![
+                "error at 23..27: mismatched types: expected Int, found Bool".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn return_type_mismatch() {
+        assert_eq!(
+            check("fn f() -> Int { true }"),
+            Err(vec![
+                "error at 16..20: mismatched types: expected Int, found Bool".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn unknown_type() {
+        assert_eq!(
+            check("let a: Foo = 1"),
+            Err(vec!["error at 7..10: unknown type 'Foo'".to_string()])
+        );
+    }
+
+    #[test]
+    fn generic_fns_are_instantiated_at_each_call() {
+        let mut env = TypeEnv::default();
+
+        assert_eq!(
+            check_in(
+                "fn first<T>(xs: List<T>) -> T { xs[0] }\nif first([true]) { first([1]) } else { 2 }",
+                &mut env
+            ),
+            Ok("Int".to_string())
+        );
+        assert_eq!(env.get("first").unwrap().to_string(), "fn(List<'a>) -> 'a");
+    }
+
+    #[test]
+    fn generic_bodies_cannot_assume_their_type_params() {
+        assert_eq!(
+            check("fn f<T>(x: T) -> Int { 1 + x }"),
+            Err(vec![
+                "error at 27..28: mismatched types: expected Int, found T".to_string()
+            ])
+        );
+        assert_eq!(
+            check("fn f<A, B>(a: A, b: B) -> A { b }"),
+            Err(vec![
+                "error at 30..31: mismatched types: expected A, found B".to_string()
+            ])
+        );
+    }
+
+    /// Whatever type a type parameter stands for might provide an operator or a method, or might
+    /// not, so neither can be used on its values.
+    #[test]
+    fn type_params_have_no_operators_or_methods() {
+        assert_eq!(
+            check("fn f<T>(x: T) -> Int { x + 1 }"),
+            Err(vec![
+                "error at 23..28: cannot apply '+' to a value of type T, as type parameter 'T' can stand for any type".to_string()
+            ])
+        );
+        assert_eq!(
+            check("fn f<A, B>(a: A, b: B) { a == b }"),
+            Err(vec![
+                "error at 25..31: cannot apply '==' to a value of type A, as type parameter 'A' can stand for any type".to_string()
+            ])
+        );
+        assert_eq!(
+            check(
+                "trait Show { fn show(self) -> Str }\nimpl Show for Int { fn show(self) { \"int\" } }\nfn f<T>(x: T) -> Str { x.show() }"
+            ),
+            Err(vec![
+                "error at 105..113: cannot call method 'show' on a value of type T, as type parameter 'T' can stand for any type".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn type_params_cannot_escape() {
+        assert_eq!(
+            check("fn outer(y) { fn inner<T>(x: T) { y == x }; y }"),
+            Err(vec![
+                "error at 14..42: type parameter 'T' escapes the function it belongs to"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(check("[1, 2][0]"), Ok("Int".to_string()));
+        assert_eq!(check("[]"), Ok("List<'a>".to_string()));
+        assert_eq!(
+            check("[1, true]"),
+            Err(vec![
+                "error at 4..8: mismatched types: expected Int, found Bool".to_string()
+            ])
+        );
+        assert_eq!(
+            check("[1][true]"),
+            Err(vec![
+                "error at 4..8: mismatched types: expected Int, found Bool".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn generic_structs() {
+        let mut env = TypeEnv::default();
+
+        check_in(
+            "struct Pair<A, B> { first: A, second: B }\nfn swap<A, B>(p: Pair<A, B>) -> Pair<B, A> { Pair { first: p.second, second: p.first } }",
+            &mut env,
+        )
+        .unwrap();
+
+        assert_eq!(
+            env.get("swap").unwrap().to_string(),
+            "fn(Pair<'a, 'b>) -> Pair<'b, 'a>"
+        );
+        assert_eq!(
+            check_in("swap(Pair { first: 1, second: true }).first", &mut env),
+            Ok("Bool".to_string())
+        );
+    }
+
+    #[test]
+    fn recursive_structs() {
+        assert_eq!(
+            check(
+                "struct Tree { children: List<Tree> }\nTree { children: [Tree { children: [] }] }"
+            ),
+            Ok("Tree".to_string())
+        );
+    }
+
+    #[test]
+    fn struct_literal_errors() {
+        assert_eq!(
+            check("struct P { x: Int }\nP { x: true, x: 1, y: 2 }"),
+            Err(vec![
+                "error at 27..31: mismatched types: expected Int, found Bool".to_string(),
+                "error at 33..37: field 'x' is specified more than once".to_string(),
+                "error at 39..43: struct 'P' has no field 'y'".to_string(),
+            ])
+        );
+        assert_eq!(
+            check("struct P { x: Int, y: Int }\nP { y: 1 }"),
+            Err(vec![
+                "error at 28..38: missing field 'x' in struct 'P'".to_string()
+            ])
+        );
+        assert_eq!(
+            check("Q { x: 1 }"),
+            Err(vec!["error at 0..10: struct 'Q' does not exist".to_string()])
+        );
+    }
+
+    #[test]
+    fn field_access_needs_known_struct_type() {
+        assert_eq!(
+            check("fn f(p) { p.x }"),
+            Err(vec![
+                "error at 10..13: the type of this value must be known to access its field 'x'"
+                    .to_string()
+            ])
+        );
+        assert_eq!(
+            check("[1].len"),
+            Err(vec![
+                "error at 0..7: cannot access field 'len' on a value of type List<Int>".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn wrong_number_of_type_args() {
+        assert_eq!(
+            check("let a: List = []"),
+            Err(vec![
+                "error at 7..11: type 'List' takes 1 type argument(s), but 0 were given"
+                    .to_string()
+            ])
+        );
+        assert_eq!(
+            check("struct P { x: Int }\nlet a: P<Int> = P { x: 1 }"),
+            Err(vec![
+                "error at 27..33: type 'P' takes 0 type argument(s), but 1 were given".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn duplicate_field_defs() {
+        assert_eq!(
+            check("struct P { x: Int, x: Bool }"),
+            Err(vec![
+                "error at 19..26: field 'x' is defined more than once".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn method_calls() {
+        let mut env = TypeEnv::default();
+
+        check_in(
+            "trait Show { fn show(self) -> Str }\nstruct P { x: Int }\nimpl Show for P { fn show(self) { \"P\" } }\nimpl Show for Int { fn show(self) { \"Int\" } }",
+            &mut env,
+        )
+        .unwrap();
+
+        assert_eq!(
+            check_in("P { x: 1 }.show() == 2.show()", &mut env),
+            Ok("Bool".to_string())
+        );
+        assert_eq!(
+            check_in("true.show()", &mut env),
+            Err(vec![
+                "error at 0..11: no method named 'show' found for a value of type Bool".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn generic_impls() {
+        assert_eq!(
+            check(
+                "trait Get { fn get(self, idx: Int) -> Self }\nimpl<T> Get for List<T> { fn get(self, idx) { [self[idx]] } }\n[true].get(0)"
+            ),
+            Ok("List<Bool>".to_string())
+        );
+    }
+
+    #[test]
+    fn impl_methods_are_checked_against_the_trait() {
+        assert_eq!(
+            check("trait Eq { fn eq(self, other: Self) -> Bool }\nimpl Eq for Int { fn eq(self, other: Bool) -> Bool { other } }"),
+            Err(vec![
+                "error at 76..87: mismatched types: expected Int, found Bool".to_string()
+            ])
+        );
+        assert_eq!(
+            check("trait Neg { fn neg(self) -> Self }\nimpl Neg for Bool { fn neg(self) { 1 } }"),
+            Err(vec![
+                "error at 70..71: mismatched types: expected Bool, found Int".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn impls_cannot_overlap() {
+        assert_eq!(
+            check("trait Show { fn show(self) -> Str }\nimpl Show for List<Int> { fn show(self) { \"ints\" } }\nimpl Show for List<Str> { fn show(self) { \"strs\" } }\n[1].show()"),
+            Err(vec![
+                "error at 89..141: trait 'Show' is already implemented for List".to_string()
+            ])
+        );
+        assert_eq!(
+            check("struct P {}\ntrait A { fn f(self) }\ntrait B { fn f(self) }\nimpl A for P { fn f(self) {} }\nimpl B for P { fn f(self) {} }"),
+            Err(vec![
+                "error at 89..119: method 'f' of trait 'B' is ambiguous, as P already has one of trait 'A'"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn impls_must_match_their_trait() {
+        assert_eq!(
+            check("trait T { fn a(self); fn b(self, x: Int) }\nimpl T for Int { fn b(self) {}; fn c(self) {} }"),
+            Err(vec![
+                "error at 43..90: missing method 'a' in impl of trait 'T' for Int".to_string(),
+                "error at 60..73: method 'b' takes 2 parameter(s) in trait 'T', but 1 in the impl"
+                    .to_string(),
+                "error at 75..88: method 'c' is not a member of trait 'T'".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn generic_impls_cannot_assume_their_type_params() {
+        assert_eq!(
+            check("trait First { fn first(self) -> Int }\nimpl<T> First for List<T> { fn first(self) { self[0] } }"),
+            Err(vec![
+                "error at 83..90: mismatched types: expected Int, found T".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn method_calls_need_known_receiver_type() {
+        assert_eq!(
+            check("trait T { fn t(self) }\nfn f(x) { x.t() }"),
+            Err(vec![
+                "error at 33..38: the type of this value must be known to call its method 't'"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn trait_methods_need_self_and_annotations() {
+        assert_eq!(
+            check("trait T { fn t(x: Int); fn u(self, y) }"),
+            Err(vec![
+                "error at 10..22: the first parameter of method 't' must be 'self'".to_string(),
+                "error at 15..21: mismatched types: expected Self, found Int".to_string(),
+                "error at 35..36: parameter 'y' of method 'u' needs a type annotation".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn overloaded_operators() {
+        let mut env = TypeEnv::default();
+
+        check_in(
+            "struct V { x: Int }\ntrait Mul { fn mul(self, by: Int) -> V }\ntrait Ord { fn cmp(self, other: Self) -> Int }\nimpl Mul for V { fn mul(self, by) { V { x: self.x * by } } }\nimpl Ord for V { fn cmp(self, other) { self.x - other.x } }",
+            &mut env,
+        )
+        .unwrap();
+
+        assert_eq!(
+            check_in("let v = V { x: 1 } * 2\nv < v", &mut env),
+            Ok("Bool".to_string())
+        );
+        assert_eq!(
+            check_in("V { x: 1 } * true", &mut env),
+            Err(vec![
+                "error at 13..17: mismatched types: expected Int, found Bool".to_string()
+            ])
+        );
+        assert_eq!(
+            check_in("V { x: 1 } - V { x: 1 }", &mut env),
+            Err(vec![
+                "error at 0..10: mismatched types: expected Int, found V".to_string(),
+                "error at 13..23: mismatched types: expected Int, found V".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn eq_must_return_bool() {
+        assert_eq!(
+            check("trait Eq { fn eq(self, other: Self) -> Int }\nimpl Eq for Str { fn eq(self, other) { 1 } }\n\"a\" == \"b\""),
+            Err(vec![
+                "error at 90..100: mismatched types: expected Bool, found Int".to_string()
+            ])
+        );
+    }
+    #[test]
+    fn option_and_result() {
+        assert_eq!(check("None"), Ok("Option<'a>".to_string()));
+        assert_eq!(check("Ok(1)"), Ok("Result<Int, 'a>".to_string()));
+        assert_eq!(
+            check("let e: Result<Int, Str> = Err(\"no\")\ne"),
+            Ok("Result<Int, Str>".to_string())
+        );
+        assert_eq!(check("unwrap_or(ok(Err(true)), 2)"), Ok("Int".to_string()));
+        assert_eq!(check("let Some = 1\nSome"), Ok("Int".to_string()));
+    }
+
+    #[test]
+    fn try_operator() {
+        assert_eq!(
+            check("fn half(n) { if n / 2 * 2 == n { Some(n / 2) } else { None } }\nfn quarter(n) { half(half(n)?) }\nquarter"),
+            Ok("fn(Int) -> Option<Int>".to_string())
+        );
+        assert_eq!(
+            check("fn parse(s: Str) -> Result<Int, Str> { Err(s) }\nfn twice(s) { Ok(parse(s)? * 2) }\ntwice"),
+            Ok("fn(Str) -> Result<Int, Str>".to_string())
+        );
+    }
+
+    #[test]
+    fn try_operator_errors() {
+        assert_eq!(
+            check("Some(1)?"),
+            Err(vec![
+                "error at 0..8: the '?' operator can only be used inside a function".to_string()
+            ])
+        );
+        assert_eq!(
+            check("fn f(x) { x? }"),
+            Err(vec![
+                "error at 10..12: the type of this value must be known to apply '?' to it"
+                    .to_string()
+            ])
+        );
+        assert_eq!(
+            check("fn f() { 1? }"),
+            Err(vec![
+                "error at 9..11: cannot apply '?' to a value of type Int, only to Option or Result"
+                    .to_string()
+            ])
+        );
+        assert_eq!(
+            check("fn f() -> Result<Int, Str> { let a = Err(1)?; Ok(a) }"),
+            Err(vec![
+                "error at 37..44: mismatched types: expected Result<Int, Str>, found Result<Int, Int>".to_string()
+            ])
+        );
+        assert_eq!(
+            check("fn f() -> Int { None?; 1 }"),
+            Err(vec![
+                "error at 16..21: mismatched types: expected Int, found Option<'a>".to_string()
+            ])
+        );
+    }
+    #[test]
+    fn throw_and_try_catch() {
+        assert_eq!(
+            check("fn check(n) { if n < 0 { throw \"negative\" } else { n } }\ncheck"),
+            Ok("fn(Int) -> Int".to_string())
+        );
+        assert_eq!(
+            check("try { 1 } catch e { e == \"x\"; 2 } finally { true }"),
+            Ok("Int".to_string())
+        );
+        assert_eq!(
+            check("throw 1"),
+            Err(vec![
+                "error at 6..7: mismatched types: expected Str, found Int".to_string()
+            ])
+        );
+        assert_eq!(
+            check("try { 1 } catch e { e }"),
+            Err(vec![
+                "error at 20..21: mismatched types: expected Int, found Str".to_string()
+            ])
+        );
+    }
+}
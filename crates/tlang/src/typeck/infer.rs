@@ -0,0 +1,1625 @@
+use std::{collections::HashMap, ops::Range};
+
+use super::{
+    ty::{self, Scheme, Type, TypeVar},
+    MethodInfo, StructInfo, TraitInfo, TypeEnv, TypeError,
+};
+use crate::{
+    ast::{Arena, BindingUsage, ExprId, StmtId, Visitor},
+    binding_def::BindingDef,
+    builtins,
+    expr::{Block, Call, ExprKind, If, MethodCall, Op, StructLit, TryCatch, UnaryOp},
+    fn_def::FnDef,
+    impl_def::ImplDef,
+    source_file::SourceFile,
+    stmt::{self, Stmt},
+    struct_def::StructDef,
+    symbol::{self, Symbol},
+    syntax::TextRange,
+    trait_def::TraitDef,
+    type_expr::{TypeExpr, TypeExprKind, TypeParam},
+};
+
+/// What is known about a type variable.
+///
+/// Unbound variables remember the level (the number of enclosing definitions) they were created
+/// at, so that generalization can tell which of them belong to the definition being generalized.
+enum VarState {
+    Unbound { level: usize },
+    Bound(Type),
+}
+
+/// What a type name declared in a scope refers to.
+#[derive(Clone)]
+enum TypeDef {
+    Param(Type),
+    Struct(StructInfo),
+}
+
+/// What the annotations of a function say about its type.
+struct Signature {
+    type_params: Vec<(Symbol, usize)>,
+    /// The scope declaring the type parameters.
+    scope: Scope,
+    params: Vec<Type>,
+    ret: Type,
+    ty: Type,
+    /// The variables created for the parts without annotations.
+    vars: Range<usize>,
+}
+
+#[derive(Default)]
+struct Scope {
+    values: HashMap<Symbol, Scheme>,
+    types: HashMap<Symbol, TypeDef>,
+    traits: HashMap<Symbol, TraitInfo>,
+    methods: HashMap<(Symbol, Symbol), MethodInfo>,
+}
+
+enum UnifyError {
+    Mismatch,
+    InfiniteType(TypeVar, Type),
+}
+
+pub(super) struct Infer<'env> {
+    env: &'env mut TypeEnv,
+    arena: &'env Arena,
+    scopes: Vec<Scope>,
+    vars: Vec<VarState>,
+    level: usize,
+    /// The return types of the functions being checked, innermost last, which is what `?`
+    /// returns from.
+    fn_rets: Vec<Type>,
+    errors: Vec<TypeError>,
+}
+
+impl<'env> Infer<'env> {
+    pub(super) fn new(env: &'env mut TypeEnv, arena: &'env Arena) -> Self {
+        Self {
+            env,
+            arena,
+            scopes: Vec::new(),
+            vars: Vec::new(),
+            level: 0,
+            fn_rets: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub(super) fn source_file(mut self, source_file: &SourceFile) -> Result<Type, Vec<TypeError>> {
+        let ty = self.stmts(&source_file.stmts);
+
+        if self.errors.is_empty() {
+            let [ty] = ty::normalize([&self.zonk(&ty)]);
+            Ok(ty)
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn stmts(&mut self, stmts: &[StmtId]) -> Type {
+        let arena = self.arena;
+        let mut ty = Type::Unit;
+
+        for group in stmt::groups(arena, stmts) {
+            ty = match group {
+                [stmt] => self.stmt(*stmt),
+                fn_defs => {
+                    let fn_defs: Vec<_> = fn_defs
+                        .iter()
+                        .filter_map(|&stmt| match &arena[stmt] {
+                            Stmt::FnDef(fn_def) => Some(fn_def),
+                            _ => None,
+                        })
+                        .collect();
+
+                    self.fn_group(&fn_defs);
+                    Type::Unit
+                }
+            };
+        }
+
+        ty
+    }
+
+    fn stmt(&mut self, stmt: StmtId) -> Type {
+        match &self.arena[stmt] {
+            Stmt::BindingDef(binding_def) => {
+                self.binding_def(binding_def);
+                Type::Unit
+            }
+            Stmt::FnDef(fn_def) => {
+                self.fn_group(&[fn_def]);
+                Type::Unit
+            }
+            Stmt::StructDef(struct_def) => {
+                self.struct_def(struct_def);
+                Type::Unit
+            }
+            Stmt::TraitDef(trait_def) => {
+                self.trait_def(trait_def);
+                Type::Unit
+            }
+            Stmt::ImplDef(impl_def) => {
+                self.impl_def(impl_def);
+                Type::Unit
+            }
+            Stmt::Expr(expr) => self.expr(*expr),
+            Stmt::Semi(expr) => {
+                self.expr(*expr);
+                Type::Unit
+            }
+        }
+    }
+
+    fn binding_def(&mut self, binding_def: &BindingDef) {
+        self.level += 1;
+
+        let ty = self.expr(binding_def.val);
+
+        if let Some(annotation) = &binding_def.ty {
+            let annotation = self.type_expr(annotation);
+            self.expect(&annotation, &ty, self.span(binding_def.val));
+        }
+
+        self.level -= 1;
+
+        let scheme = self.generalize(&ty);
+        self.define(binding_def.name.clone(), scheme);
+    }
+
+    /// The functions of a group can call each other, so they are checked one strongly connected
+    /// component of their calls at a time, callees first. That way only functions that really are
+    /// mutually recursive have to use each other at a single type.
+    fn fn_group(&mut self, fn_defs: &[&FnDef]) {
+        // The functions checked so far are looked up in a scope of their own, as they aren't
+        // checked in order, and are only defined in order once all of them have been checked.
+        self.scopes.push(Scope::default());
+        let mut schemes = vec![None; fn_defs.len()];
+
+        for component in call_components(self.arena, fn_defs) {
+            let component_defs: Vec<_> = component.iter().map(|&idx| fn_defs[idx]).collect();
+            let component_schemes = self.fn_defs(&component_defs);
+
+            for (idx, scheme) in component.into_iter().zip(component_schemes) {
+                let scope = self.scopes.last_mut().unwrap();
+                scope
+                    .values
+                    .insert(fn_defs[idx].name.clone(), scheme.clone());
+                schemes[idx] = Some(scheme);
+            }
+        }
+
+        self.scopes.pop();
+
+        for (fn_def, scheme) in fn_defs.iter().zip(schemes) {
+            self.define(fn_def.name.clone(), scheme.unwrap());
+        }
+    }
+
+    /// Functions are only generalized once the bodies of all of `fn_defs` have been inferred, so
+    /// calls among them, including recursive ones, all have to use the same type.
+    ///
+    /// Within a body, type parameters are rigid: they only unify with themselves, so the body
+    /// can't assume anything about them. Once it has been checked, they are replaced by fresh
+    /// variables that are generalized, which instantiates them anew at every call site.
+    fn fn_defs(&mut self, fn_defs: &[&FnDef]) -> Vec<Scheme> {
+        self.level += 1;
+
+        let vars_before = self.vars.len();
+        let sigs: Vec<_> = fn_defs
+            .iter()
+            .map(|fn_def| self.signature(fn_def))
+            .collect();
+
+        let mut scope = Scope::default();
+        for (fn_def, sig) in fn_defs.iter().zip(&sigs) {
+            scope
+                .values
+                .insert(fn_def.name.clone(), Scheme::mono(sig.ty.clone()));
+        }
+        self.scopes.push(scope);
+
+        for (fn_def, sig) in fn_defs.iter().zip(&sigs) {
+            self.fn_body(fn_def, sig);
+        }
+
+        self.scopes.pop();
+
+        let fn_tys: Vec<_> = fn_defs
+            .iter()
+            .zip(&sigs)
+            .enumerate()
+            .map(|(idx, (fn_def, sig))| {
+                // The type parameters of a function mean nothing in the signatures of the others.
+                let others = sigs.iter().enumerate().filter(|(other, _)| *other != idx);
+                let outside =
+                    (0..vars_before).chain(others.flat_map(|(_, other)| other.vars.clone()));
+                self.check_params_dont_escape(&sig.type_params, outside, fn_def.span);
+
+                let substitution = sig
+                    .type_params
+                    .iter()
+                    .map(|(_, id)| (*id, self.fresh_var()))
+                    .collect();
+
+                self.zonk(&sig.ty).replace_params(&substitution)
+            })
+            .collect();
+
+        self.level -= 1;
+
+        fn_tys.iter().map(|ty| self.generalize(ty)).collect()
+    }
+
+    /// The type of `fn_def` as far as its annotations tell, with fresh variables for the rest.
+    fn signature(&mut self, fn_def: &FnDef) -> Signature {
+        let start = self.vars.len();
+
+        let mut scope = Scope::default();
+        let type_params = self.declare_type_params(&fn_def.type_params, &mut scope);
+        self.scopes.push(scope);
+
+        let params: Vec<_> = fn_def
+            .params
+            .iter()
+            .map(|param| self.annotation_or_fresh_var(param.ty.as_ref()))
+            .collect();
+        let ret = self.annotation_or_fresh_var(fn_def.ret_ty.as_ref());
+
+        let scope = self.scopes.pop().unwrap();
+
+        Signature {
+            type_params,
+            scope,
+            ty: Type::Fn {
+                params: params.clone(),
+                ret: Box::new(ret.clone()),
+            },
+            params,
+            ret,
+            vars: start..self.vars.len(),
+        }
+    }
+
+    fn fn_body(&mut self, fn_def: &FnDef, sig: &Signature) {
+        let mut scope = Scope {
+            types: sig.scope.types.clone(),
+            ..Scope::default()
+        };
+        for (param, ty) in fn_def.params.iter().zip(&sig.params) {
+            scope
+                .values
+                .insert(param.name.clone(), Scheme::mono(ty.clone()));
+        }
+        self.scopes.push(scope);
+
+        self.fn_rets.push(sig.ret.clone());
+        let body = self.block(&fn_def.body);
+        self.fn_rets.pop();
+        self.scopes.pop();
+
+        let span = self.block_result_span(&fn_def.body).unwrap_or(fn_def.span);
+        self.expect(&sig.ret, &body, span);
+    }
+
+    /// Declares each of `type_params` as a new rigid type in `scope`, returning their names and
+    /// ids.
+    fn declare_type_params(
+        &mut self,
+        type_params: &[TypeParam],
+        scope: &mut Scope,
+    ) -> Vec<(Symbol, usize)> {
+        type_params
+            .iter()
+            .map(|type_param| {
+                let (ty, id) = self.new_param(type_param.name.clone());
+                scope
+                    .types
+                    .insert(type_param.name.clone(), TypeDef::Param(ty));
+
+                (type_param.name.clone(), id)
+            })
+            .collect()
+    }
+
+    fn new_param(&mut self, name: Symbol) -> (Type, usize) {
+        let id = self.env.next_param;
+        self.env.next_param += 1;
+
+        let ty = Type::Param { name, id };
+
+        (ty, id)
+    }
+
+    /// Reports type parameters that ended up in the type of something defined outside of their
+    /// function, like a parameter of an enclosing function, where they would mean nothing.
+    ///
+    /// Only the variables in `outside` can belong to the outside, such as those created before
+    /// the function was entered.
+    fn check_params_dont_escape(
+        &mut self,
+        type_params: &[(Symbol, usize)],
+        outside: impl Iterator<Item = usize> + Clone,
+        span: TextRange,
+    ) {
+        for (name, id) in type_params {
+            let escapes = outside.clone().any(|var| match &self.vars[var] {
+                VarState::Bound(ty) => self.zonk(ty).contains_param(*id),
+                VarState::Unbound { .. } => false,
+            });
+
+            if escapes {
+                self.error(
+                    format!(
+                        "type parameter '{}' escapes the function it belongs to",
+                        name
+                    ),
+                    span,
+                );
+            }
+        }
+    }
+
+    /// The struct is declared before its fields are resolved, so they can refer to it.
+    fn struct_def(&mut self, struct_def: &StructDef) {
+        let mut scope = Scope::default();
+        let type_params = self.declare_type_params(&struct_def.type_params, &mut scope);
+        let params: Vec<_> = type_params.iter().map(|(_, id)| *id).collect();
+
+        self.define_struct(
+            struct_def.name.clone(),
+            StructInfo {
+                params: params.clone(),
+                fields: Vec::new(),
+            },
+        );
+
+        self.scopes.push(scope);
+
+        let mut fields: Vec<(Symbol, Type)> = Vec::new();
+
+        for field_def in &struct_def.fields {
+            let ty = self.type_expr(&field_def.ty);
+
+            if fields.iter().any(|(name, _)| *name == field_def.name) {
+                self.error(
+                    format!("field '{}' is defined more than once", field_def.name),
+                    field_def.span,
+                );
+            } else {
+                fields.push((field_def.name.clone(), ty));
+            }
+        }
+
+        self.scopes.pop();
+
+        self.define_struct(struct_def.name.clone(), StructInfo { params, fields });
+    }
+
+    /// Within the trait, `Self` stands for the type implementing it, which is also the type of
+    /// the `self` parameter every method starts with.
+    fn trait_def(&mut self, trait_def: &TraitDef) {
+        let (self_ty, self_param) = self.new_param(symbol::SELF_TYPE.clone());
+
+        let mut scope = Scope::default();
+        scope
+            .types
+            .insert(symbol::SELF_TYPE.clone(), TypeDef::Param(self_ty.clone()));
+        self.scopes.push(scope);
+
+        let mut methods: Vec<(Symbol, Type)> = Vec::new();
+
+        for sig in &trait_def.methods {
+            if sig.params.first().map(|param| param.name.as_str()) != Some("self") {
+                self.error(
+                    format!(
+                        "the first parameter of method '{}' must be 'self'",
+                        sig.name
+                    ),
+                    sig.span,
+                );
+            }
+
+            let mut params = Vec::new();
+
+            for (idx, param) in sig.params.iter().enumerate() {
+                let ty = match &param.ty {
+                    Some(ty) => self.type_expr(ty),
+                    None if idx == 0 => self_ty.clone(),
+                    None => {
+                        self.error(
+                            format!(
+                                "parameter '{}' of method '{}' needs a type annotation",
+                                param.name, sig.name
+                            ),
+                            param.span,
+                        );
+                        self.fresh_var()
+                    }
+                };
+
+                if idx == 0 {
+                    self.expect(&self_ty, &ty, param.span);
+                }
+
+                params.push(ty);
+            }
+
+            let ret = match &sig.ret_ty {
+                Some(ret_ty) => self.type_expr(ret_ty),
+                None => Type::Unit,
+            };
+
+            if methods.iter().any(|(name, _)| *name == sig.name) {
+                self.error(
+                    format!("method '{}' is defined more than once", sig.name),
+                    sig.span,
+                );
+            } else {
+                methods.push((
+                    sig.name.clone(),
+                    Type::Fn {
+                        params,
+                        ret: Box::new(ret),
+                    },
+                ));
+            }
+        }
+
+        self.scopes.pop();
+
+        self.define_trait(
+            trait_def.name.clone(),
+            TraitInfo {
+                self_param,
+                methods,
+            },
+        );
+    }
+
+    /// The methods are declared before their bodies are checked, so that they can call each
+    /// other. Like generic functions, their bodies can't assume anything about the type
+    /// parameters of the impl.
+    fn impl_def(&mut self, impl_def: &ImplDef) {
+        let Some(trait_info) = self.lookup_trait(impl_def.trait_name.clone()).cloned() else {
+            self.error(
+                format!("trait '{}' does not exist", impl_def.trait_name),
+                impl_def.span,
+            );
+            return;
+        };
+
+        self.level += 1;
+
+        let vars_before = self.vars.len();
+        let mut scope = Scope::default();
+        let type_params = self.declare_type_params(&impl_def.type_params, &mut scope);
+
+        self.scopes.push(scope);
+        let target = self.type_expr(&impl_def.target);
+        let scope = self.scopes.pop().unwrap();
+
+        let target_name = match &target {
+            Type::Param { name, .. } => {
+                self.error(
+                    format!("cannot implement a trait for the type parameter '{}'", name),
+                    impl_def.target.span,
+                );
+                None
+            }
+            // An unknown type, which has been reported already.
+            Type::Var(_) => None,
+            ty => {
+                let name = type_name(ty);
+
+                if name.is_none() {
+                    self.error(
+                        format!("cannot implement a trait for {}", ty),
+                        impl_def.target.span,
+                    );
+                }
+
+                name
+            }
+        };
+
+        let Some(target_name) = target_name else {
+            self.level -= 1;
+            return;
+        };
+
+        // Methods are looked up by the name of the type alone, so impls for types of the same name
+        // can't both define a method of the same name.
+        let taken: Vec<_> = trait_info
+            .methods
+            .iter()
+            .filter_map(|(name, _)| {
+                let other = self.lookup_method(target_name.clone(), name.clone())?;
+                Some((name.clone(), other.trait_name.clone()))
+            })
+            .collect();
+
+        if taken.iter().any(|(_, other)| *other == impl_def.trait_name) {
+            self.error(
+                format!(
+                    "trait '{}' is already implemented for {}",
+                    impl_def.trait_name, target_name
+                ),
+                impl_def.span,
+            );
+        } else {
+            for (name, other) in &taken {
+                self.error(
+                    format!(
+                        "method '{}' of trait '{}' is ambiguous, as {} already has one of trait '{}'",
+                        name, impl_def.trait_name, target_name, other
+                    ),
+                    impl_def.span,
+                );
+            }
+        }
+
+        let substitution = HashMap::from([(trait_info.self_param, target.clone())]);
+        let params: Vec<_> = type_params.iter().map(|(_, id)| *id).collect();
+
+        for (name, ty) in &trait_info.methods {
+            if !impl_def.methods.iter().any(|method| method.name == *name) {
+                self.error(
+                    format!(
+                        "missing method '{}' in impl of trait '{}' for {}",
+                        name, impl_def.trait_name, target
+                    ),
+                    impl_def.span,
+                );
+            }
+
+            if taken.iter().any(|(taken, _)| taken == name) {
+                continue;
+            }
+
+            self.define_method(
+                target_name.clone(),
+                name.clone(),
+                MethodInfo {
+                    trait_name: impl_def.trait_name.clone(),
+                    params: params.clone(),
+                    target: target.clone(),
+                    ty: ty.replace_params(&substitution),
+                },
+            );
+        }
+
+        self.scopes.push(scope);
+
+        for (idx, method) in impl_def.methods.iter().enumerate() {
+            if impl_def.methods[..idx]
+                .iter()
+                .any(|other| other.name == method.name)
+            {
+                self.error(
+                    format!("method '{}' is defined more than once", method.name),
+                    method.span,
+                );
+                continue;
+            }
+
+            match trait_info
+                .methods
+                .iter()
+                .find(|(name, _)| *name == method.name)
+            {
+                Some((_, ty)) => {
+                    let ty = ty.replace_params(&substitution);
+                    self.method(method, &ty, &impl_def.trait_name);
+                }
+                None => self.error(
+                    format!(
+                        "method '{}' is not a member of trait '{}'",
+                        method.name, impl_def.trait_name
+                    ),
+                    method.span,
+                ),
+            }
+        }
+
+        self.scopes.pop();
+
+        self.check_params_dont_escape(&type_params, 0..vars_before, impl_def.span);
+
+        self.level -= 1;
+    }
+
+    /// Checks a method of an impl against `expected`, the type its trait declares for it.
+    fn method(&mut self, method: &FnDef, expected: &Type, trait_name: &str) {
+        let Type::Fn {
+            params: expected_params,
+            ret: expected_ret,
+        } = expected
+        else {
+            unreachable!("methods have function types")
+        };
+
+        if method.params.len() != expected_params.len() {
+            self.error(
+                format!(
+                    "method '{}' takes {} parameter(s) in trait '{}', but {} in the impl",
+                    method.name,
+                    expected_params.len(),
+                    trait_name,
+                    method.params.len()
+                ),
+                method.span,
+            );
+            return;
+        }
+
+        if !method.type_params.is_empty() {
+            self.error(
+                format!(
+                    "method '{}' can't have type parameters of its own",
+                    method.name
+                ),
+                method.span,
+            );
+            return;
+        }
+
+        let mut scope = Scope::default();
+
+        for (param, expected) in method.params.iter().zip(expected_params) {
+            // A mismatched annotation has been reported, so the body sticks to the annotation.
+            let ty = match &param.ty {
+                Some(ty) => {
+                    let ty = self.type_expr(ty);
+                    self.expect(expected, &ty, param.span);
+                    ty
+                }
+                None => expected.clone(),
+            };
+
+            scope.values.insert(param.name.clone(), Scheme::mono(ty));
+        }
+
+        if let Some(ret_ty) = &method.ret_ty {
+            let ret = self.type_expr(ret_ty);
+            self.expect(expected_ret, &ret, ret_ty.span);
+        }
+
+        self.scopes.push(scope);
+        self.fn_rets.push((**expected_ret).clone());
+        let body = self.block(&method.body);
+        self.fn_rets.pop();
+        self.scopes.pop();
+
+        let span = self.block_result_span(&method.body).unwrap_or(method.span);
+        self.expect(expected_ret, &body, span);
+    }
+
+    fn block(&mut self, block: &Block) -> Type {
+        self.scopes.push(Scope::default());
+        let ty = self.stmts(&block.stmts);
+        self.scopes.pop();
+
+        ty
+    }
+
+    fn expr(&mut self, id: ExprId) -> Type {
+        let expr = &self.arena[id];
+
+        match &expr.kind {
+            ExprKind::Number(_) => Type::Int,
+            ExprKind::Bool(_) => Type::Bool,
+            ExprKind::Str(_) => Type::Str,
+            ExprKind::Operation { lhs, rhs, op } => {
+                let lhs_ty = self.expr(*lhs);
+                let rhs_ty = self.expr(*rhs);
+
+                // Operands of any type other than `Int` can provide the operator as a method. That
+                // of a type parameter might, so it can't be relied on either way.
+                let resolved = self.zonk(&lhs_ty);
+                let method = match resolved {
+                    Type::Int | Type::Var(_) => None,
+                    Type::Param { name, .. } => {
+                        self.error(
+                            format!(
+                                "cannot apply '{}' to a value of type {}, as type parameter '{}' can stand for any type",
+                                op, name, name
+                            ),
+                            expr.span,
+                        );
+
+                        return match op {
+                            Op::Add | Op::Sub | Op::Mul | Op::Div => self.fresh_var(),
+                            _ => Type::Bool,
+                        };
+                    }
+                    ty => self.find_method(&ty, op.method_name()),
+                };
+
+                if let Some(method) = method {
+                    let ret = self.apply_method(
+                        &method,
+                        op.method_name(),
+                        (lhs_ty, self.span(*lhs)),
+                        &[(rhs_ty, self.span(*rhs))],
+                        expr.span,
+                    );
+
+                    return match op {
+                        Op::Add | Op::Sub | Op::Mul | Op::Div => ret,
+                        Op::Eq | Op::NotEq => {
+                            self.expect(&Type::Bool, &ret, expr.span);
+                            Type::Bool
+                        }
+                        Op::Lt | Op::LtEq | Op::Gt | Op::GtEq => {
+                            self.expect(&Type::Int, &ret, expr.span);
+                            Type::Bool
+                        }
+                    };
+                }
+
+                match op {
+                    Op::Add | Op::Sub | Op::Mul | Op::Div => {
+                        self.expect(&Type::Int, &lhs_ty, self.span(*lhs));
+                        self.expect(&Type::Int, &rhs_ty, self.span(*rhs));
+
+                        Type::Int
+                    }
+                    Op::Eq | Op::NotEq => {
+                        self.expect(&lhs_ty, &rhs_ty, self.span(*rhs));
+
+                        Type::Bool
+                    }
+                    Op::Lt | Op::LtEq | Op::Gt | Op::GtEq => {
+                        self.expect(&Type::Int, &lhs_ty, self.span(*lhs));
+                        self.expect(&Type::Int, &rhs_ty, self.span(*rhs));
+
+                        Type::Bool
+                    }
+                }
+            }
+            ExprKind::Unary { op, expr } => {
+                let operand = match op {
+                    UnaryOp::Neg => Type::Int,
+                    UnaryOp::Not => Type::Bool,
+                };
+
+                let ty = self.expr(*expr);
+                self.expect(&operand, &ty, self.span(*expr));
+
+                operand
+            }
+            ExprKind::BindingUsage(binding_usage) => {
+                match self.lookup(binding_usage.name.clone()) {
+                    Some(scheme) => self.instantiate(&scheme),
+                    None => {
+                        self.error(
+                            format!("binding with name '{}' does not exist", binding_usage.name),
+                            expr.span,
+                        );
+
+                        self.fresh_var()
+                    }
+                }
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::If(if_expr) => self.if_expr(if_expr, expr.span),
+            ExprKind::Call(call) => self.call(call, expr.span),
+            ExprKind::List(elements) => {
+                let element_ty = self.fresh_var();
+
+                for &element in elements {
+                    let ty = self.expr(element);
+                    self.expect(&element_ty, &ty, self.span(element));
+                }
+
+                Type::list(element_ty)
+            }
+            ExprKind::Index { base, index } => {
+                let element_ty = self.fresh_var();
+
+                let base_ty = self.expr(*base);
+                self.expect(&Type::list(element_ty.clone()), &base_ty, self.span(*base));
+
+                let index_ty = self.expr(*index);
+                self.expect(&Type::Int, &index_ty, self.span(*index));
+
+                element_ty
+            }
+            ExprKind::Field { base, name } => self.field(*base, name, expr.span),
+            ExprKind::StructLit(struct_lit) => self.struct_lit(struct_lit, expr.span),
+            ExprKind::MethodCall(method_call) => self.method_call(method_call, expr.span),
+            ExprKind::Try(operand) => self.try_expr(*operand, expr.span),
+            ExprKind::Throw(val) => {
+                let ty = self.expr(*val);
+                self.expect(&Type::Str, &ty, self.span(*val));
+
+                // `throw` never produces a value, so it fits in wherever any type is expected.
+                self.fresh_var()
+            }
+            ExprKind::TryCatch(try_catch) => self.try_catch(try_catch),
+        }
+    }
+
+    /// Thrown values are messages, so the caught value is always a `Str`. The value of the
+    /// `finally` block is discarded, so it can have any type.
+    fn try_catch(&mut self, try_catch: &TryCatch) -> Type {
+        let ty = self.block(&try_catch.body);
+
+        if let Some(catch) = &try_catch.catch {
+            let mut scope = Scope::default();
+            scope
+                .values
+                .insert(catch.name.clone(), Scheme::mono(Type::Str));
+
+            self.scopes.push(scope);
+            let catch_ty = self.block(&catch.body);
+            self.scopes.pop();
+
+            let catch_span = self.block_result_span(&catch.body).unwrap_or(catch.span);
+            self.expect(&ty, &catch_ty, catch_span);
+        }
+
+        if let Some(finally) = &try_catch.finally {
+            self.block(finally);
+        }
+
+        ty
+    }
+
+    /// Like field access, `?` needs the type of its operand to be known already, as it works
+    /// differently for `Option` and `Result`. Either way the enclosing function has to return the
+    /// same kind of value, with the same error type for `Result`.
+    fn try_expr(&mut self, operand: ExprId, span: TextRange) -> Type {
+        let ty = self.expr(operand);
+
+        let Some(ret) = self.fn_rets.last().cloned() else {
+            self.error(
+                "the '?' operator can only be used inside a function".to_string(),
+                span,
+            );
+            return self.fresh_var();
+        };
+
+        let (returned, val) = match self.zonk(&ty) {
+            Type::Named { name, mut args } if name == "Option" && args.len() == 1 => {
+                (Type::option(self.fresh_var()), args.remove(0))
+            }
+            Type::Named { name, mut args } if name == "Result" && args.len() == 2 => {
+                let err = args.remove(1);
+                (Type::result(self.fresh_var(), err), args.remove(0))
+            }
+            Type::Var(_) => {
+                self.error(
+                    "the type of this value must be known to apply '?' to it".to_string(),
+                    span,
+                );
+                return self.fresh_var();
+            }
+            ty => {
+                let [ty] = ty::normalize([&ty]);
+                self.error(
+                    format!(
+                        "cannot apply '?' to a value of type {}, only to Option or Result",
+                        ty
+                    ),
+                    span,
+                );
+                return self.fresh_var();
+            }
+        };
+
+        self.expect(&ret, &returned, span);
+
+        val
+    }
+
+    /// Like field access, method calls need the type of the receiver to be known already, as
+    /// that is what the impl providing the method is found by.
+    fn method_call(&mut self, method_call: &MethodCall, span: TextRange) -> Type {
+        let receiver = self.expr(method_call.receiver);
+        let args: Vec<_> = method_call
+            .args
+            .iter()
+            .map(|&arg| (self.expr(arg), self.span(arg)))
+            .collect();
+
+        let receiver_ty = self.zonk(&receiver);
+
+        let Some(method) = self.find_method(&receiver_ty, method_call.name.clone()) else {
+            let message = match receiver_ty {
+                Type::Var(_) => format!(
+                    "the type of this value must be known to call its method '{}'",
+                    method_call.name
+                ),
+                Type::Param { name, .. } => format!(
+                    "cannot call method '{}' on a value of type {}, as type parameter '{}' can stand for any type",
+                    method_call.name, name, name
+                ),
+                ty => {
+                    let [ty] = ty::normalize([&ty]);
+                    format!(
+                        "no method named '{}' found for a value of type {}",
+                        method_call.name, ty
+                    )
+                }
+            };
+
+            self.error(message, span);
+            return self.fresh_var();
+        };
+
+        let receiver = (receiver, self.span(method_call.receiver));
+        self.apply_method(&method, method_call.name.clone(), receiver, &args, span)
+    }
+
+    /// The method `name` of the impl for `ty`, which has to be zonked already.
+    fn find_method(&self, ty: &Type, name: Symbol) -> Option<MethodInfo> {
+        type_name(ty)
+            .and_then(|type_name| self.lookup_method(type_name, name))
+            .cloned()
+    }
+
+    /// Checks a call of `method` on `receiver` with `args`, each paired with its span, returning
+    /// the type of the result.
+    fn apply_method(
+        &mut self,
+        method: &MethodInfo,
+        name: Symbol,
+        receiver: (Type, TextRange),
+        args: &[(Type, TextRange)],
+        span: TextRange,
+    ) -> Type {
+        let substitution = method
+            .params
+            .iter()
+            .map(|id| (*id, self.fresh_var()))
+            .collect();
+
+        let target = method.target.replace_params(&substitution);
+        self.expect(&target, &receiver.0, receiver.1);
+
+        let Type::Fn { params, ret } = method.ty.replace_params(&substitution) else {
+            unreachable!("methods have function types")
+        };
+
+        if params.len() - 1 != args.len() {
+            self.error(
+                format!(
+                    "method '{}' takes {} argument(s), but {} were given",
+                    name,
+                    params.len() - 1,
+                    args.len()
+                ),
+                span,
+            );
+        } else {
+            for (param, (arg_ty, arg_span)) in params[1..].iter().zip(args) {
+                self.expect(param, arg_ty, *arg_span);
+            }
+        }
+
+        *ret
+    }
+
+    fn struct_lit(&mut self, struct_lit: &StructLit, span: TextRange) -> Type {
+        let Some(TypeDef::Struct(info)) = self.lookup_type(struct_lit.name.clone()) else {
+            self.error(format!("struct '{}' does not exist", struct_lit.name), span);
+
+            for field in &struct_lit.fields {
+                self.expr(field.val);
+            }
+
+            return self.fresh_var();
+        };
+
+        let (args, fields) = self.instantiate_struct(&info);
+        let mut initialized: Vec<&str> = Vec::new();
+
+        for field in &struct_lit.fields {
+            let ty = self.expr(field.val);
+
+            if initialized.contains(&field.name.as_str()) {
+                self.error(
+                    format!("field '{}' is specified more than once", field.name),
+                    field.span,
+                );
+                continue;
+            }
+
+            match fields.iter().find(|(name, _)| *name == field.name) {
+                Some((_, field_ty)) => self.expect(field_ty, &ty, self.span(field.val)),
+                None => self.error(
+                    format!("struct '{}' has no field '{}'", struct_lit.name, field.name),
+                    field.span,
+                ),
+            }
+
+            initialized.push(&field.name);
+        }
+
+        for (name, _) in &fields {
+            if !initialized.contains(&name.as_str()) {
+                self.error(
+                    format!("missing field '{}' in struct '{}'", name, struct_lit.name),
+                    span,
+                );
+            }
+        }
+
+        Type::Named {
+            name: struct_lit.name.clone(),
+            args,
+        }
+    }
+
+    /// Unlike everything else, field access needs the type of `base` to be known already, as
+    /// the same field name can belong to several structs.
+    fn field(&mut self, base: ExprId, name: &str, span: TextRange) -> Type {
+        let base_ty = self.expr(base);
+
+        let error = match self.zonk(&base_ty) {
+            Type::Named {
+                name: struct_name,
+                args,
+            } => match self.lookup_type(struct_name.clone()) {
+                Some(TypeDef::Struct(info)) => {
+                    let substitution = info.params.iter().copied().zip(args).collect();
+
+                    match info.fields.iter().find(|(field, _)| field == name) {
+                        Some((_, ty)) => return ty.replace_params(&substitution),
+                        None => format!("struct '{}' has no field '{}'", struct_name, name),
+                    }
+                }
+                _ => {
+                    let [ty] = ty::normalize([&self.zonk(&base_ty)]);
+                    format!("cannot access field '{}' on a value of type {}", name, ty)
+                }
+            },
+            Type::Var(_) => format!(
+                "the type of this value must be known to access its field '{}'",
+                name
+            ),
+            ty => {
+                let [ty] = ty::normalize([&ty]);
+                format!("cannot access field '{}' on a value of type {}", name, ty)
+            }
+        };
+
+        self.error(error, span);
+        self.fresh_var()
+    }
+
+    fn if_expr(&mut self, if_expr: &If, span: TextRange) -> Type {
+        let condition = self.expr(if_expr.condition);
+        self.expect(&Type::Bool, &condition, self.span(if_expr.condition));
+
+        let then_ty = self.block(&if_expr.then_branch);
+
+        match if_expr.else_branch {
+            Some(else_branch) => {
+                let else_ty = self.expr(else_branch);
+                self.expect(&then_ty, &else_ty, self.span(else_branch));
+
+                then_ty
+            }
+            None => {
+                let span = self.block_result_span(&if_expr.then_branch).unwrap_or(span);
+                self.expect(&Type::Unit, &then_ty, span);
+
+                Type::Unit
+            }
+        }
+    }
+
+    fn call(&mut self, call: &Call, span: TextRange) -> Type {
+        let callee = self.expr(call.callee);
+        let args: Vec<_> = call.args.iter().map(|&arg| self.expr(arg)).collect();
+
+        match self.resolve(&callee) {
+            Type::Fn { params, ret } => {
+                if params.len() != args.len() {
+                    self.error(
+                        format!(
+                            "function takes {} argument(s), but {} were given",
+                            params.len(),
+                            args.len()
+                        ),
+                        span,
+                    );
+                } else {
+                    for ((param, arg_ty), arg) in params.iter().zip(&args).zip(&call.args) {
+                        self.expect(param, arg_ty, self.span(*arg));
+                    }
+                }
+
+                *ret
+            }
+            Type::Var(_) => {
+                let ret = self.fresh_var();
+                let fn_ty = Type::Fn {
+                    params: args,
+                    ret: Box::new(ret.clone()),
+                };
+
+                self.expect(&fn_ty, &callee, self.span(call.callee));
+
+                ret
+            }
+            ty => {
+                let ty = self.zonk(&ty);
+                self.error(
+                    format!("cannot call a value of type {}", ty),
+                    self.span(call.callee),
+                );
+
+                self.fresh_var()
+            }
+        }
+    }
+
+    /// The type an annotation stands for.
+    fn type_expr(&mut self, ty: &TypeExpr) -> Type {
+        match &ty.kind {
+            TypeExprKind::Named { name, args } => {
+                let arg_tys: Vec<_> = args.iter().map(|arg| self.type_expr(arg)).collect();
+
+                let (expected_args, resolved) =
+                    match (self.lookup_type(name.clone()), name.as_str()) {
+                        (Some(TypeDef::Param(param)), _) => (0, param),
+                        (Some(TypeDef::Struct(info)), _) => (
+                            info.params.len(),
+                            Type::Named {
+                                name: name.clone(),
+                                args: arg_tys,
+                            },
+                        ),
+                        (None, "Int") => (0, Type::Int),
+                        (None, "Bool") => (0, Type::Bool),
+                        (None, "Str") => (0, Type::Str),
+                        (None, "Unit") => (0, Type::Unit),
+                        (None, "List") => (
+                            1,
+                            Type::list(arg_tys.first().cloned().unwrap_or(Type::Unit)),
+                        ),
+                        (None, "Option") => (
+                            1,
+                            Type::option(arg_tys.first().cloned().unwrap_or(Type::Unit)),
+                        ),
+                        (None, "Result") => {
+                            let mut arg_tys = arg_tys.into_iter();
+                            let mut next = || arg_tys.next().unwrap_or(Type::Unit);
+
+                            (2, Type::result(next(), next()))
+                        }
+                        (None, _) => {
+                            self.error(format!("unknown type '{}'", name), ty.span);
+                            return self.fresh_var();
+                        }
+                    };
+
+                if args.len() != expected_args {
+                    self.error(
+                        format!(
+                            "type '{}' takes {} type argument(s), but {} were given",
+                            name,
+                            expected_args,
+                            args.len()
+                        ),
+                        ty.span,
+                    );
+                    return self.fresh_var();
+                }
+
+                resolved
+            }
+            TypeExprKind::Fn { params, ret } => Type::Fn {
+                params: params.iter().map(|param| self.type_expr(param)).collect(),
+                ret: Box::new(self.type_expr(ret)),
+            },
+        }
+    }
+
+    fn annotation_or_fresh_var(&mut self, annotation: Option<&TypeExpr>) -> Type {
+        match annotation {
+            Some(annotation) => self.type_expr(annotation),
+            None => self.fresh_var(),
+        }
+    }
+
+    fn lookup(&self, name: Symbol) -> Option<Scheme> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.values.get(&name))
+            .or_else(|| self.env.bindings.get(&name))
+            .cloned()
+            .or_else(|| builtins::scheme(&name))
+    }
+
+    fn lookup_type(&self, name: Symbol) -> Option<TypeDef> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.types.get(&name))
+            .cloned()
+            .or_else(|| self.env.structs.get(&name).cloned().map(TypeDef::Struct))
+    }
+
+    fn lookup_trait(&self, name: Symbol) -> Option<&TraitInfo> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.traits.get(&name))
+            .or_else(|| self.env.traits.get(&name))
+    }
+
+    fn define_trait(&mut self, name: Symbol, info: TraitInfo) {
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.traits.insert(name, info);
+            }
+            None => {
+                self.env.traits.insert(name, info);
+            }
+        }
+    }
+
+    fn lookup_method(&self, type_name: Symbol, name: Symbol) -> Option<&MethodInfo> {
+        let key = (type_name, name);
+
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.methods.get(&key))
+            .or_else(|| self.env.methods.get(&key))
+    }
+
+    fn define_method(&mut self, type_name: Symbol, name: Symbol, info: MethodInfo) {
+        let key = (type_name, name);
+
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.methods.insert(key, info);
+            }
+            None => {
+                self.env.methods.insert(key, info);
+            }
+        }
+    }
+
+    fn define_struct(&mut self, name: Symbol, info: StructInfo) {
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.types.insert(name, TypeDef::Struct(info));
+            }
+            None => {
+                self.env.structs.insert(name, info);
+            }
+        }
+    }
+
+    /// Replaces the type parameters of a struct by fresh variables, returning them along with
+    /// the resulting field types.
+    fn instantiate_struct(&mut self, info: &StructInfo) -> (Vec<Type>, Vec<(Symbol, Type)>) {
+        let args: Vec<_> = info.params.iter().map(|_| self.fresh_var()).collect();
+        let substitution = info.params.iter().copied().zip(args.clone()).collect();
+
+        let fields = info
+            .fields
+            .iter()
+            .map(|(name, ty)| (name.clone(), ty.replace_params(&substitution)))
+            .collect();
+
+        (args, fields)
+    }
+
+    fn define(&mut self, name: Symbol, scheme: Scheme) {
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.values.insert(name, scheme);
+            }
+            // Top-level bindings outlive this inference, so they must not refer to its variables.
+            None => {
+                let ty = self.zonk(&scheme.ty);
+                let substitution = scheme
+                    .vars
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, var)| (*var, Type::Var(TypeVar(idx))))
+                    .collect();
+
+                let scheme = Scheme {
+                    vars: (0..scheme.vars.len()).map(TypeVar).collect(),
+                    ty: ty.substitute(&substitution),
+                };
+
+                self.env.bindings.insert(name, scheme);
+            }
+        }
+    }
+
+    fn fresh_var(&mut self) -> Type {
+        let var = TypeVar(self.vars.len());
+        self.vars.push(VarState::Unbound { level: self.level });
+
+        Type::Var(var)
+    }
+
+    #[inline]
+    fn span(&self, expr: ExprId) -> TextRange {
+        self.arena[expr].span
+    }
+
+    /// The span of the statement that gives `block` its value, if there is one.
+    fn block_result_span(&self, block: &Block) -> Option<TextRange> {
+        block
+            .stmts
+            .last()
+            .map(|&stmt| self.arena[stmt].span(self.arena))
+    }
+
+    fn error(&mut self, message: String, span: TextRange) {
+        self.errors.push(TypeError::new(message, span));
+    }
+
+    /// Unifies `found` with `expected`, reporting a mismatch at `span`.
+    fn expect(&mut self, expected: &Type, found: &Type, span: TextRange) {
+        match self.unify(expected, found) {
+            Ok(()) => {}
+            Err(UnifyError::Mismatch) => {
+                let [expected, found] = ty::normalize([&self.zonk(expected), &self.zonk(found)]);
+
+                self.error(
+                    format!("mismatched types: expected {}, found {}", expected, found),
+                    span,
+                );
+            }
+            Err(UnifyError::InfiniteType(var, ty)) => {
+                let [var, ty] = ty::normalize([&Type::Var(var), &self.zonk(&ty)]);
+
+                self.error(
+                    format!("cannot construct the infinite type {} = {}", var, ty),
+                    span,
+                );
+            }
+        }
+    }
+
+    fn unify(&mut self, lhs: &Type, rhs: &Type) -> Result<(), UnifyError> {
+        match (self.resolve(lhs), self.resolve(rhs)) {
+            (Type::Var(lhs), Type::Var(rhs)) if lhs == rhs => Ok(()),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => self.bind(var, ty),
+            (Type::Int, Type::Int)
+            | (Type::Bool, Type::Bool)
+            | (Type::Str, Type::Str)
+            | (Type::Unit, Type::Unit) => Ok(()),
+            (
+                Type::Fn {
+                    params: lhs_params,
+                    ret: lhs_ret,
+                },
+                Type::Fn {
+                    params: rhs_params,
+                    ret: rhs_ret,
+                },
+            ) if lhs_params.len() == rhs_params.len() => {
+                for (lhs, rhs) in lhs_params.iter().zip(&rhs_params) {
+                    self.unify(lhs, rhs)?;
+                }
+
+                self.unify(&lhs_ret, &rhs_ret)
+            }
+            (
+                Type::Named {
+                    name: lhs_name,
+                    args: lhs_args,
+                },
+                Type::Named {
+                    name: rhs_name,
+                    args: rhs_args,
+                },
+            ) if lhs_name == rhs_name && lhs_args.len() == rhs_args.len() => {
+                for (lhs, rhs) in lhs_args.iter().zip(&rhs_args) {
+                    self.unify(lhs, rhs)?;
+                }
+
+                Ok(())
+            }
+            (Type::Param { id: lhs, .. }, Type::Param { id: rhs, .. }) if lhs == rhs => Ok(()),
+            _ => Err(UnifyError::Mismatch),
+        }
+    }
+
+    fn bind(&mut self, var: TypeVar, ty: Type) -> Result<(), UnifyError> {
+        let ty = self.zonk(&ty);
+        let vars = ty.vars();
+
+        if vars.contains(&var) {
+            return Err(UnifyError::InfiniteType(var, ty));
+        }
+
+        // The variables of `ty` now also belong to wherever `var` was created, so they mustn't
+        // be generalized any earlier than `var` could be.
+        let VarState::Unbound { level } = self.vars[var.0] else {
+            unreachable!("only unbound variables are bound")
+        };
+
+        for other in vars {
+            if let VarState::Unbound { level: other_level } = &mut self.vars[other.0] {
+                *other_level = (*other_level).min(level);
+            }
+        }
+
+        self.vars[var.0] = VarState::Bound(ty);
+
+        Ok(())
+    }
+
+    /// Follows bound variables until reaching a type that isn't one.
+    fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Var(var) => match &self.vars[var.0] {
+                VarState::Bound(ty) => self.resolve(ty),
+                VarState::Unbound { .. } => ty.clone(),
+            },
+            ty => ty.clone(),
+        }
+    }
+
+    /// Replaces every bound variable in `ty`, however deeply nested, by what it is bound to.
+    fn zonk(&self, ty: &Type) -> Type {
+        match self.resolve(ty) {
+            Type::Fn { params, ret } => Type::Fn {
+                params: params.iter().map(|param| self.zonk(param)).collect(),
+                ret: Box::new(self.zonk(&ret)),
+            },
+            Type::Named { name, args } => Type::Named {
+                name,
+                args: args.iter().map(|arg| self.zonk(arg)).collect(),
+            },
+            ty => ty,
+        }
+    }
+
+    fn generalize(&self, ty: &Type) -> Scheme {
+        let ty = self.zonk(ty);
+
+        let vars = ty
+            .vars()
+            .into_iter()
+            .filter(
+                |var| matches!(self.vars[var.0], VarState::Unbound { level } if level > self.level),
+            )
+            .collect();
+
+        Scheme { vars, ty }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let substitution = scheme
+            .vars
+            .iter()
+            .map(|var| (*var, self.fresh_var()))
+            .collect();
+
+        scheme.ty.substitute(&substitution)
+    }
+}
+
+/// The name impls for `ty` are found by, which is the same as the name of its values at runtime.
+fn type_name(ty: &Type) -> Option<Symbol> {
+    match ty {
+        Type::Int => Some(symbol::INT.clone()),
+        Type::Bool => Some(symbol::BOOL.clone()),
+        Type::Str => Some(symbol::STR.clone()),
+        Type::Unit => Some(symbol::UNIT.clone()),
+        Type::Named { name, .. } => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// Groups the functions of a group that call each other, directly or not, into strongly connected
+/// components, as indices into `fn_defs`. Each component comes after those it calls.
+///
+/// Any usage of a function's name counts as a call, even if an inner binding shadows it, which at
+/// worst makes functions share a component that didn't need to.
+fn call_components(arena: &Arena, fn_defs: &[&FnDef]) -> Vec<Vec<usize>> {
+    struct Names(Vec<Symbol>);
+
+    impl Visitor for Names {
+        fn visit_binding_usage(&mut self, binding_usage: &BindingUsage) {
+            self.0.push(binding_usage.name.clone());
+        }
+    }
+
+    // Of functions with the same name, the last one is the one the name refers to.
+    let calls: Vec<Vec<usize>> = fn_defs
+        .iter()
+        .map(|fn_def| {
+            let mut names = Names(Vec::new());
+            names.visit_block(arena, &fn_def.body);
+
+            let mut callees: Vec<_> = names
+                .0
+                .iter()
+                .filter_map(|name| fn_defs.iter().rposition(|callee| callee.name == *name))
+                .collect();
+            callees.sort_unstable();
+            callees.dedup();
+
+            callees
+        })
+        .collect();
+
+    // Tarjan's algorithm, which finds the components callees first.
+    struct Search<'a> {
+        calls: &'a [Vec<usize>],
+        /// The order each function was reached in, and the earliest one reachable from it.
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl Search<'_> {
+        fn visit(&mut self, fn_idx: usize) {
+            self.index[fn_idx] = Some(self.next);
+            self.low[fn_idx] = self.next;
+            self.next += 1;
+            self.stack.push(fn_idx);
+            self.on_stack[fn_idx] = true;
+
+            for &callee in &self.calls[fn_idx] {
+                match self.index[callee] {
+                    None => {
+                        self.visit(callee);
+                        self.low[fn_idx] = self.low[fn_idx].min(self.low[callee]);
+                    }
+                    Some(index) if self.on_stack[callee] => {
+                        self.low[fn_idx] = self.low[fn_idx].min(index);
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            if Some(self.low[fn_idx]) == self.index[fn_idx] {
+                let mut component = Vec::new();
+
+                loop {
+                    let member = self.stack.pop().unwrap();
+                    self.on_stack[member] = false;
+                    component.push(member);
+
+                    if member == fn_idx {
+                        break;
+                    }
+                }
+
+                component.sort_unstable();
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut search = Search {
+        calls: &calls,
+        index: vec![None; fn_defs.len()],
+        low: vec![0; fn_defs.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; fn_defs.len()],
+        next: 0,
+        components: Vec::new(),
+    };
+
+    for fn_idx in 0..fn_defs.len() {
+        if search.index[fn_idx].is_none() {
+            search.visit(fn_idx);
+        }
+    }
+
+    search.components
+}
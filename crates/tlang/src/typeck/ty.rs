@@ -0,0 +1,303 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::symbol::{self, Symbol};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Type {
+    Int,
+    Bool,
+    Str,
+    Unit,
+    /// `fn(params) -> ret`
+    Fn {
+        params: Vec<Type>,
+        ret: Box<Type>,
+    },
+    /// A struct or a built-in type like `List` or `Option`, applied to its type arguments.
+    Named {
+        name: Symbol,
+        args: Vec<Type>,
+    },
+    /// A type parameter within the generic definition that declares it, where it stands for an
+    /// unknown but fixed type, so it only unifies with itself.
+    Param {
+        name: Symbol,
+        id: usize,
+    },
+    Var(TypeVar),
+}
+
+/// A type that isn't known (yet), printed as `'a`, `'b`, ….
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeVar(pub(crate) usize);
+
+impl Type {
+    #[inline]
+    pub(crate) fn list(element: Type) -> Self {
+        Type::Named {
+            name: symbol::LIST.clone(),
+            args: vec![element],
+        }
+    }
+
+    #[inline]
+    pub(crate) fn option(value: Type) -> Self {
+        Type::Named {
+            name: symbol::OPTION.clone(),
+            args: vec![value],
+        }
+    }
+
+    #[inline]
+    pub(crate) fn result(ok: Type, err: Type) -> Self {
+        Type::Named {
+            name: symbol::RESULT.clone(),
+            args: vec![ok, err],
+        }
+    }
+
+    #[inline]
+    pub(crate) fn func(params: impl Into<Vec<Type>>, ret: Type) -> Self {
+        Type::Fn {
+            params: params.into(),
+            ret: Box::new(ret),
+        }
+    }
+
+    /// Every type variable in the type, in order of first appearance.
+    pub(crate) fn vars(&self) -> Vec<TypeVar> {
+        let mut vars = Vec::new();
+        self.collect_vars(&mut vars);
+
+        vars
+    }
+
+    fn collect_vars(&self, vars: &mut Vec<TypeVar>) {
+        match self {
+            Type::Fn { params, ret } => {
+                for param in params {
+                    param.collect_vars(vars);
+                }
+
+                ret.collect_vars(vars);
+            }
+            Type::Named { args, .. } => {
+                for arg in args {
+                    arg.collect_vars(vars);
+                }
+            }
+            Type::Var(var) if !vars.contains(var) => vars.push(*var),
+            _ => {}
+        }
+    }
+
+    /// Replaces the variables that are keys of `substitution`, leaving all others in place.
+    pub(crate) fn substitute(&self, substitution: &HashMap<TypeVar, Type>) -> Type {
+        match self {
+            Type::Fn { params, ret } => Type::Fn {
+                params: params
+                    .iter()
+                    .map(|param| param.substitute(substitution))
+                    .collect(),
+                ret: Box::new(ret.substitute(substitution)),
+            },
+            Type::Named { name, args } => Type::Named {
+                name: name.clone(),
+                args: args
+                    .iter()
+                    .map(|arg| arg.substitute(substitution))
+                    .collect(),
+            },
+            Type::Var(var) => substitution.get(var).cloned().unwrap_or(Type::Var(*var)),
+            ty => ty.clone(),
+        }
+    }
+
+    /// Replaces the type parameters whose ids are keys of `substitution`.
+    pub(crate) fn replace_params(&self, substitution: &HashMap<usize, Type>) -> Type {
+        match self {
+            Type::Fn { params, ret } => Type::Fn {
+                params: params
+                    .iter()
+                    .map(|param| param.replace_params(substitution))
+                    .collect(),
+                ret: Box::new(ret.replace_params(substitution)),
+            },
+            Type::Named { name, args } => Type::Named {
+                name: name.clone(),
+                args: args
+                    .iter()
+                    .map(|arg| arg.replace_params(substitution))
+                    .collect(),
+            },
+            Type::Param { id, .. } => substitution
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| self.clone()),
+            ty => ty.clone(),
+        }
+    }
+
+    /// Whether the type parameter `id` occurs anywhere in the type.
+    pub(crate) fn contains_param(&self, id: usize) -> bool {
+        match self {
+            Type::Fn { params, ret } => {
+                params.iter().any(|param| param.contains_param(id)) || ret.contains_param(id)
+            }
+            Type::Named { args, .. } => args.iter().any(|arg| arg.contains_param(id)),
+            Type::Param { id: other, .. } => *other == id,
+            _ => false,
+        }
+    }
+}
+
+/// Renames the variables of `types` to `'a`, `'b`, … in order of first appearance, which makes
+/// the types independent of how many variables inference happened to create.
+pub(crate) fn normalize<const N: usize>(types: [&Type; N]) -> [Type; N] {
+    let mut substitution = HashMap::new();
+
+    for ty in types {
+        for var in ty.vars() {
+            let len = substitution.len();
+            substitution.entry(var).or_insert(Type::Var(TypeVar(len)));
+        }
+    }
+
+    types.map(|ty| ty.substitute(&substitution))
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Str => write!(f, "Str"),
+            Type::Unit => write!(f, "Unit"),
+            Type::Fn { params, ret } => {
+                write!(f, "fn(")?;
+
+                for (idx, param) in params.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}", param)?;
+                }
+
+                write!(f, ") -> {}", ret)
+            }
+            Type::Named { name, args } => {
+                write!(f, "{}", name)?;
+
+                if !args.is_empty() {
+                    write!(f, "<")?;
+
+                    for (idx, arg) in args.iter().enumerate() {
+                        if idx != 0 {
+                            write!(f, ", ")?;
+                        }
+
+                        write!(f, "{}", arg)?;
+                    }
+
+                    write!(f, ">")?;
+                }
+
+                Ok(())
+            }
+            Type::Param { name, .. } => write!(f, "{}", name),
+            Type::Var(var) => write!(f, "{}", var),
+        }
+    }
+}
+
+impl Display for TypeVar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let letter = (b'a' + (self.0 % 26) as u8) as char;
+
+        match self.0 / 26 {
+            0 => write!(f, "'{}", letter),
+            n => write!(f, "'{}{}", letter, n),
+        }
+    }
+}
+
+/// A possibly polymorphic type: `vars` stand for any type, and get replaced by fresh variables
+/// wherever a binding with this type is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scheme {
+    pub(crate) vars: Vec<TypeVar>,
+    pub(crate) ty: Type,
+}
+
+impl Scheme {
+    /// A scheme without any quantified variables.
+    #[inline]
+    pub(crate) fn mono(ty: Type) -> Self {
+        Self {
+            vars: Vec::new(),
+            ty,
+        }
+    }
+
+    #[inline]
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+
+    #[inline]
+    pub fn vars(&self) -> &[TypeVar] {
+        &self.vars
+    }
+}
+
+/// Quantified variables aren't printed, so `fn('a) -> 'a` means "for all `'a`".
+impl Display for Scheme {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.ty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_fn() {
+        let ty = Type::Fn {
+            params: vec![Type::Int, Type::Var(TypeVar(27))],
+            ret: Box::new(Type::Unit),
+        };
+
+        assert_eq!(ty.to_string(), "fn(Int, 'b1) -> Unit");
+    }
+
+    #[test]
+    fn display_named() {
+        let ty = Type::Named {
+            name: "Pair".into(),
+            args: vec![
+                Type::list(Type::Int),
+                Type::Param {
+                    name: "T".into(),
+                    id: 0,
+                },
+            ],
+        };
+
+        assert_eq!(ty.to_string(), "Pair<List<Int>, T>");
+    }
+
+    #[test]
+    fn normalize_renames_vars_in_order() {
+        let [lhs, rhs] = normalize([&Type::Var(TypeVar(7)), &Type::Var(TypeVar(3))]);
+
+        assert_eq!(lhs, Type::Var(TypeVar(0)));
+        assert_eq!(rhs, Type::Var(TypeVar(1)));
+    }
+}
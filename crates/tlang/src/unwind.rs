@@ -0,0 +1,267 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{limits::Limit, syntax::TextRange, val::Val};
+
+/// Why the evaluation of an expression stopped before producing a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Unwind {
+    /// A runtime error, which stops evaluation altogether.
+    Error {
+        kind: ErrorKind,
+        message: String,
+        trace: Trace,
+    },
+    /// `?` applied to `None` or an `Err`, which is returned from the enclosing function.
+    Return(Val),
+    /// A `throw`n value, which is passed on until a `try` expression catches it.
+    Throw { val: Val, trace: Trace },
+}
+
+/// Where an error happened or a value was thrown, followed by the calls it has been passed out
+/// of since, innermost first.
+///
+/// A trace starts out empty, and the first expression or statement the error passes through
+/// gives it its innermost frame. That is the smallest part of the input the error can be pinned
+/// to, as most errors are created without knowing where they happen.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Trace(Vec<Frame>);
+
+impl Trace {
+    /// Pins the error to `span` unless it has been already.
+    fn locate(&mut self, span: TextRange) {
+        if self.0.is_empty() {
+            self.0.push(Frame {
+                function: None,
+                span,
+            });
+        }
+    }
+
+    /// Records that the error is passed on out of `function`, which was called at `call_site`.
+    fn leave(&mut self, function: &str, call_site: TextRange) {
+        if let Some(frame) = self.0.last_mut() {
+            frame.function = Some(function.to_string());
+        }
+
+        self.0.push(Frame {
+            function: None,
+            span: call_site,
+        });
+    }
+}
+
+impl Unwind {
+    #[inline]
+    pub(crate) fn throw(val: Val) -> Self {
+        Unwind::Throw {
+            val,
+            trace: Trace::default(),
+        }
+    }
+
+    fn trace_mut(&mut self) -> Option<&mut Trace> {
+        match self {
+            Unwind::Error { trace, .. } | Unwind::Throw { trace, .. } => Some(trace),
+            Unwind::Return(_) => None,
+        }
+    }
+
+    /// Pins the error to `span` unless it has been already.
+    pub(crate) fn located(mut self, span: TextRange) -> Self {
+        if let Some(trace) = self.trace_mut() {
+            trace.locate(span);
+        }
+
+        self
+    }
+
+    /// Records that the error is passed on out of `function`, which was called at `call_site`.
+    /// `span` is where in `function` it happened, if it hasn't been pinned to anything within it.
+    pub(crate) fn leaving(mut self, function: &str, span: TextRange, call_site: TextRange) -> Self {
+        if let Some(trace) = self.trace_mut() {
+            trace.locate(span);
+            trace.leave(function, call_site);
+        }
+
+        self
+    }
+
+    /// What to report when this reaches the top level, outside of any function.
+    pub(crate) fn into_error(self) -> EvalError {
+        match self {
+            Unwind::Error {
+                kind,
+                message,
+                trace,
+            } => EvalError {
+                kind,
+                message,
+                trace: trace.0,
+            },
+            Unwind::Return(_) => EvalError {
+                kind: ErrorKind::Runtime,
+                message: "the '?' operator can only be used inside a function".to_string(),
+                trace: Vec::new(),
+            },
+            Unwind::Throw { val, trace } => EvalError {
+                kind: ErrorKind::Runtime,
+                message: format!("uncaught exception: {}", val),
+                trace: trace.0,
+            },
+        }
+    }
+}
+
+impl From<String> for Unwind {
+    #[inline]
+    fn from(message: String) -> Self {
+        Unwind::Error {
+            kind: ErrorKind::Runtime,
+            message,
+            trace: Trace::default(),
+        }
+    }
+}
+
+/// Why evaluating an input failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Where the error happened, followed by the calls it was passed out of, innermost first. Of
+    /// a long chain of tail calls, only the first call and the innermost ones are kept.
+    pub trace: Vec<Frame>,
+}
+
+/// Whether an [`EvalError`] is due to the input itself, or to evaluation being stopped from the
+/// outside.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// An operation failed, a thrown value wasn't caught, or the input couldn't be evaluated at
+    /// all.
+    Runtime,
+    /// Evaluation used up one of the [`Limits`](crate::limits::Limits) of the environment.
+    LimitExceeded(Limit),
+    /// Evaluation was stopped with an [`InterruptHandle`](crate::InterruptHandle).
+    Interrupted,
+}
+
+/// A location in a trace: a span within a function, or within the top level if `function` is
+/// `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub function: Option<String>,
+    pub span: TextRange,
+}
+
+/// Only shows the message; the trace refers to the input by byte offsets, which are best turned
+/// into lines and columns by whoever has the input at hand.
+impl Display for EvalError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Env;
+
+    /// The message of the error `s` fails with, and its trace with each frame shown as the
+    /// function's name and the part of `s` the frame refers to.
+    fn trace(s: &str) -> (String, Vec<String>) {
+        let error = crate::parse(s)
+            .eval_traced(&mut Env::default())
+            .unwrap_err();
+
+        let frames = error
+            .trace
+            .iter()
+            .map(|frame| {
+                format!(
+                    "{}: {}",
+                    frame.function.as_deref().unwrap_or("<top level>"),
+                    &s[frame.span.start..frame.span.end]
+                )
+            })
+            .collect();
+
+        (error.message, frames)
+    }
+
+    #[test]
+    fn trace_of_error_in_nested_calls() {
+        assert_eq!(
+            trace("fn div(a, b) { { a / b } }\nfn half(n) { div(n, 0) }\nlet a = half(1)"),
+            (
+                "division by zero".to_string(),
+                vec![
+                    "div: a / b".to_string(),
+                    "half: div(n, 0)".to_string(),
+                    "<top level>: half(1)".to_string(),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn tail_calls_show_up_in_trace() {
+        assert_eq!(
+            trace("fn f(n) -> Int { if n == 0 { 1 / 0 } else { f(n - 1) } }\nf(2)"),
+            (
+                "division by zero".to_string(),
+                vec![
+                    "f: 1 / 0".to_string(),
+                    "f: f(n - 1)".to_string(),
+                    "f: f(n - 1)".to_string(),
+                    "<top level>: f(2)".to_string(),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn trace_of_error_at_top_level() {
+        assert_eq!(
+            trace("let a: Int = true"),
+            (
+                "in binding 'a': expected Int, but found Bool".to_string(),
+                vec!["<top level>: let a: Int = true".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn argument_errors_are_located_at_the_call() {
+        assert_eq!(
+            trace("fn f(a: Int) { a }\nfn g() { f(true) }\ng()"),
+            (
+                "in argument 'a' of function 'f': expected Int, but found Bool".to_string(),
+                vec!["g: f(true)".to_string(), "<top level>: g()".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn return_value_errors_are_located_at_the_return_type() {
+        assert_eq!(
+            trace("fn f() -> Int { true }\nf()"),
+            (
+                "in return value of function 'f': expected Int, but found Bool".to_string(),
+                vec!["f: Int".to_string(), "<top level>: f()".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn operator_methods_show_up_in_trace() {
+        assert_eq!(
+            trace("trait Add { fn add(self, other: Str) -> Str }\nimpl Add for Str { fn add(self, other) { 1 / 0 } }\n\"a\" + \"b\""),
+            (
+                "division by zero".to_string(),
+                vec!["add: 1 / 0".to_string(), "<top level>: \"a\" + \"b\"".to_string()]
+            )
+        );
+    }
+}
@@ -1,17 +1,116 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    rc::Rc,
+};
+
+pub use function::Function;
+pub(crate) use function::{Builtin, TailCalls};
+
+use crate::symbol::{self, Symbol};
+
+mod function;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Val {
     Number(i32),
+    Bool(bool),
+    Str(String),
+    Fn(Function),
+    List(Rc<[Val]>),
+    Struct(Rc<StructVal>),
+    /// `Some(val)` or `None`
+    Option(Option<Rc<Val>>),
+    /// `Ok(val)` or `Err(val)`
+    Result(Result<Rc<Val>, Rc<Val>>),
     Unit,
 }
 
+/// An instance of a struct, with its fields in the order of the struct's definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructVal {
+    pub name: Symbol,
+    pub fields: Vec<(Symbol, Val)>,
+}
+
+impl StructVal {
+    pub fn field(&self, name: Symbol) -> Option<&Val> {
+        self.fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, val)| val)
+    }
+}
+
+impl Val {
+    /// The name of the type of the value, as used in runtime error messages.
+    pub(crate) fn type_name(&self) -> Symbol {
+        match self {
+            Val::Number(_) => symbol::INT.clone(),
+            Val::Bool(_) => symbol::BOOL.clone(),
+            Val::Str(_) => symbol::STR.clone(),
+            Val::Fn(_) => symbol::FUNCTION.clone(),
+            Val::List(_) => symbol::LIST.clone(),
+            Val::Struct(instance) => instance.name.clone(),
+            Val::Option(_) => symbol::OPTION.clone(),
+            Val::Result(_) => symbol::RESULT.clone(),
+            Val::Unit => symbol::UNIT.clone(),
+        }
+    }
+}
+
 impl Display for Val {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Val::Number(n) => {
                 write!(f, "{}", n)
             }
+            Val::Bool(b) => {
+                write!(f, "{}", b)
+            }
+            Val::Str(s) => {
+                write!(f, "{:?}", s)
+            }
+            Val::Fn(function) => {
+                write!(f, "<fn {}>", function.name())
+            }
+            Val::List(elements) => {
+                write!(f, "[")?;
+
+                for (idx, element) in elements.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}", element)?;
+                }
+
+                write!(f, "]")
+            }
+            Val::Struct(instance) => {
+                write!(f, "{} {{ ", instance.name)?;
+
+                for (idx, (name, val)) in instance.fields.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}: {}", name, val)?;
+                }
+
+                write!(f, " }}")
+            }
+            Val::Option(Some(val)) => {
+                write!(f, "Some({})", val)
+            }
+            Val::Option(None) => {
+                write!(f, "None")
+            }
+            Val::Result(Ok(val)) => {
+                write!(f, "Ok({})", val)
+            }
+            Val::Result(Err(val)) => {
+                write!(f, "Err({})", val)
+            }
             Val::Unit => {
                 write!(f, "Unit")
             }
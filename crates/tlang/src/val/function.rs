@@ -0,0 +1,422 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Debug, Formatter},
+    rc::Rc,
+};
+
+use crate::{
+    ast::Arena,
+    env::Env,
+    expr::Tail,
+    fn_def::FnDef,
+    gc, limits,
+    syntax::TextRange,
+    unwind::Unwind,
+    val::Val,
+    vm::{self, Proto},
+};
+
+/// A function value: either the definition of the function together with the environment it was
+/// defined in, which is where bindings used by the body are looked up, or a built-in function.
+#[derive(Clone)]
+pub struct Function(Rc<Callable>);
+
+enum Callable {
+    Closure {
+        def: FnDef,
+        /// The arena the nodes of the body are in.
+        arena: Rc<Arena>,
+        env: Env,
+    },
+    /// A function defined in code run by the [VM](crate::vm), which runs its body too.
+    Compiled {
+        proto: Rc<Proto>,
+        env: Env,
+    },
+    Builtin(Builtin),
+}
+
+/// A function implemented in Rust, which is called with exactly `arity` arguments.
+pub(crate) struct Builtin {
+    pub(crate) name: &'static str,
+    pub(crate) arity: usize,
+    pub(crate) call: fn(Vec<Val>) -> Result<Val, Unwind>,
+}
+
+impl Function {
+    pub(crate) fn new(def: FnDef, arena: Rc<Arena>, env: Env) -> Self {
+        gc::track(&env);
+        Self(Rc::new(Callable::Closure { def, arena, env }))
+    }
+
+    #[inline]
+    pub(crate) fn compiled(proto: Rc<Proto>, env: Env) -> Self {
+        gc::track(&env);
+        Self(Rc::new(Callable::Compiled { proto, env }))
+    }
+
+    #[inline]
+    pub(crate) fn builtin(builtin: Builtin) -> Self {
+        Self(Rc::new(Callable::Builtin(builtin)))
+    }
+
+    /// The environment the function was defined in, unless it is built in.
+    #[inline]
+    pub(crate) fn env(&self) -> Option<&Env> {
+        match &*self.0 {
+            Callable::Closure { env, .. } | Callable::Compiled { env, .. } => Some(env),
+            Callable::Builtin(_) => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn addr(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+
+    #[inline]
+    pub(crate) fn strong_count(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        match &*self.0 {
+            Callable::Closure { def, .. } => &def.name,
+            Callable::Compiled { proto, .. } => &proto.def.name,
+            Callable::Builtin(builtin) => builtin.name,
+        }
+    }
+
+    #[inline]
+    pub fn arity(&self) -> usize {
+        match &*self.0 {
+            Callable::Closure { def, .. } => def.params.len(),
+            Callable::Compiled { proto, .. } => proto.def.params.len(),
+            Callable::Builtin(builtin) => builtin.arity,
+        }
+    }
+
+    /// Calls the function from `call_site`, which is recorded in the trace of errors passed out
+    /// of it.
+    ///
+    /// A call a function body ends with is made here rather than from within the body, so that
+    /// chains of tail calls, such as tail recursion, run in constant native stack space.
+    pub(crate) fn call(&self, args: Vec<Val>, call_site: TextRange) -> Result<Val, Unwind> {
+        limits::enter_call()?;
+        let result = self.call_chain(args, call_site);
+        limits::leave_call();
+
+        result
+    }
+
+    /// Makes the call, followed by the tail calls it ends with.
+    fn call_chain(&self, args: Vec<Val>, call_site: TextRange) -> Result<Val, Unwind> {
+        let mut tail_calls = TailCalls::default();
+        let (mut function, mut args, mut call_site) = (self.clone(), args, call_site);
+
+        loop {
+            let callable = Rc::clone(&function.0);
+
+            let result = match &*callable {
+                Callable::Closure { def, arena, env } => match function.enter(def, env, args) {
+                    Ok(env) => match def.body.eval_tail(arena, &env) {
+                        Ok(Tail::Call {
+                            function: callee,
+                            args: callee_args,
+                            call_site: callee_site,
+                        }) => {
+                            tail_calls.push(function, env, call_site);
+                            (function, args, call_site) = (callee, callee_args, callee_site);
+                            continue;
+                        }
+                        Ok(Tail::Val(val)) => function.leave(def, Ok(val), &env, call_site),
+                        Err(unwind) => function.leave(def, Err(unwind), &env, call_site),
+                    },
+                    Err(unwind) => Err(unwind),
+                },
+                Callable::Compiled { proto, env } => {
+                    function.enter(&proto.def, env, args).and_then(|env| {
+                        function.leave(
+                            &proto.def,
+                            vm::run(Rc::clone(&proto.chunk), env.clone()),
+                            &env,
+                            call_site,
+                        )
+                    })
+                }
+                Callable::Builtin(builtin) => function
+                    .check_arity(args.len())
+                    .map_err(Unwind::from)
+                    .and_then(|()| (builtin.call)(args)),
+            };
+
+            return tail_calls.leave(result, call_site);
+        }
+    }
+
+    /// The definition of a function that isn't built in.
+    fn def(&self) -> &FnDef {
+        match &*self.0 {
+            Callable::Closure { def, .. } => def,
+            Callable::Compiled { proto, .. } => &proto.def,
+            Callable::Builtin(builtin) => {
+                unreachable!("built-in function '{}' has no definition", builtin.name)
+            }
+        }
+    }
+
+    /// The compiled code of the function and the environment it was defined in, if it has been
+    /// compiled, so that the VM can call it without going through [`call`](Self::call).
+    #[inline]
+    pub(crate) fn as_compiled(&self) -> Option<(&Rc<Proto>, &Env)> {
+        match &*self.0 {
+            Callable::Compiled { proto, env } => Some((proto, env)),
+            _ => None,
+        }
+    }
+
+    fn check_arity(&self, args: usize) -> Result<(), String> {
+        if args == self.arity() {
+            Ok(())
+        } else {
+            Err(format!(
+                "function '{}' takes {} argument(s), but {} were given",
+                self.name(),
+                self.arity(),
+                args
+            ))
+        }
+    }
+
+    /// Creates the environment the body of `def` is evaluated in, a child of `env` with the
+    /// arguments bound to the parameters.
+    pub(crate) fn enter(&self, def: &FnDef, env: &Env, args: Vec<Val>) -> Result<Env, Unwind> {
+        self.check_arity(args.len())?;
+
+        let mut env = env.create_child();
+
+        for type_param in &def.type_params {
+            env.store_type_param(type_param.name.clone());
+        }
+
+        for (param, arg) in def.params.iter().zip(args) {
+            if let Some(ty) = &param.ty {
+                ty.check(&arg, &env).map_err(|msg| {
+                    format!(
+                        "in argument '{}' of function '{}': {}",
+                        param.name,
+                        self.name(),
+                        msg
+                    )
+                })?;
+            }
+
+            env.store_binding(param.name.clone(), arg);
+        }
+
+        Ok(env)
+    }
+
+    /// Turns the result of evaluating the body of `def` in `env` into the result of the call.
+    pub(crate) fn leave(
+        &self,
+        def: &FnDef,
+        result: Result<Val, Unwind>,
+        env: &Env,
+        call_site: TextRange,
+    ) -> Result<Val, Unwind> {
+        // `?` returns from the innermost function it is used in.
+        let ret = match result {
+            Ok(ret) | Err(Unwind::Return(ret)) => ret,
+            Err(unwind) => return Err(unwind.leaving(self.name(), def.span, call_site)),
+        };
+
+        if let Some(ty) = &def.ret_ty {
+            ty.check(&ret, env).map_err(|msg| {
+                Unwind::from(format!(
+                    "in return value of function '{}': {}",
+                    self.name(),
+                    msg
+                ))
+                .leaving(self.name(), ty.span, call_site)
+            })?;
+        }
+
+        Ok(ret)
+    }
+}
+
+/// The calls left by a chain of tail calls, which are only left for real once the last call of the
+/// chain returns: errors passed out of them get a trace then, and their return types are checked.
+///
+/// What is recorded takes up bounded space, however long the chain gets. The trace only keeps the
+/// first call and the [`TRACED_TAIL_CALLS`] innermost ones. The return type of a function is only
+/// checked for its innermost call, as all of its calls in the chain return the same value, and its
+/// type means the same in each of them.
+#[derive(Default)]
+pub(crate) struct TailCalls {
+    /// The number of calls in the chain so far.
+    len: usize,
+    first: Option<TailCall>,
+    /// Outermost first.
+    innermost: VecDeque<TailCall>,
+    /// The innermost call of each function with a return type, innermost last.
+    checks: Vec<TailCall>,
+}
+
+/// The number of innermost calls of a chain of tail calls that show up in the trace of errors
+/// passed out of it.
+pub(crate) const TRACED_TAIL_CALLS: usize = 64;
+
+#[derive(Clone)]
+struct TailCall {
+    function: Function,
+    /// The environment holding the arguments, which the return type is checked in.
+    env: Env,
+    call_site: TextRange,
+    /// How many calls of the chain were made before this one.
+    depth: usize,
+}
+
+impl TailCalls {
+    /// Records that `function`, called from `call_site` with its arguments in `env`, has made a
+    /// tail call.
+    pub(crate) fn push(&mut self, function: Function, env: Env, call_site: TextRange) {
+        let call = TailCall {
+            function,
+            env,
+            call_site,
+            depth: self.len,
+        };
+        self.len += 1;
+
+        if call.function.def().ret_ty.is_some() {
+            if let Some(idx) = self
+                .checks
+                .iter()
+                .rposition(|check| check.function == call.function)
+            {
+                self.checks.remove(idx);
+            }
+
+            self.checks.push(call.clone());
+        }
+
+        if self.first.is_none() {
+            self.first = Some(call);
+            return;
+        }
+
+        if self.innermost.len() == TRACED_TAIL_CALLS {
+            self.innermost.pop_front();
+        }
+
+        self.innermost.push_back(call);
+    }
+
+    /// Turns the result of the last call of the chain, made from `call_site`, into the result of
+    /// the first one. Errors that haven't been pinned to anything yet are pinned to `call_site`,
+    /// as the call expression would have done.
+    pub(crate) fn leave(
+        self,
+        mut result: Result<Val, Unwind>,
+        call_site: TextRange,
+    ) -> Result<Val, Unwind> {
+        if self.len == 0 {
+            return result;
+        }
+
+        result = result.map_err(|unwind| unwind.located(call_site));
+
+        // The calls left so far, which the trace of an error starts after.
+        let mut left = self.len;
+
+        for check in self.checks.into_iter().rev() {
+            if result.is_err() {
+                break;
+            }
+
+            let def = check.function.def();
+            result = check
+                .function
+                .leave(def, result, &check.env, check.call_site);
+            left = check.depth;
+        }
+
+        result.map_err(|mut unwind| {
+            let traced = self.innermost.into_iter().rev().chain(self.first);
+
+            for call in traced.filter(|call| call.depth < left) {
+                unwind = unwind.leaving(
+                    call.function.name(),
+                    call.function.def().span,
+                    call.call_site,
+                );
+            }
+
+            unwind
+        })
+    }
+}
+
+/// Functions are only equal to themselves, as there is no way to tell whether two different
+/// functions behave the same. Built-in functions are identified by their name.
+impl PartialEq for Function {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (&*self.0, &*other.0) {
+            (Callable::Builtin(lhs), Callable::Builtin(rhs)) => lhs.name == rhs.name,
+            _ => Rc::ptr_eq(&self.0, &other.0),
+        }
+    }
+}
+
+impl Eq for Function {}
+
+/// Only prints the name, as the captured environment usually contains the function itself.
+impl Debug for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Function").field(&self.name()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The functions the list `s` evaluates to.
+    fn functions(s: &str) -> Vec<Function> {
+        match crate::parse(s).eval(&mut Env::default()) {
+            Ok(Val::List(functions)) => functions
+                .iter()
+                .map(|function| match function {
+                    Val::Fn(function) => function.clone(),
+                    val => panic!("expected a function, found {:?}", val),
+                })
+                .collect(),
+            result => panic!("expected a list, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn tail_calls_take_up_bounded_space() {
+        let functions = functions("fn even(n) -> Bool { if n == 0 { true } else { odd(n - 1) } }\nfn odd(n) -> Bool { if n == 0 { false } else { even(n - 1) } }\nfn g(flip) { flip }\n[even, odd, g]");
+        let mut tail_calls = TailCalls::default();
+
+        for n in 0..100_000 {
+            let function = &functions[n % functions.len()];
+            let call_site = TextRange::new(n % 2, n % 2 + 1);
+            tail_calls.push(function.clone(), Env::default(), call_site);
+        }
+
+        assert_eq!(tail_calls.len, 100_000);
+        assert_eq!(tail_calls.innermost.len(), TRACED_TAIL_CALLS);
+        assert_eq!(tail_calls.checks.len(), 2);
+
+        assert_eq!(
+            tail_calls.leave(Ok(Val::Bool(true)), TextRange::default()),
+            Ok(Val::Bool(true))
+        );
+    }
+}
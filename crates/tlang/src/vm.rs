@@ -0,0 +1,669 @@
+//! A backend that compiles the [AST](crate::ast) to bytecode, which is then run by a stack-based
+//! virtual machine instead of walking the AST.
+//!
+//! Everything observable behaves exactly as with the tree walker: values, errors and their traces,
+//! and the environments bindings end up in. Functions defined by either backend can be called by
+//! the other, so the backend can be switched between the inputs of a session.
+
+use std::{mem, rc::Rc};
+
+use chunk::{Chunk, Instr};
+
+use crate::{
+    builtins,
+    env::Env,
+    expr::Expr,
+    fn_def::FnDef,
+    limits,
+    source_file::SourceFile,
+    syntax::TextRange,
+    unwind::{EvalError, Unwind},
+    val::{Function, TailCalls, Val},
+};
+
+mod chunk;
+mod compiler;
+
+/// A compiled function: its definition, which its parameters are checked against, and the
+/// bytecode of its body.
+#[derive(Debug)]
+pub(crate) struct Proto {
+    pub(crate) def: FnDef,
+    pub(crate) chunk: Rc<Chunk>,
+}
+
+/// Evaluates every statement of `source_file` directly in `env`, like
+/// [`SourceFile::eval`](crate::source_file::SourceFile) does.
+pub(crate) fn eval(source_file: &SourceFile, env: &Env) -> Result<Val, EvalError> {
+    let chunk = compiler::compile(source_file);
+
+    run(Rc::new(chunk), env.clone()).map_err(Unwind::into_error)
+}
+
+/// Runs `chunk` in `env`. Unwinding out of it is passed on to the caller, including `?`
+/// returning from the enclosing function.
+pub(crate) fn run(chunk: Rc<Chunk>, env: Env) -> Result<Val, Unwind> {
+    let mut vm = Vm {
+        stack: Vec::new(),
+        frames: vec![Frame::new(chunk, env, 0, None)],
+    };
+
+    vm.run()
+}
+
+struct Vm {
+    stack: Vec<Val>,
+    /// The chunks being run, with the innermost call last.
+    frames: Vec<Frame>,
+}
+
+struct Frame {
+    chunk: Rc<Chunk>,
+    ip: usize,
+    env: Env,
+    /// The environments of the blocks the current one is nested in, restored on leaving it.
+    scopes: Vec<Env>,
+    /// The height of the stack when the frame was entered.
+    stack_base: usize,
+    handlers: Vec<Handler>,
+    /// How the `try` expressions whose `finally` blocks are running completed, innermost last:
+    /// the unwinding they interrupted, or `None` if their bodies completed normally.
+    pending: Vec<Option<Unwind>>,
+    /// The call that created the frame, or `None` for the chunk the VM was started with.
+    call: Option<Call>,
+    /// The calls whose frames were replaced by this one, which are left once `call` is.
+    tail_calls: TailCalls,
+}
+
+struct Call {
+    function: Function,
+    proto: Rc<Proto>,
+    /// The environment holding the arguments, which the return type is checked in.
+    env: Env,
+    call_site: TextRange,
+}
+
+/// Where to continue when unwinding reaches a `try` expression, and the state of the frame to
+/// restore before doing so.
+struct Handler {
+    catch: bool,
+    target: usize,
+    stack_len: usize,
+    scopes_len: usize,
+    env: Env,
+    pending_len: usize,
+}
+
+impl Frame {
+    fn new(chunk: Rc<Chunk>, env: Env, stack_base: usize, call: Option<Call>) -> Self {
+        Self {
+            chunk,
+            ip: 0,
+            env,
+            scopes: Vec::new(),
+            stack_base,
+            handlers: Vec::new(),
+            pending: Vec::new(),
+            call,
+            tail_calls: TailCalls::default(),
+        }
+    }
+}
+
+impl Vm {
+    fn run(&mut self) -> Result<Val, Unwind> {
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            let instr = frame.chunk.code[frame.ip];
+            let span = frame.chunk.spans[frame.ip];
+            frame.ip += 1;
+
+            match limits::step().and_then(|()| self.exec(instr, span)) {
+                Ok(None) => {}
+                Ok(Some(val)) => return Ok(val),
+                Err(unwind) => self.unwind(unwind.located(span))?,
+            }
+        }
+    }
+
+    /// Runs `instr`, returning the value the VM was started for once it is done.
+    fn exec(&mut self, instr: Instr, span: TextRange) -> Result<Option<Val>, Unwind> {
+        let frame = self.frames.last_mut().unwrap();
+        let chunk = &frame.chunk;
+
+        match instr {
+            Instr::Const(idx) => {
+                let val = match &chunk.consts[idx as usize] {
+                    // Each evaluation of a string literal makes a new string.
+                    val @ Val::Str(_) => limits::allocated(val.clone())?,
+                    val => val.clone(),
+                };
+                self.stack.push(val);
+            }
+            Instr::Unit => self.stack.push(Val::Unit),
+            Instr::Pop => {
+                self.stack.pop().unwrap();
+            }
+            Instr::LoadSlot { depth, slot } => {
+                let val = frame.env.get_slot(depth as usize, slot as usize);
+                self.stack.push(val);
+            }
+            Instr::Load(name) => {
+                let val = builtins::lookup(chunk.names[name as usize].clone())?;
+                self.stack.push(val);
+            }
+            Instr::Store(name) => {
+                let val = self.stack.pop().unwrap();
+                frame
+                    .env
+                    .store_binding(chunk.names[name as usize].clone(), val);
+            }
+            Instr::CheckBinding { name, ty } => {
+                let val = self.stack.last().unwrap();
+
+                chunk.types[ty as usize]
+                    .check(val, &frame.env)
+                    .map_err(|msg| {
+                        format!("in binding '{}': {}", chunk.names[name as usize], msg)
+                    })?;
+            }
+            Instr::DefineFn(proto) => {
+                let proto = Rc::clone(&chunk.protos[proto as usize]);
+                let function = Function::compiled(proto, frame.env.clone());
+
+                self.stack.push(Val::Fn(function));
+            }
+            Instr::Declare(decl) => {
+                chunk.decls[decl as usize].eval(&mut frame.env)?;
+            }
+            Instr::DefineImpl(def) => {
+                let def = &chunk.impls[def as usize];
+
+                def.def.define(&mut frame.env, |idx, env| {
+                    Function::compiled(Rc::clone(&def.methods[idx]), env)
+                })?;
+            }
+            Instr::EnterScope => {
+                let child = frame.env.create_child();
+                frame.scopes.push(mem::replace(&mut frame.env, child));
+            }
+            Instr::ExitScope => frame.env = frame.scopes.pop().unwrap(),
+            Instr::Binary(op) => {
+                let rhs = self.stack.pop().unwrap();
+                let lhs = self.stack.pop().unwrap();
+
+                self.stack.push(op.eval(lhs, rhs, &frame.env, span)?);
+            }
+            Instr::Unary(op) => {
+                let val = self.stack.pop().unwrap();
+                self.stack.push(op.eval(val)?);
+            }
+            Instr::Jump(target) => frame.ip = target as usize,
+            Instr::JumpIfFalse(target) => match self.stack.pop().unwrap() {
+                Val::Bool(true) => {}
+                Val::Bool(false) => frame.ip = target as usize,
+                val => {
+                    return Err(format!(
+                        "expected Bool as condition of if, but found {}",
+                        val.type_name()
+                    )
+                    .into())
+                }
+            },
+            Instr::CheckCallable => match self.stack.last().unwrap() {
+                Val::Fn(_) => {}
+                val => {
+                    return Err(format!("cannot call a value of type {}", val.type_name()).into())
+                }
+            },
+            Instr::Call(args) => self.call(args as usize, span)?,
+            Instr::TailCall(args) => self.tail_call(args as usize, span)?,
+            Instr::LookupMethod(name) => {
+                let receiver = self.stack.pop().unwrap();
+                let name = chunk.names[name as usize].clone();
+
+                let method = frame
+                    .env
+                    .get_method(receiver.type_name(), name.clone())
+                    .ok_or_else(|| {
+                        format!(
+                            "no method named '{}' found for a value of type {}",
+                            name,
+                            receiver.type_name()
+                        )
+                    })?;
+
+                self.stack.push(Val::Fn(method));
+                self.stack.push(receiver);
+            }
+            Instr::List(len) => {
+                let elements = self.stack.split_off(self.stack.len() - len as usize);
+                self.stack
+                    .push(limits::allocated(Val::List(elements.into()))?);
+            }
+            Instr::Index => {
+                let index = self.stack.pop().unwrap();
+                let base = self.stack.pop().unwrap();
+                self.stack.push(Expr::eval_index(base, index)?);
+            }
+            Instr::Field(name) => {
+                let base = self.stack.pop().unwrap();
+                let val = Expr::eval_field(base, chunk.names[name as usize].clone())?;
+                self.stack.push(val);
+            }
+            Instr::CheckField { lit, field } => {
+                let lit = &chunk.struct_lits[lit as usize];
+                lit.check_field(&*lit.def(&frame.env)?, field as usize)?;
+            }
+            Instr::BuildStruct(lit) => {
+                let lit = &chunk.struct_lits[lit as usize];
+                let def = lit.def(&frame.env)?;
+                let vals = self.stack.split_off(self.stack.len() - lit.fields.len());
+
+                self.stack.push(lit.build(&def, vals, &frame.env)?);
+            }
+            Instr::Try => {
+                let val = self.stack.pop().unwrap();
+                self.stack.push(Expr::eval_try(val)?);
+            }
+            Instr::Throw => return Err(Unwind::throw(self.stack.pop().unwrap())),
+            Instr::PushCatch(target) | Instr::PushFinally(target) => {
+                frame.handlers.push(Handler {
+                    catch: matches!(instr, Instr::PushCatch(_)),
+                    target: target as usize,
+                    stack_len: self.stack.len(),
+                    scopes_len: frame.scopes.len(),
+                    env: frame.env.clone(),
+                    pending_len: frame.pending.len(),
+                });
+            }
+            Instr::PopHandler => {
+                frame.handlers.pop();
+            }
+            Instr::PopFinally => {
+                frame.handlers.pop();
+                frame.pending.push(None);
+            }
+            Instr::Resume => {
+                if let Some(unwind) = frame.pending.pop().unwrap() {
+                    return Err(unwind);
+                }
+            }
+            Instr::Return => return self.ret(),
+        }
+
+        Ok(None)
+    }
+
+    /// Calls the function below the top `args` values on the stack. Compiled functions get a
+    /// frame of their own, while any others are called right away.
+    fn call(&mut self, args: usize, call_site: TextRange) -> Result<(), Unwind> {
+        let args = self.stack.split_off(self.stack.len() - args);
+        let Val::Fn(function) = self.stack.pop().unwrap() else {
+            unreachable!("called a value that isn't a function")
+        };
+
+        let Some((proto, env)) = function.as_compiled() else {
+            let ret = function.call(args, call_site)?;
+            self.stack.push(ret);
+            return Ok(());
+        };
+
+        let proto = Rc::clone(proto);
+        limits::enter_call()?;
+        let env = function
+            .enter(&proto.def, env, args)
+            .inspect_err(|_| limits::leave_call())?;
+
+        let call = Call {
+            function,
+            proto: Rc::clone(&proto),
+            env: env.clone(),
+            call_site,
+        };
+
+        self.frames.push(Frame::new(
+            Rc::clone(&proto.chunk),
+            env,
+            self.stack.len(),
+            Some(call),
+        ));
+
+        Ok(())
+    }
+
+    /// Makes a call like [`call`](Self::call), but a compiled function called from another one
+    /// takes the place of the current frame, as all that is left to do in it is to return the
+    /// value of the call.
+    fn tail_call(&mut self, args: usize, call_site: TextRange) -> Result<(), Unwind> {
+        let frame = self.frames.last().unwrap();
+        let compiled = matches!(
+            &self.stack[self.stack.len() - args - 1],
+            Val::Fn(function) if function.as_compiled().is_some()
+        );
+
+        if frame.call.is_none() || !compiled {
+            return self.call(args, call_site);
+        }
+
+        self.call(args, call_site)?;
+
+        let mut callee = self.frames.pop().unwrap();
+        let frame = self.frames.pop().unwrap();
+        let call = frame.call.unwrap();
+        limits::leave_call();
+
+        callee.stack_base = frame.stack_base;
+        callee.tail_calls = frame.tail_calls;
+        callee
+            .tail_calls
+            .push(call.function, call.env, call.call_site);
+
+        self.frames.push(callee);
+
+        Ok(())
+    }
+
+    fn ret(&mut self) -> Result<Option<Val>, Unwind> {
+        let val = self.stack.pop().unwrap();
+        let frame = self.frames.pop().unwrap();
+
+        match frame.call {
+            Some(call) => {
+                limits::leave_call();
+                let result =
+                    call.function
+                        .leave(&call.proto.def, Ok(val), &call.env, call.call_site);
+                let val = frame.tail_calls.leave(result, call.call_site)?;
+                self.stack.push(val);
+
+                Ok(None)
+            }
+            None => Ok(Some(val)),
+        }
+    }
+
+    /// Passes `unwind` on to the innermost handler that takes it, leaving frames on the way,
+    /// or out of the VM if there is none.
+    fn unwind(&mut self, mut unwind: Unwind) -> Result<(), Unwind> {
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+
+            while let Some(handler) = frame.handlers.pop() {
+                if handler.catch && !matches!(unwind, Unwind::Throw { .. }) {
+                    continue;
+                }
+
+                self.stack.truncate(handler.stack_len);
+                frame.scopes.truncate(handler.scopes_len);
+                frame.env = handler.env;
+                frame.pending.truncate(handler.pending_len);
+                frame.ip = handler.target;
+
+                match unwind {
+                    Unwind::Throw { val, .. } if handler.catch => self.stack.push(val),
+                    unwind => frame.pending.push(Some(unwind)),
+                }
+
+                return Ok(());
+            }
+
+            let frame = self.frames.pop().unwrap();
+            self.stack.truncate(frame.stack_base);
+
+            let Some(call) = frame.call else {
+                return Err(unwind);
+            };
+            limits::leave_call();
+
+            let result =
+                call.function
+                    .leave(&call.proto.def, Err(unwind), &call.env, call.call_site);
+
+            match frame.tail_calls.leave(result, call.call_site) {
+                // `?` returned from the function.
+                Ok(val) => {
+                    self.stack.push(val);
+                    return Ok(());
+                }
+                Err(outer) => unwind = outer,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{expr::Op, Backend};
+
+    fn eval(s: &str, backend: Backend) -> Result<Val, EvalError> {
+        crate::parse(s).eval_with(&mut Env::default(), backend)
+    }
+
+    /// Evaluates `s` with both backends, which have to agree on the value or error, including its
+    /// trace.
+    #[track_caller]
+    fn check(s: &str) -> Result<Val, EvalError> {
+        let tree_walker = eval(s, Backend::TreeWalker);
+        assert_eq!(eval(s, Backend::Vm), tree_walker, "input: {}", s);
+
+        tree_walker
+    }
+
+    #[test]
+    fn compile_operations() {
+        let chunk = compiler::compile(crate::parse("1 + 2 * 3").source_file().unwrap());
+
+        assert_eq!(
+            chunk.code,
+            [
+                Instr::Const(0),
+                Instr::Const(1),
+                Instr::Const(2),
+                Instr::Binary(Op::Mul),
+                Instr::Binary(Op::Add),
+                Instr::Return
+            ]
+        );
+        assert_eq!(
+            chunk.consts,
+            [Val::Number(1), Val::Number(2), Val::Number(3)]
+        );
+    }
+
+    #[test]
+    fn compile_nested_finally_once() {
+        let code_len = |levels| {
+            let s = format!(
+                "{}1{}",
+                "try { ".repeat(levels),
+                " } finally { 2 }".repeat(levels)
+            );
+            compiler::compile(crate::parse(&s).source_file().unwrap())
+                .code
+                .len()
+        };
+
+        assert_eq!(code_len(50) - code_len(49), code_len(2) - code_len(1));
+    }
+
+    #[test]
+    fn compile_tail_calls() {
+        let chunk = compiler::compile(
+            crate::parse("fn f(n) { if n == 0 { g(n) } else { 1 + g(n) } }")
+                .source_file()
+                .unwrap(),
+        );
+        let calls: Vec<_> = chunk.protos[0]
+            .chunk
+            .code
+            .iter()
+            .filter(|instr| matches!(instr, Instr::Call(_) | Instr::TailCall(_)))
+            .collect();
+
+        assert_eq!(calls, [&Instr::TailCall(1), &Instr::Call(1)]);
+    }
+
+    #[test]
+    fn compile_bindings_to_slots() {
+        let mut parse = crate::parse("let a = 1\n{ let b = a; { b + c } }");
+        let _ = parse.resolve(&Env::default());
+        let chunk = compiler::compile(parse.source_file().unwrap());
+
+        assert_eq!(
+            chunk.code[..10],
+            [
+                Instr::Const(0),
+                Instr::Store(0),
+                Instr::EnterScope,
+                Instr::LoadSlot { depth: 1, slot: 0 },
+                Instr::Store(1),
+                Instr::EnterScope,
+                Instr::LoadSlot { depth: 1, slot: 0 },
+                Instr::Load(2),
+                Instr::Binary(Op::Add),
+                Instr::ExitScope,
+            ]
+        );
+    }
+
+    #[test]
+    fn same_values() {
+        for s in [
+            "",
+            "1 + 2 * 3 - -4",
+            "!(1 < 2) == false",
+            "let a = 1\nlet b = { let a = a + 1; a * 10 }\n[a, b]",
+            "if 1 > 2 { 10 } else if true { 20 } else { 30 }",
+            "if false { 1; }",
+            "fn fib(n) { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }\nfib(15)",
+            "fn adder(n) { fn add(x) { x + n }; add }\nlet add2 = adder(2)\nadd2(3)",
+            "[1, 2, 3][2]",
+            "struct P<T> { x: T, y: Int }\nlet p = P { y: 2, x: \"a\" }\n[p.x, p]",
+            "struct P { x: Int }
+trait Add { fn add(self, other: P) -> P }
+impl Add for P { fn add(self, other) { P { x: self.x + other.x } } }
+trait Show { fn show(self) -> Str }
+impl Show for P { fn show(self) { \"P\" } }
+let p = P { x: 1 } + P { x: 2 }
+[p.x, p.show()]",
+            "fn first(list) { Some(list[0]) }
+fn f(a) { let x = ok(a)?; let y = first([x])?; Some(x + y) }
+[f(Ok(1)), f(Err(2))]",
+            "unwrap_or(None, 3)",
+            "fn f(n) -> Int { try { if n > 0 { throw \"positive\" } else { n } } catch e { 1 } finally { 2 } }
+[f(1), f(0)]",
+            "fn f() { try { try { throw 1 } finally { 2 } } catch e { e + 10 } }\nf()",
+            "let a = try { { let b = 1; throw b } } catch e { let c = e; c }\na",
+            "fn f() { try { None? } finally { 1 } }\nf()",
+            "try { try { 1 } finally { 2 } } catch e { e } finally { try { 3 } finally { 4 } }",
+            "try { try { throw 1 } finally { try { 2 } finally { 3 } } } catch e { e + 10 }",
+            "let a = 1\n{ fn f() { a }; let a = 2; f() }",
+            "{ fn f() { g() }; fn g() { 1 }; f() }",
+            "fn f(a, a) { a }\nf(1, 2)",
+            "fn f(x) { fn g() { { x } }; g() }\nf(3)",
+            "let e = 1\ntry { throw 2 } catch e { { let x = e; [x, e] } }",
+            "fn count(n, acc) { if n == 0 { acc } else { count(n - 1, acc + 1) } }\ncount(100000, 0)",
+            "fn even(n) -> Bool { if n == 0 { true } else { odd(n - 1) } }
+fn odd(n) -> Bool { if n == 0 { false } else { even(n - 1) } }
+even(10001)",
+            "trait Down { fn down(self, acc: Int) -> Int }
+impl Down for Int { fn down(self, acc) { if self == 0 { acc } else { (self - 1).down(acc + 2) } } }
+100000.down(0)",
+            "fn f(n) { if n > 0 { f(n - 1) } }\nf(3)",
+        ] {
+            assert!(check(s).is_ok(), "input: {}", s);
+        }
+    }
+
+    #[test]
+    fn shadowing_keeps_bindings_of_functions() {
+        assert_eq!(
+            check("{ let a = 1; fn f() { a }; let a = 2; [a, f()] }"),
+            Ok(Val::List(Rc::from([Val::Number(2), Val::Number(1)])))
+        );
+        assert_eq!(
+            check("fn g() { let a = 1; fn f() { a + 1 }; let a = \"s\"; f() }\ng()"),
+            Ok(Val::Number(2))
+        );
+    }
+
+    #[test]
+    fn same_errors() {
+        for s in [
+            "1 / 0",
+            "a",
+            "{ fn f() { b }\nlet b = 7\nf() }",
+            "let a: Int = true",
+            "fn f(a: Int) -> Bool { a }\nf(1)",
+            "fn f(a: Int) { a }\nfn g() { f(true) }\ng()",
+            "fn f(a) { a }\nf(1, 2)",
+            "1(throw \"not reached\")",
+            "if 1 { 2 }",
+            "[1][5]",
+            "1.x",
+            "struct P { x: Int }\nP { y: throw \"not reached\" }",
+            "struct P { x: Int }\nP { x: 1, x: 2 }",
+            "struct P { x: Int }\nP { }",
+            "struct P { x: Int }\nP { x: true }",
+            "1.show(throw \"not reached\")",
+            "trait T { fn t(x) -> Int }",
+            "impl Nope for Int { }",
+            "fn inner() { throw \"deep\" }\nfn outer() { 1 + inner() }\nouter()",
+            "fn f() { try { 1 / 0 } catch e { 0 } }\nf()",
+            "fn f() { try { throw 1 } catch e { throw e + 1 } finally { throw 10 } }\nf()",
+            "try { throw 1 } finally { 2 }",
+            "try { 1 } finally { try { throw 2 } finally { 3 } }",
+            "None?",
+            "1?",
+            "struct S { x: Int }
+trait Add { fn add(self, other: S) -> Int }
+impl Add for S { fn add(self, other) { 1 / 0 } }
+S { x: 1 } + S { x: 2 }",
+            "fn f(n) { if n == 0 { throw \"done\" } else { f(n - 1) } }\nf(5)",
+            "fn f(n) -> Int { if n == 0 { true } else { f(n - 1) } }\nf(3)",
+            "fn g(n: Int) { n }\nfn f(n) { if n == 0 { g(true) } else { f(n - 1) } }\nf(3)",
+            "fn f(n) { if n == 0 { ok(1) } else { f(n - 1) } }\nf(3)",
+            "fn f(n) { if n == 0 { f(1, 2) } else { f(n - 1) } }\nf(3)",
+        ] {
+            assert!(check(s).is_err(), "input: {}", s);
+        }
+    }
+
+    #[test]
+    fn bindings_end_up_in_env() {
+        for backend in [Backend::TreeWalker, Backend::Vm] {
+            let mut env = Env::default();
+
+            crate::parse(
+                "let a = 1\nfn f() { a + 1 }\n{ let b = 2 }\nlet c = try { throw 3 } catch e { e }",
+            )
+            .eval_with(&mut env, backend)
+            .unwrap();
+
+            assert_eq!(env.get_binding_value("a".into()), Ok(Val::Number(1)));
+            assert_eq!(env.get_binding_value("c".into()), Ok(Val::Number(3)));
+            assert!(env.get_binding_value("b".into()).is_err());
+            assert!(env.get_binding_value("e".into()).is_err());
+        }
+    }
+
+    #[test]
+    fn backends_call_each_others_functions() {
+        let mut env = Env::default();
+
+        crate::parse("fn double(n) { n * 2 }")
+            .eval_with(&mut env, Backend::TreeWalker)
+            .unwrap();
+        crate::parse("fn quadruple(n) { double(double(n)) }")
+            .eval_with(&mut env, Backend::Vm)
+            .unwrap();
+
+        assert_eq!(
+            crate::parse("quadruple(3)").eval_with(&mut env, Backend::TreeWalker),
+            Ok(Val::Number(12))
+        );
+    }
+}
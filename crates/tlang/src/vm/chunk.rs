@@ -0,0 +1,131 @@
+use std::rc::Rc;
+
+use super::Proto;
+use crate::{
+    env::Env,
+    expr::{Op, StructLit, UnaryOp},
+    impl_def::ImplDef,
+    struct_def::StructDef,
+    symbol::Symbol,
+    syntax::TextRange,
+    trait_def::TraitDef,
+    type_expr::TypeExpr,
+    val::Val,
+};
+
+/// The bytecode of a function body or of the top level of an input, together with everything its
+/// instructions refer to by index.
+#[derive(Debug, Default)]
+pub(crate) struct Chunk {
+    pub(crate) code: Vec<Instr>,
+    /// The span of the expression or statement each instruction was compiled from, which errors
+    /// caused by it are located at.
+    pub(crate) spans: Vec<TextRange>,
+    pub(crate) consts: Vec<Val>,
+    pub(crate) names: Vec<Symbol>,
+    pub(crate) types: Vec<TypeExpr>,
+    pub(crate) protos: Vec<Rc<Proto>>,
+    /// Struct and trait definitions, which are evaluated like the tree walker does.
+    pub(crate) decls: Vec<Decl>,
+    pub(crate) impls: Vec<Impl>,
+    pub(crate) struct_lits: Vec<StructLit>,
+}
+
+/// A struct or trait definition.
+#[derive(Debug)]
+pub(crate) enum Decl {
+    Struct(StructDef),
+    Trait(TraitDef),
+}
+
+impl Decl {
+    pub(crate) fn eval(&self, env: &mut Env) -> Result<(), String> {
+        match self {
+            Decl::Struct(struct_def) => {
+                struct_def.eval(env);
+                Ok(())
+            }
+            Decl::Trait(trait_def) => trait_def.eval(env),
+        }
+    }
+}
+
+/// An impl with its methods compiled.
+#[derive(Debug)]
+pub(crate) struct Impl {
+    pub(crate) def: ImplDef,
+    pub(crate) methods: Vec<Rc<Proto>>,
+}
+
+/// An instruction of the stack machine, which takes its operands from the top of the stack and
+/// pushes its result, if it has one. Jump targets are indices into [`Chunk::code`], and all other
+/// indices refer to the other tables of the chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Instr {
+    Const(u32),
+    Unit,
+    Pop,
+    /// Pushes the value in a slot of the environment `depth` levels up from the current one.
+    LoadSlot {
+        depth: u32,
+        slot: u32,
+    },
+    /// Pushes the built-in binding with the name, for bindings the resolver found no slot for.
+    Load(u32),
+    /// Pops the value and binds it to the name in the current environment.
+    Store(u32),
+    /// Checks the value on top of the stack against the type annotation of a binding, leaving it
+    /// on the stack.
+    CheckBinding {
+        name: u32,
+        ty: u32,
+    },
+    /// Pushes the function, capturing the current environment.
+    DefineFn(u32),
+    Declare(u32),
+    DefineImpl(u32),
+    /// Makes a child of the current environment the current one, until the matching `ExitScope`.
+    EnterScope,
+    ExitScope,
+    Binary(Op),
+    Unary(UnaryOp),
+    Jump(u32),
+    /// Pops the condition of an `if`, jumping if it is `false`.
+    JumpIfFalse(u32),
+    /// Checks that the value on top of the stack can be called, before the arguments are
+    /// evaluated.
+    CheckCallable,
+    /// Pops the arguments and the function below them, and calls it.
+    Call(u32),
+    /// Like `Call`, for a call the function being run returns the value of right away.
+    TailCall(u32),
+    /// Pops the receiver and pushes the method with the name for its type, followed by the
+    /// receiver again as the first argument.
+    LookupMethod(u32),
+    List(u32),
+    Index,
+    Field(u32),
+    /// Checks a field of a struct literal before its value is evaluated.
+    CheckField {
+        lit: u32,
+        field: u32,
+    },
+    /// Pops the values of all fields of a struct literal and builds the instance.
+    BuildStruct(u32),
+    Try,
+    Throw,
+    /// Starts a `try` body, jumping to the `catch` clause with the thrown value on the stack if
+    /// a value is thrown before the matching `PopHandler`.
+    PushCatch(u32),
+    /// Like `PushCatch`, but for any unwinding, which is resumed after running the `finally`
+    /// block at the target.
+    PushFinally(u32),
+    PopHandler,
+    /// Pops the handler of a `PushFinally` whose `try` body completed, before running the
+    /// `finally` block at its target.
+    PopFinally,
+    /// Ends a `finally` block, resuming the unwinding it interrupted if there is any.
+    Resume,
+    /// Returns the value on top of the stack from the chunk.
+    Return,
+}
@@ -0,0 +1,375 @@
+use std::{collections::HashMap, rc::Rc};
+
+use super::{
+    chunk::{Chunk, Decl, Impl, Instr},
+    Proto,
+};
+use crate::{
+    ast::{Arena, ExprId, StmtId},
+    binding_def::BindingDef,
+    expr::{Block, ExprKind, Number, Slot, TryCatch},
+    fn_def::FnDef,
+    source_file::SourceFile,
+    stmt::Stmt,
+    symbol::Symbol,
+    syntax::TextRange,
+    val::Val,
+};
+
+/// Compiles the top level of an input, which is evaluated directly in the environment it is run
+/// in. Binding usages are loaded from the slots the [resolver](crate::resolve) found for them.
+pub(crate) fn compile(source_file: &SourceFile) -> Chunk {
+    let mut compiler = Compiler::new(source_file.arena());
+
+    compiler.stmts(&source_file.stmts, TextRange::default(), false);
+    compiler.emit(Instr::Return, TextRange::default());
+
+    compiler.chunk
+}
+
+struct Compiler<'a> {
+    arena: &'a Arena,
+    chunk: Chunk,
+    /// The index of each name in [`Chunk::names`].
+    names: HashMap<Symbol, u32>,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(arena: &'a Arena) -> Self {
+        Self {
+            arena,
+            chunk: Chunk::default(),
+            names: HashMap::new(),
+        }
+    }
+
+    fn emit(&mut self, instr: Instr, span: TextRange) -> usize {
+        self.chunk.code.push(instr);
+        self.chunk.spans.push(span);
+        self.chunk.code.len() - 1
+    }
+
+    /// Makes the jump or handler at `at` target the next instruction.
+    fn patch(&mut self, at: usize) {
+        let target = index(self.chunk.code.len());
+
+        match &mut self.chunk.code[at] {
+            Instr::Jump(to)
+            | Instr::JumpIfFalse(to)
+            | Instr::PushCatch(to)
+            | Instr::PushFinally(to) => *to = target,
+            instr => unreachable!("cannot patch {:?}", instr),
+        }
+    }
+
+    fn name(&mut self, name: Symbol) -> u32 {
+        if let Some(idx) = self.names.get(&name) {
+            return *idx;
+        }
+
+        let idx = push(&mut self.chunk.names, name.clone());
+        self.names.insert(name, idx);
+        idx
+    }
+
+    /// Leaves the value of the last statement on the stack, or `Unit` if there are none. `tail` is
+    /// set if the statements end the body of a function, as in [`expr_at`](Self::expr_at).
+    fn stmts(&mut self, stmts: &[StmtId], span: TextRange, tail: bool) {
+        match stmts.split_last() {
+            Some((&last, init)) => {
+                for &stmt in init {
+                    self.stmt(stmt, false);
+                }
+
+                match self.arena[last] {
+                    Stmt::Expr(expr) => self.expr_at(expr, tail),
+                    _ => self.stmt(last, true),
+                }
+            }
+            None => {
+                self.emit(Instr::Unit, span);
+            }
+        }
+    }
+
+    /// Leaves the value of `stmt` on the stack if `keep` is set.
+    fn stmt(&mut self, stmt: StmtId, keep: bool) {
+        let span = self.arena[stmt].span(self.arena);
+
+        match &self.arena[stmt] {
+            Stmt::BindingDef(binding_def) => self.binding_def(binding_def),
+            Stmt::FnDef(fn_def) => {
+                let proto = function(self.arena, fn_def);
+                let proto = push(&mut self.chunk.protos, proto);
+                self.emit(Instr::DefineFn(proto), span);
+                self.store(fn_def.name.clone(), span);
+            }
+            Stmt::StructDef(struct_def) => {
+                let decl = push(&mut self.chunk.decls, Decl::Struct(struct_def.clone()));
+                self.emit(Instr::Declare(decl), span);
+            }
+            Stmt::TraitDef(trait_def) => {
+                let decl = push(&mut self.chunk.decls, Decl::Trait(trait_def.clone()));
+                self.emit(Instr::Declare(decl), span);
+            }
+            Stmt::ImplDef(impl_def) => {
+                let methods = impl_def
+                    .methods
+                    .iter()
+                    .map(|method| function(self.arena, method))
+                    .collect();
+                let def = push(
+                    &mut self.chunk.impls,
+                    Impl {
+                        def: impl_def.clone(),
+                        methods,
+                    },
+                );
+                self.emit(Instr::DefineImpl(def), span);
+            }
+            &Stmt::Expr(expr) => {
+                self.expr(expr);
+
+                if !keep {
+                    self.emit(Instr::Pop, span);
+                }
+
+                return;
+            }
+            &Stmt::Semi(expr) => {
+                self.expr(expr);
+                self.emit(Instr::Pop, span);
+            }
+        }
+
+        if keep {
+            self.emit(Instr::Unit, span);
+        }
+    }
+
+    fn binding_def(&mut self, binding_def: &BindingDef) {
+        self.expr(binding_def.val);
+
+        let name = self.name(binding_def.name.clone());
+
+        if let Some(ty) = &binding_def.ty {
+            let ty = push(&mut self.chunk.types, ty.clone());
+            self.emit(Instr::CheckBinding { name, ty }, binding_def.span);
+        }
+
+        self.store(binding_def.name.clone(), binding_def.span);
+    }
+
+    /// Pops the value on top of the stack and binds it to `name` in the current environment.
+    fn store(&mut self, name: Symbol, span: TextRange) {
+        let name = self.name(name);
+        self.emit(Instr::Store(name), span);
+    }
+
+    /// Like the tree walker, only blocks with statements get an environment of their own.
+    fn block(&mut self, block: &Block, span: TextRange) {
+        self.block_at(block, span, false);
+    }
+
+    fn block_at(&mut self, block: &Block, span: TextRange, tail: bool) {
+        if block.stmts.is_empty() {
+            self.emit(Instr::Unit, span);
+            return;
+        }
+
+        self.emit(Instr::EnterScope, span);
+        self.stmts(&block.stmts, span, tail);
+        self.emit(Instr::ExitScope, span);
+    }
+
+    fn exprs(&mut self, exprs: &[ExprId]) -> u32 {
+        for &expr in exprs {
+            self.expr(expr);
+        }
+
+        index(exprs.len())
+    }
+
+    fn expr(&mut self, expr: ExprId) {
+        self.expr_at(expr, false);
+    }
+
+    /// `tail` is set if the function being compiled returns the value of `expr` right away, in
+    /// which case the calls it ends with are tail calls, like with the tree walker.
+    fn expr_at(&mut self, expr: ExprId, tail: bool) {
+        let expr = &self.arena[expr];
+        let span = expr.span;
+        let call_instr = if tail { Instr::TailCall } else { Instr::Call };
+
+        match &expr.kind {
+            ExprKind::Number(Number(n)) => self.constant(Val::Number(*n), span),
+            ExprKind::Bool(b) => self.constant(Val::Bool(*b), span),
+            ExprKind::Str(s) => self.constant(Val::Str(s.clone()), span),
+            ExprKind::Operation { lhs, rhs, op } => {
+                self.expr(*lhs);
+                self.expr(*rhs);
+                self.emit(Instr::Binary(*op), span);
+            }
+            ExprKind::Unary { op, expr } => {
+                self.expr(*expr);
+                self.emit(Instr::Unary(*op), span);
+            }
+            ExprKind::BindingUsage(binding_usage) => {
+                let instr = match binding_usage.slot {
+                    Some(Slot { depth, index: slot }) => Instr::LoadSlot {
+                        depth: index(depth),
+                        slot: index(slot),
+                    },
+                    None => Instr::Load(self.name(binding_usage.name.clone())),
+                };
+                self.emit(instr, span);
+            }
+            ExprKind::Block(block) => self.block_at(block, span, tail),
+            ExprKind::If(if_expr) => {
+                self.expr(if_expr.condition);
+                let jump_to_else = self.emit(Instr::JumpIfFalse(0), span);
+
+                let tail = tail && if_expr.else_branch.is_some();
+                self.block_at(&if_expr.then_branch, span, tail);
+
+                match if_expr.else_branch {
+                    Some(else_branch) => {
+                        let jump_to_end = self.emit(Instr::Jump(0), span);
+                        self.patch(jump_to_else);
+                        self.expr_at(else_branch, tail);
+                        self.patch(jump_to_end);
+                    }
+                    // Both ways end up with `Unit`.
+                    None => {
+                        self.emit(Instr::Pop, span);
+                        self.patch(jump_to_else);
+                        self.emit(Instr::Unit, span);
+                    }
+                }
+            }
+            ExprKind::Call(call) => {
+                self.expr(call.callee);
+                self.emit(Instr::CheckCallable, span);
+                let args = self.exprs(&call.args);
+                self.emit(call_instr(args), span);
+            }
+            ExprKind::List(elements) => {
+                let len = self.exprs(elements);
+                self.emit(Instr::List(len), span);
+            }
+            ExprKind::Index { base, index } => {
+                self.expr(*base);
+                self.expr(*index);
+                self.emit(Instr::Index, span);
+            }
+            ExprKind::Field { base, name } => {
+                self.expr(*base);
+                let name = self.name(name.clone());
+                self.emit(Instr::Field(name), span);
+            }
+            ExprKind::StructLit(struct_lit) => {
+                let lit = push(&mut self.chunk.struct_lits, struct_lit.clone());
+
+                for (field, field_init) in struct_lit.fields.iter().enumerate() {
+                    let field = index(field);
+                    self.emit(Instr::CheckField { lit, field }, span);
+                    self.expr(field_init.val);
+                }
+
+                self.emit(Instr::BuildStruct(lit), span);
+            }
+            ExprKind::MethodCall(method_call) => {
+                self.expr(method_call.receiver);
+                let name = self.name(method_call.name.clone());
+                self.emit(Instr::LookupMethod(name), span);
+                let args = self.exprs(&method_call.args);
+                self.emit(call_instr(args + 1), span);
+            }
+            ExprKind::Try(expr) => {
+                self.expr(*expr);
+                self.emit(Instr::Try, span);
+            }
+            ExprKind::Throw(expr) => {
+                self.expr(*expr);
+                self.emit(Instr::Throw, span);
+            }
+            ExprKind::TryCatch(try_catch) => self.try_catch(try_catch, span),
+        }
+    }
+
+    fn constant(&mut self, val: Val, span: TextRange) {
+        let idx = push(&mut self.chunk.consts, val);
+        self.emit(Instr::Const(idx), span);
+    }
+
+    /// The `finally` block is run both when the `try` expression completes and when it is unwound
+    /// through, which `Resume` tells apart afterwards.
+    fn try_catch(&mut self, try_catch: &TryCatch, span: TextRange) {
+        let finally_handler = try_catch
+            .finally
+            .as_ref()
+            .map(|_| self.emit(Instr::PushFinally(0), span));
+        let catch_handler = try_catch
+            .catch
+            .as_ref()
+            .map(|_| self.emit(Instr::PushCatch(0), span));
+
+        self.block(&try_catch.body, span);
+
+        if let (Some(handler), Some(catch)) = (catch_handler, &try_catch.catch) {
+            self.emit(Instr::PopHandler, span);
+            let jump_to_end = self.emit(Instr::Jump(0), span);
+
+            // The thrown value is on the stack.
+            self.patch(handler);
+            self.emit(Instr::EnterScope, catch.span);
+            self.store(catch.name.clone(), catch.span);
+            self.block(&catch.body, catch.span);
+            self.emit(Instr::ExitScope, catch.span);
+
+            self.patch(jump_to_end);
+        }
+
+        if let (Some(handler), Some(finally)) = (finally_handler, &try_catch.finally) {
+            self.emit(Instr::PopFinally, span);
+
+            // Unwinding leaves the stack as it was before the `try` expression, and completing
+            // the body leaves its value on top.
+            self.patch(handler);
+            self.block(finally, span);
+            self.emit(Instr::Pop, span);
+            self.emit(Instr::Resume, span);
+        }
+    }
+}
+
+/// Compiles the body of `fn_def`, which is run in the environment holding its arguments.
+fn function(arena: &Arena, fn_def: &FnDef) -> Rc<Proto> {
+    let mut compiler = Compiler::new(arena);
+
+    compiler.block_at(&fn_def.body, fn_def.span, true);
+    compiler.emit(Instr::Return, fn_def.span);
+
+    Rc::new(Proto {
+        // The body is only needed as bytecode.
+        def: FnDef {
+            name: fn_def.name.clone(),
+            type_params: fn_def.type_params.clone(),
+            params: fn_def.params.clone(),
+            ret_ty: fn_def.ret_ty.clone(),
+            body: Block { stmts: Vec::new() },
+            span: fn_def.span,
+        },
+        chunk: Rc::new(compiler.chunk),
+    })
+}
+
+/// Adds `item` to `items`, returning its index.
+fn push<T>(items: &mut Vec<T>, item: T) -> u32 {
+    items.push(item);
+    index(items.len() - 1)
+}
+
+fn index(idx: usize) -> u32 {
+    u32::try_from(idx).expect("too many items in a chunk")
+}